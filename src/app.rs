@@ -10,7 +10,6 @@ use crate::{
 
 pub struct App {
     state: State,
-    app_handler: AppHandler,
     event_handler: EditorEventHandler,
     editor_state: EditorState,
 }
@@ -18,7 +17,7 @@ pub struct App {
 impl App {
     pub fn new() -> color_eyre::Result<Self> {
         let app_handler = AppHandler::new()?;
-        let state = State::new()?;
+        let mut state = State::new()?;
         let event_handler = EditorEventHandler::default();
         let mut editor_state = EditorState::default();
         editor_state.lines = Lines::from(
@@ -44,10 +43,10 @@ impl App {
         );
 
         // app_handler.load(&mut state);
+        app_handler.load_lint_config(&mut state)?;
 
         Ok(Self {
             state,
-            app_handler,
             event_handler,
             editor_state,
         })
@@ -56,13 +55,18 @@ impl App {
     pub fn run<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> color_eyre::Result<()> {
         loop {
             terminal.draw(|frame| {
-                ui::draw(frame, &self.state, &mut self.editor_state);
+                ui::draw(frame, &mut self.state, &mut self.editor_state);
             })?;
 
+            if let Some(interval) = self.state.playback.interval()
+                && !event::poll(interval)?
+            {
+                InputHandler::handle_tick(&mut self.state, &mut self.editor_state);
+                continue;
+            }
             let event = event::read()?;
 
-            if let Event::Key(key) = event
-            {
+            if let Event::Key(key) = event {
                 if key.kind != KeyEventKind::Press {
                     continue;
                 }
@@ -71,7 +75,6 @@ impl App {
                     &mut self.state,
                     &mut self.editor_state,
                     key,
-                    &self.app_handler,
                     &mut self.event_handler,
                 )?;
             };
@@ -0,0 +1,266 @@
+use std::{fs, path::Path};
+
+use color_eyre::eyre::{Result, WrapErr, eyre};
+
+use crate::models::{
+    analysis::metrics,
+    complexity::{self, Input, Measurement},
+    coverage::Coverage,
+    interpreter::{limits::Limits, vm::Vm},
+    nodes::{program::Program, test_case::TestCase},
+    stdlib::BUILTINS,
+    testing,
+    trace::Trace,
+    transpile::{self, Language},
+};
+
+const USAGE: &str = "usage: pseugo [--max-steps <n>] [--max-depth <n>] [run <file> | metrics <file> | trace <file> [--csv] [function [args...]] | test <file> [--lcov <out>] | complexity <file> <function> [--csv] [--sizes <n,...>] [inputs...] | transpile <file> <language> | builtins]";
+
+/// Runs a command given on the command line instead of starting the editor.
+pub fn run(args: &[String]) -> Result<()> {
+    let (limits, args) = parse_limits(args)?;
+    match args.as_slice() {
+        [command, path] if command == "run" => run_program(path, &limits),
+        [command, path] if command == "metrics" => print_metrics(path),
+        [command, path, rest @ ..] if command == "trace" => print_trace(path, rest, &limits),
+        [command, path, rest @ ..] if command == "test" => run_tests(path, rest, &limits),
+        [command, path, function, rest @ ..] if command == "complexity" => {
+            print_complexity(path, function, rest, &limits)
+        }
+        [command, path, language] if command == "transpile" => print_transpiled(path, language),
+        [command] if command == "builtins" => {
+            print_builtins();
+            Ok(())
+        }
+        _ => Err(eyre!(USAGE)),
+    }
+}
+
+/// Takes `--max-steps` and `--max-depth` out of the arguments wherever they
+/// appear.
+fn parse_limits(args: &[String]) -> Result<(Limits, Vec<String>)> {
+    let mut limits = Limits::default();
+    let mut rest = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let limit = match arg.as_str() {
+            "--max-steps" => &mut limits.max_steps,
+            "--max-depth" => &mut limits.max_depth,
+            _ => {
+                rest.push(arg.clone());
+                continue;
+            }
+        };
+        let value = args.next().ok_or_else(|| eyre!("{arg} needs a number"))?;
+        *limit = value
+            .parse()
+            .wrap_err_with(|| format!("{arg} needs a number but got '{value}'"))?;
+    }
+    Ok((limits, rest))
+}
+
+fn read_program(path: &str) -> Result<Program> {
+    let text = fs::read_to_string(path).wrap_err_with(|| format!("Failed to read {path}"))?;
+    Program::new(&text)
+}
+
+fn run_program(path: &str, limits: &Limits) -> Result<()> {
+    let program = read_program(path)?;
+    let mut vm = Vm::new(&program);
+    vm.limits = limits.clone();
+    let result = vm.run();
+    for line in &vm.output {
+        println!("{line}");
+    }
+    result.map(|_| ())
+}
+
+/// Runs the tests declared in the file and in a sidecar file next to it with
+/// the extension `.tests`, and fails if any of them does not pass. Prints how
+/// many statements the tests ran, and with `--lcov` writes them as lcov.
+fn run_tests(path: &str, args: &[String], limits: &Limits) -> Result<()> {
+    let lcov = match args {
+        [] => None,
+        [flag, out] if flag == "--lcov" => Some(out),
+        _ => return Err(eyre!(USAGE)),
+    };
+    let program = read_program(path)?;
+    let mut tests = Vec::new();
+    let sidecar = Path::new(path).with_extension("tests");
+    if sidecar.exists() {
+        let text = fs::read_to_string(&sidecar)
+            .wrap_err_with(|| format!("Failed to read {}", sidecar.display()))?;
+        tests = TestCase::parse_all(&text)
+            .wrap_err_with(|| format!("Failed to parse {}", sidecar.display()))?;
+    }
+
+    let mut coverage = Coverage::new(&program);
+    let results = testing::run(&program, &program.tests, limits, &mut coverage);
+    let sidecar_results = testing::run(&program, &tests, limits, &mut coverage);
+    let mut failed = 0;
+    for (file, results) in [
+        (Path::new(path), &results),
+        (sidecar.as_path(), &sidecar_results),
+    ] {
+        for result in results {
+            let status = if result.passed() { "PASS" } else { "FAIL" };
+            println!(
+                "{status} {}:{} {}",
+                file.display(),
+                result.line + 1,
+                result.name
+            );
+            for detail in result.details() {
+                println!("    {detail}");
+            }
+            failed += usize::from(!result.passed());
+        }
+    }
+
+    let total = results.len() + sidecar_results.len();
+    println!("{} passed, {failed} failed", total - failed);
+    println!(
+        "{} of {} statement lines covered",
+        coverage.covered(),
+        coverage.hits.len()
+    );
+    if let Some(out) = lcov {
+        fs::write(out, coverage.to_lcov(path))
+            .wrap_err_with(|| format!("Failed to write {out}"))?;
+    }
+    if failed > 0 {
+        return Err(eyre!("{failed} test(s) failed"));
+    }
+    Ok(())
+}
+
+/// Prints the program translated into `language`.
+fn print_transpiled(path: &str, language: &str) -> Result<()> {
+    let Some(language) = Language::from_name(language) else {
+        let names: Vec<&str> = Language::ALL
+            .iter()
+            .map(|language| language.name())
+            .collect();
+        return Err(eyre!(
+            "unknown language '{language}', expected one of: {}",
+            names.join(", ")
+        ));
+    };
+    let program = read_program(path)?;
+    print!("{}", transpile::transpile(&program, language)?);
+    Ok(())
+}
+
+/// Prints the trace table of `start`, or of a function called with the
+/// given arguments, as Markdown or with `--csv` as CSV.
+fn print_trace(path: &str, args: &[String], limits: &Limits) -> Result<()> {
+    let program = read_program(path)?;
+    let (csv, args) = match args.split_first() {
+        Some((flag, rest)) if flag == "--csv" => (true, rest),
+        Some((flag, rest)) if flag == "--markdown" => (false, rest),
+        _ => (false, args),
+    };
+    let function = args
+        .split_first()
+        .map(|(name, inputs)| (name.as_str(), inputs));
+
+    let trace = Trace::record(&program, function, limits)?;
+    if csv {
+        print!("{}", trace.to_csv());
+        if let Some(truncation) = trace.truncation() {
+            eprintln!("{truncation}");
+        }
+    } else {
+        print!("{}", trace.to_markdown());
+    }
+    match trace.error {
+        Some(error) => Err(eyre!(error)),
+        None => Ok(()),
+    }
+}
+
+/// Counts the operations of `function` over inputs of growing size and
+/// prints them with the best-fitting growth class, or with `--csv` as CSV.
+/// Each remaining argument describes one parameter: `random`, `sorted`,
+/// `reversed`, `n` for the size, or a fixed value.
+fn print_complexity(path: &str, function: &str, args: &[String], limits: &Limits) -> Result<()> {
+    let program = read_program(path)?;
+    let mut csv = false;
+    let mut sizes = complexity::DEFAULT_SIZES.to_vec();
+    let mut inputs = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--csv" => csv = true,
+            "--sizes" => {
+                let list = args.next().ok_or_else(|| eyre!("--sizes needs a list"))?;
+                sizes = list
+                    .split(',')
+                    .map(|size| size.trim().parse())
+                    .collect::<Result<_, _>>()
+                    .wrap_err_with(|| format!("--sizes needs numbers but got '{list}'"))?;
+            }
+            input => inputs.push(Input::parse(input)),
+        }
+    }
+    if inputs.is_empty() {
+        let params = program
+            .functions
+            .iter()
+            .find(|candidate| candidate.name == function)
+            .map_or(0, |function| function.params.len());
+        inputs = Input::defaults(params);
+    }
+
+    let measurement = Measurement::record(&program, function, &inputs, &sizes, limits)?;
+    if csv {
+        print!("{}", measurement.to_csv());
+    } else {
+        println!(
+            "{:>8} {:>12} {:>12} {:>14} {:>12}",
+            "n", "comparisons", "assignments", "array accesses", "total"
+        );
+        for sample in &measurement.samples {
+            println!(
+                "{:>8} {:>12} {:>12} {:>14} {:>12}",
+                sample.size,
+                sample.comparisons,
+                sample.assignments,
+                sample.array_accesses,
+                sample.total()
+            );
+        }
+        if let Some((growth, factor)) = measurement.fit {
+            println!("best fit: {factor:.2} · {growth}");
+        }
+    }
+    match measurement.error {
+        Some(error) => Err(eyre!(error)),
+        None => Ok(()),
+    }
+}
+
+fn print_metrics(path: &str) -> Result<()> {
+    let program = read_program(path)?;
+    println!(
+        "{:<24} {:>10} {:>7} {:>10} {:>9}",
+        "routine", "cyclomatic", "nesting", "statements", "variables"
+    );
+    for metrics in metrics::measure(&program) {
+        println!(
+            "{:<24} {:>10} {:>7} {:>10} {:>9}",
+            metrics.name,
+            metrics.cyclomatic,
+            metrics.max_nesting,
+            metrics.statements,
+            metrics.variables
+        );
+    }
+    Ok(())
+}
+
+fn print_builtins() {
+    for builtin in BUILTINS {
+        println!("{:<40} {}", builtin.to_string(), builtin.description);
+    }
+}
@@ -1,31 +1,30 @@
-use crate::models::state::State;
+use crate::models::{lint::config::LintConfig, state::State};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub struct AppHandler {
-    config_path: PathBuf,
+    lint_config_path: PathBuf,
 }
 
 impl AppHandler {
     pub fn new() -> color_eyre::Result<Self> {
-        let config_path = Self::get_config_path()?;
+        let lint_config_path = Self::get_config_path("lint.json")?;
 
-        if let Some(parent) = config_path.parent() {
+        if let Some(parent) = lint_config_path.parent() {
             fs::create_dir_all(parent)?;
         }
 
-        Ok(Self { config_path })
+        Ok(Self { lint_config_path })
     }
 
-    fn get_config_path() -> color_eyre::Result<PathBuf> {
+    fn get_config_path(filename: &str) -> color_eyre::Result<PathBuf> {
         let app_name = "pseugo";
-        let filename = "state.json";
 
         #[cfg(target_os = "windows")]
         {
             // Windows: %APPDATA%\todo\state.json
-            let appdata =
-                std::env::var("APPDATA").map_err(|_| color_eyre::eyre::eyre!("APPDATA environment variable not found"))?;
+            let appdata = std::env::var("APPDATA")
+                .map_err(|_| color_eyre::eyre::eyre!("APPDATA environment variable not found"))?;
             Ok(PathBuf::from(appdata).join(app_name).join(filename))
         }
 
@@ -59,20 +58,32 @@ impl AppHandler {
         }
     }
 
-    pub fn save(&self, state: &State) -> color_eyre::Result<()> {
-        let json = serde_json::to_string_pretty(state)?;
-        fs::write(&self.config_path, json)?;
-        Ok(())
-    }
-
-    pub fn load(&self, state: &mut State) -> color_eyre::Result<()> {
-        if self.config_path.exists() {
-            let json = fs::read_to_string(&self.config_path)?;
-            let loaded_state: State = serde_json::from_str(&json)?;
-            *state = loaded_state;
+    /// Reads the lint settings, writing the defaults out when there are
+    /// none. A file that can't be read keeps the defaults and says why in
+    /// the diagnostics panel, rather than keeping the editor from starting.
+    pub fn load_lint_config(&self, state: &mut State) -> color_eyre::Result<()> {
+        if self.lint_config_path.exists() {
+            let config = fs::read_to_string(&self.lint_config_path)
+                .map_err(color_eyre::Report::from)
+                .and_then(|json| Ok(serde_json::from_str(&json)?));
+            state.lint_config = match config {
+                Ok(config) => config,
+                Err(e) => {
+                    Self::report(state, &self.lint_config_path, e);
+                    LintConfig::default()
+                }
+            };
         } else {
-            self.save(state)?;
+            let json = serde_json::to_string_pretty(&LintConfig::default())?;
+            fs::write(&self.lint_config_path, json)?;
         }
         Ok(())
     }
+
+    /// Records a settings file that was ignored, for the diagnostics panel.
+    fn report(state: &mut State, path: &Path, error: color_eyre::Report) {
+        state
+            .settings_errors
+            .push(format!("ignored {}: {error}", path.display()));
+    }
 }
@@ -1,11 +1,22 @@
-use edtui::{EditorEventHandler, EditorMode, EditorState};
-use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use edtui::{EditorEventHandler, EditorMode, EditorState, Index2, Lines};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
 
-use crate::{
-    handlers::app::AppHandler,
-    models::state::{AppState, State},
+use std::{fs, time::Duration};
+
+use crate::models::{
+    complexity::{self, Input, Measurement},
+    coverage::Coverage,
+    debugger::Session,
+    interpreter::{limits::Limits, value::Value},
+    state::{AppState, Panel, State},
+    testing,
+    trace::Trace,
+    transpile::{self, Language},
 };
 
+/// Columns a diagram moves by for each press of `h` or `l`.
+const PAN_STEP: usize = 4;
+
 pub struct InputHandler;
 
 impl InputHandler {
@@ -13,49 +24,439 @@ impl InputHandler {
         state: &mut State,
         editor_state: &mut EditorState,
         key: KeyEvent,
-        app_handler: &AppHandler,
         event_handler: &mut EditorEventHandler,
     ) -> color_eyre::Result<()> {
         match &state.app_state {
             AppState::LeftSelected => {
-                if editor_state.mode == EditorMode::Normal && Self::handle_normal_state(key, state)
+                if editor_state.mode == EditorMode::Normal
+                    && Self::handle_normal_state(key, state, editor_state)
                 {
                     return Ok(());
                 }
                 event_handler.on_key_event(key, editor_state);
             }
-            AppState::RightSelected => Self::handle_default_inputs(key, state),
+            AppState::RightSelected => Self::handle_default_inputs(key, state, editor_state),
+            AppState::Debugging => Self::handle_debugger_inputs(key, state, editor_state),
         };
         Ok(())
     }
 
-    fn handle_default_inputs(key: KeyEvent, state: &mut State) {
+    fn handle_default_inputs(key: KeyEvent, state: &mut State, editor_state: &mut EditorState) {
         match (key.modifiers, key.code) {
             (KeyModifiers::NONE, KeyCode::Char('q')) => state.should_quit = true,
-            (KeyModifiers::NONE, KeyCode::Tab) => {
-                state.app_state = match state.app_state {
-                    AppState::LeftSelected => AppState::RightSelected,
-                    AppState::RightSelected => AppState::LeftSelected,
-                }
+            (KeyModifiers::NONE, KeyCode::Tab) => state.app_state = AppState::LeftSelected,
+            (KeyModifiers::NONE, KeyCode::F(5)) => Self::start_debugging(state, editor_state),
+            (KeyModifiers::NONE, KeyCode::F(6)) => Self::run_tests(state, editor_state),
+            (KeyModifiers::NONE, KeyCode::F(7)) => state.show_coverage = !state.show_coverage,
+            (KeyModifiers::NONE, KeyCode::F(8)) => Self::export_coverage(state),
+            (KeyModifiers::NONE, KeyCode::Left | KeyCode::Char('h'))
+                if matches!(state.panel, Panel::Flowchart | Panel::Structogram) =>
+            {
+                state.diagram_pan = state.diagram_pan.saturating_sub(PAN_STEP)
+            }
+            (KeyModifiers::NONE, KeyCode::Right | KeyCode::Char('l'))
+                if matches!(state.panel, Panel::Flowchart | Panel::Structogram) =>
+            {
+                state.diagram_pan += PAN_STEP
+            }
+            (KeyModifiers::NONE, KeyCode::Char('t')) => state.show_types = !state.show_types,
+            (KeyModifiers::NONE, KeyCode::Char('m')) => state.show_metrics = !state.show_metrics,
+            (KeyModifiers::NONE, KeyCode::Char('p')) => state.panel = Panel::Preview,
+            (KeyModifiers::NONE, KeyCode::Char('r')) => Self::record_trace(state, editor_state),
+            (KeyModifiers::NONE, KeyCode::Char('e')) if state.panel == Panel::Trace => {
+                Self::export_trace(state)
+            }
+            (KeyModifiers::NONE, KeyCode::Char('o')) => {
+                Self::measure_complexity(state, editor_state, None)
+            }
+            (KeyModifiers::NONE, KeyCode::Char('c')) => Self::show_code(state),
+            (KeyModifiers::NONE, KeyCode::Char('f')) => Self::show_diagram(state, Panel::Flowchart),
+            (KeyModifiers::NONE, KeyCode::Char('s')) => {
+                Self::show_diagram(state, Panel::Structogram)
+            }
+            (KeyModifiers::NONE, KeyCode::Char('e')) if state.panel == Panel::Code => {
+                Self::export_code(state, editor_state, state.language)
+            }
+            (KeyModifiers::NONE, KeyCode::Char('e')) if state.panel == Panel::Structogram => {
+                Self::export_code(state, editor_state, Language::Structogram)
+            }
+            (KeyModifiers::NONE, KeyCode::Char('i')) if state.panel == Panel::Complexity => {
+                Self::cycle_complexity_input(state, editor_state)
+            }
+            (KeyModifiers::NONE, KeyCode::Char('e')) if state.panel == Panel::Complexity => {
+                Self::export_complexity(state)
+            }
+            (KeyModifiers::NONE, KeyCode::Down | KeyCode::Char('j'))
+                if state.panel == Panel::Trace =>
+            {
+                state.trace_scroll += 1
+            }
+            (KeyModifiers::NONE, KeyCode::Up | KeyCode::Char('k'))
+                if state.panel == Panel::Trace =>
+            {
+                state.trace_scroll = state.trace_scroll.saturating_sub(1)
+            }
+            (KeyModifiers::NONE, KeyCode::Down | KeyCode::Char('j'))
+                if state.panel == Panel::Code =>
+            {
+                state.code_scroll += 1
+            }
+            (KeyModifiers::NONE, KeyCode::Up | KeyCode::Char('k'))
+                if state.panel == Panel::Code =>
+            {
+                state.code_scroll = state.code_scroll.saturating_sub(1)
+            }
+            (KeyModifiers::NONE, KeyCode::Down | KeyCode::Char('j'))
+                if matches!(state.panel, Panel::Flowchart | Panel::Structogram) =>
+            {
+                state.diagram_scroll += 1
+            }
+            (KeyModifiers::NONE, KeyCode::Up | KeyCode::Char('k'))
+                if matches!(state.panel, Panel::Flowchart | Panel::Structogram) =>
+            {
+                state.diagram_scroll = state.diagram_scroll.saturating_sub(1)
             }
             _ => {}
         };
     }
 
-    fn handle_normal_state(key: KeyEvent, state: &mut State) -> bool {
+    fn handle_normal_state(
+        key: KeyEvent,
+        state: &mut State,
+        editor_state: &mut EditorState,
+    ) -> bool {
         match (key.modifiers, key.code) {
             (KeyModifiers::NONE, KeyCode::Char('q')) => {
                 state.should_quit = true;
                 true
             }
             (KeyModifiers::NONE, KeyCode::Tab) => {
-                state.app_state = match state.app_state {
-                    AppState::LeftSelected => AppState::RightSelected,
-                    AppState::RightSelected => AppState::LeftSelected,
-                };
+                state.app_state = AppState::RightSelected;
+                true
+            }
+            (KeyModifiers::NONE, KeyCode::F(5)) => {
+                Self::start_debugging(state, editor_state);
+                true
+            }
+            (KeyModifiers::NONE, KeyCode::F(6)) => {
+                Self::run_tests(state, editor_state);
+                true
+            }
+            (KeyModifiers::NONE, KeyCode::F(7)) => {
+                state.show_coverage = !state.show_coverage;
+                true
+            }
+            (KeyModifiers::NONE, KeyCode::F(8)) => {
+                Self::export_coverage(state);
+                true
+            }
+            (KeyModifiers::NONE, KeyCode::F(9)) => {
+                Self::toggle_breakpoint(state, editor_state.cursor.row);
+                true
+            }
+            (KeyModifiers::NONE, KeyCode::Char('f')) => {
+                Self::apply_fix(state, editor_state);
                 true
             }
             _ => false,
         }
     }
+
+    /// Applies the first available quick-fix for a diagnostic on the
+    /// cursor's line.
+    fn apply_fix(state: &State, editor_state: &mut EditorState) {
+        let text: String = editor_state.lines.clone().into();
+        let program = State::get_output(text.as_str());
+        let row = editor_state.cursor.row;
+        let fix = State::get_diagnostics(&program, &state.lint_config)
+            .into_iter()
+            .filter(|diagnostic| diagnostic.line == row)
+            .find_map(|diagnostic| diagnostic.fix);
+
+        if let Some(fix) = fix {
+            editor_state.lines = Lines::from(fix.apply(&text));
+            let last_row = editor_state.lines.len().saturating_sub(1);
+            editor_state.cursor.row = row.min(last_row);
+            editor_state.cursor.col = 0;
+        }
+    }
+
+    fn handle_debugger_inputs(key: KeyEvent, state: &mut State, editor_state: &mut EditorState) {
+        let Some(session) = &mut state.debugger else {
+            Self::stop_debugging(state);
+            return;
+        };
+        match (key.modifiers, key.code) {
+            (KeyModifiers::NONE, KeyCode::Char('s') | KeyCode::F(11)) => session.step_into(),
+            (KeyModifiers::NONE, KeyCode::Char('n') | KeyCode::F(10)) => session.step_over(),
+            (KeyModifiers::NONE, KeyCode::Char('c') | KeyCode::F(5)) => {
+                session.resume(&state.breakpoints)
+            }
+            (KeyModifiers::NONE, KeyCode::Char('r')) => session.position = 0,
+            (KeyModifiers::NONE, KeyCode::Char('b') | KeyCode::F(9)) => {
+                Self::toggle_breakpoint(state, editor_state.cursor.row)
+            }
+            (KeyModifiers::NONE, KeyCode::Char(' ')) => {
+                state.playback.playing = !state.playback.playing && session.current().is_some()
+            }
+            (_, KeyCode::Char('+' | '=')) => state.playback.faster(),
+            (KeyModifiers::NONE, KeyCode::Char('-')) => state.playback.slower(),
+            (KeyModifiers::NONE, KeyCode::Char('a')) => Self::cycle_array_view(state),
+            (KeyModifiers::NONE, KeyCode::Esc | KeyCode::Char('q')) => {
+                Self::stop_debugging(state);
+                return;
+            }
+            _ => {}
+        }
+        Self::show_debugger_line(state, editor_state);
+    }
+
+    /// Runs the program in the editor and shows its trace table.
+    fn record_trace(state: &mut State, editor_state: &EditorState) {
+        let text: String = editor_state.lines.clone().into();
+        state.panel = Panel::Trace;
+        state.trace_scroll = 0;
+        state.status = None;
+        match State::get_output(text.as_str())
+            .and_then(|program| Trace::record(&program, None, &Self::limits(state)))
+        {
+            Ok(trace) => state.trace = Some(trace),
+            Err(e) => {
+                state.trace = None;
+                state.status = Some(e.to_string());
+            }
+        }
+    }
+
+    /// Counts the operations of the function under the cursor, or the first
+    /// function, over inputs of growing size. `inputs` defaults to a random
+    /// array for the first parameter and the size for the rest.
+    fn measure_complexity(
+        state: &mut State,
+        editor_state: &EditorState,
+        inputs: Option<Vec<Input>>,
+    ) {
+        let text: String = editor_state.lines.clone().into();
+        state.panel = Panel::Complexity;
+        state.status = None;
+        let program = match State::get_output(text.as_str()) {
+            Ok(program) => program,
+            Err(e) => {
+                state.status = Some(e.to_string());
+                return;
+            }
+        };
+        let row = editor_state.cursor.row;
+        let Some(function) = program
+            .functions
+            .iter()
+            .rev()
+            .find(|function| function.line <= row)
+            .or(program.functions.first())
+        else {
+            state.status = Some("there is no function to measure".to_string());
+            return;
+        };
+        let inputs = inputs.unwrap_or_else(|| Input::defaults(function.params.len()));
+        match Measurement::record(
+            &program,
+            &function.name,
+            &inputs,
+            complexity::DEFAULT_SIZES,
+            &Self::limits(state),
+        ) {
+            Ok(measurement) => state.complexity = Some(measurement),
+            Err(e) => state.status = Some(e.to_string()),
+        }
+    }
+
+    /// Measures again with the first argument switched to the next kind of
+    /// generated array: random, sorted, reversed.
+    fn cycle_complexity_input(state: &mut State, editor_state: &EditorState) {
+        let Some(measurement) = &state.complexity else {
+            return;
+        };
+        let mut inputs = measurement.inputs.clone();
+        if let Some(first) = inputs.first_mut() {
+            *first = first.next();
+        }
+        Self::measure_complexity(state, editor_state, Some(inputs));
+    }
+
+    fn export_complexity(state: &mut State) {
+        let Some(measurement) = &state.complexity else {
+            return;
+        };
+        state.status = Some(match fs::write("complexity.csv", measurement.to_csv()) {
+            Ok(()) => "exported complexity.csv".to_string(),
+            Err(e) => format!("export failed: {e}"),
+        });
+    }
+
+    /// Runs the `test` lines of the program in the editor and shows the
+    /// lines they ran. The results stay until the tests are run again.
+    fn run_tests(state: &mut State, editor_state: &EditorState) {
+        let text: String = editor_state.lines.clone().into();
+        let Ok(program) = State::get_output(text.as_str()) else {
+            state.test_results = Vec::new();
+            return;
+        };
+        let mut coverage = Coverage::new(&program);
+        state.test_results = testing::run(
+            &program,
+            &program.tests,
+            &Self::limits(state),
+            &mut coverage,
+        );
+        state.coverage = Some(coverage);
+        state.show_coverage = true;
+    }
+
+    fn export_coverage(state: &mut State) {
+        let Some(coverage) = &state.coverage else {
+            return;
+        };
+        state.status = Some(
+            match fs::write("coverage.lcov", coverage.to_lcov("editor")) {
+                Ok(()) => "exported coverage.lcov".to_string(),
+                Err(e) => format!("export failed: {e}"),
+            },
+        );
+    }
+
+    /// Opens the code panel, or switches it to the next language when it is
+    /// already open.
+    fn show_code(state: &mut State) {
+        if state.panel == Panel::Code {
+            state.language = state.language.next();
+        }
+        state.panel = Panel::Code;
+        state.code_scroll = 0;
+        state.status = None;
+    }
+
+    fn show_diagram(state: &mut State, panel: Panel) {
+        state.panel = panel;
+        state.diagram_scroll = 0;
+        state.diagram_pan = 0;
+        state.diagram_cursor = None;
+        state.status = None;
+    }
+
+    fn export_code(state: &mut State, editor_state: &EditorState, language: Language) {
+        let text: String = editor_state.lines.clone().into();
+        let path = format!("program.{}", language.extension());
+        let result = State::get_output(text.as_str())
+            .and_then(|program| transpile::transpile(&program, language))
+            .and_then(|code| Ok(fs::write(&path, code)?));
+        state.status = Some(match result {
+            Ok(()) => format!("exported {path}"),
+            Err(e) => format!("export failed: {e}"),
+        });
+    }
+
+    fn export_trace(state: &mut State) {
+        let Some(trace) = &state.trace else {
+            return;
+        };
+        let result = fs::write("trace.csv", trace.to_csv())
+            .and_then(|_| fs::write("trace.md", trace.to_markdown()));
+        state.status = Some(match result {
+            Ok(()) => "exported trace.csv and trace.md".to_string(),
+            Err(e) => format!("export failed: {e}"),
+        });
+    }
+
+    /// The configured limits, cancellable by pressing Esc while the program
+    /// runs.
+    fn limits(state: &State) -> Limits {
+        Limits {
+            interrupt: Some(Self::escape_pressed),
+            ..state.limits.clone()
+        }
+    }
+
+    /// Drains pending input without blocking and reports whether Esc was
+    /// among it.
+    fn escape_pressed() -> bool {
+        let mut pressed = false;
+        while let Ok(true) = event::poll(Duration::ZERO) {
+            if let Ok(Event::Key(key)) = event::read() {
+                pressed |= key.code == KeyCode::Esc;
+            }
+        }
+        pressed
+    }
+
+    fn toggle_breakpoint(state: &mut State, row: usize) {
+        if !state.breakpoints.remove(&row) {
+            state.breakpoints.insert(row);
+        }
+    }
+
+    /// Runs the program in the editor and opens the debugger on its first
+    /// statement. Nothing happens while the program does not parse.
+    fn start_debugging(state: &mut State, editor_state: &mut EditorState) {
+        let text: String = editor_state.lines.clone().into();
+        let Ok(program) = State::get_output(text.as_str()) else {
+            return;
+        };
+        editor_state.mode = EditorMode::Normal;
+        let session = Session::start(&program, &Self::limits(state));
+        state.coverage = Some(session.coverage(&program));
+        state.show_coverage = true;
+        state.debugger = Some(session);
+        state.app_state = AppState::Debugging;
+        Self::show_debugger_line(state, editor_state);
+    }
+
+    /// Takes the next step while the debugger is playing. Playback pauses at
+    /// breakpoints and at the end of the run.
+    pub fn handle_tick(state: &mut State, editor_state: &mut EditorState) {
+        let Some(session) = &mut state.debugger else {
+            state.playback.playing = false;
+            return;
+        };
+        session.step_into();
+        match session.current() {
+            Some(snapshot) if !state.breakpoints.contains(&snapshot.line) => {}
+            _ => state.playback.playing = false,
+        }
+        Self::show_debugger_line(state, editor_state);
+    }
+
+    /// Switches the bar chart to the next array variable in scope.
+    fn cycle_array_view(state: &mut State) {
+        let Some(snapshot) = state.debugger.as_ref().and_then(Session::current) else {
+            return;
+        };
+        let arrays: Vec<&String> = snapshot
+            .variables
+            .iter()
+            .filter(|(_, value)| matches!(value, Value::Array(_)))
+            .map(|(name, _)| name)
+            .collect();
+        let next = match arrays
+            .iter()
+            .position(|name| Some(*name) == state.array_view.as_ref())
+        {
+            Some(i) => arrays.get(i + 1).or(arrays.first()),
+            None => arrays.get(1).or(arrays.first()),
+        };
+        state.array_view = next.map(|name| name.to_string());
+    }
+
+    fn stop_debugging(state: &mut State) {
+        state.playback.playing = false;
+        state.debugger = None;
+        state.app_state = AppState::LeftSelected;
+    }
+
+    /// Moves the editor cursor to the line the debugger is stopped on so
+    /// the editor scrolls it into view.
+    fn show_debugger_line(state: &State, editor_state: &mut EditorState) {
+        if let Some(row) = state.debugger.as_ref().and_then(Session::line) {
+            editor_state.cursor = Index2::new(row, 0);
+        }
+    }
 }
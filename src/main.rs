@@ -8,6 +8,7 @@ use std::error::Error;
 use std::io;
 
 mod app;
+mod cli;
 mod handlers;
 mod models;
 mod ui;
@@ -15,6 +16,15 @@ mod ui;
 use app::App;
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        if let Err(err) = cli::run(&args) {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
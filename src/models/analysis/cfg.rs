@@ -0,0 +1,298 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::models::nodes::{
+    condition::{Condition, If},
+    expression::Expression,
+    loops::{Loop, LoopType},
+    node::Node,
+    program::Program,
+};
+
+/// The block every path of a routine starts in.
+pub const ENTRY: usize = 0;
+/// The block every path of a routine ends in.
+pub const EXIT: usize = 1;
+
+/// What a basic block does, in order. Only the last step may branch or jump.
+pub enum Step<'a> {
+    /// A statement, `return`, `break` and `continue` included.
+    Statement(&'a Node),
+    /// The condition of an `if`, `elseif` or `while`, with a `True` and a
+    /// `False` edge out.
+    Condition(&'a Condition),
+    /// Taking the next item of a `for` loop: `True` while there is one.
+    Next { item: &'a str, list: &'a Expression },
+}
+
+#[derive(Default)]
+pub struct BasicBlock<'a> {
+    pub steps: Vec<Step<'a>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EdgeKind {
+    Jump,
+    True,
+    False,
+}
+
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+/// The control-flow graph of one routine, with its blocks indexed by
+/// position: `ENTRY` and `EXIT` are empty, and a block no path reaches
+/// holds code after a `return`, `break` or `continue`.
+pub struct Cfg<'a> {
+    pub name: String,
+    pub blocks: Vec<BasicBlock<'a>>,
+    pub edges: Vec<Edge>,
+}
+
+/// One graph per function, followed by the one for `start`.
+pub fn cfgs(program: &Program) -> Vec<Cfg<'_>> {
+    let mut graphs: Vec<Cfg> = program
+        .functions
+        .iter()
+        .map(|function| Cfg::new(&function.name, &function.body))
+        .collect();
+    graphs.push(Cfg::new("start", &program.start.body));
+    graphs
+}
+
+/// Where the edges into the next block come from.
+type Pending = Vec<(usize, EdgeKind)>;
+
+/// The loop being built, for `break` and `continue` to find.
+struct Enclosing {
+    header: usize,
+    breaks: Pending,
+}
+
+struct Builder<'a> {
+    cfg: Cfg<'a>,
+    /// The block statements are added to, until something ends it.
+    current: Option<usize>,
+    pending: Pending,
+    loops: Vec<Enclosing>,
+}
+
+impl<'a> Cfg<'a> {
+    pub fn new(name: &str, body: &'a [Node]) -> Self {
+        let mut builder = Builder {
+            cfg: Cfg {
+                name: name.to_string(),
+                blocks: vec![BasicBlock::default(), BasicBlock::default()],
+                edges: Vec::new(),
+            },
+            current: None,
+            pending: vec![(ENTRY, EdgeKind::Jump)],
+            loops: Vec::new(),
+        };
+        builder.sequence(body);
+        builder.close();
+        let pending = std::mem::take(&mut builder.pending);
+        builder.connect(pending, EXIT);
+        builder.cfg
+    }
+
+    pub fn successors(&self, block: usize) -> impl Iterator<Item = usize> + '_ {
+        self.edges
+            .iter()
+            .filter(move |edge| edge.from == block)
+            .map(|edge| edge.to)
+    }
+
+    pub fn predecessors(&self, block: usize) -> impl Iterator<Item = usize> + '_ {
+        self.edges
+            .iter()
+            .filter(move |edge| edge.to == block)
+            .map(|edge| edge.from)
+    }
+
+    /// Whether some path from `ENTRY` gets to each block.
+    pub fn reachable(&self) -> Vec<bool> {
+        let mut reached = vec![false; self.blocks.len()];
+        reached[ENTRY] = true;
+        let mut queue = VecDeque::from([ENTRY]);
+        while let Some(block) = queue.pop_front() {
+            for next in self.successors(block) {
+                if !reached[next] {
+                    reached[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        reached
+    }
+
+    /// The blocks every path from `ENTRY` to each block goes through, the
+    /// block itself included. Empty for blocks no path reaches.
+    pub fn dominators(&self) -> Vec<HashSet<usize>> {
+        let reachable = self.reachable();
+        let all: HashSet<usize> = (0..self.blocks.len())
+            .filter(|&block| reachable[block])
+            .collect();
+        let mut dominators: Vec<HashSet<usize>> = (0..self.blocks.len())
+            .map(|block| match block {
+                ENTRY => HashSet::from([ENTRY]),
+                _ if reachable[block] => all.clone(),
+                _ => HashSet::new(),
+            })
+            .collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for block in (0..self.blocks.len()).filter(|&block| block != ENTRY && reachable[block])
+            {
+                let mut common: Option<HashSet<usize>> = None;
+                for predecessor in self.predecessors(block).filter(|&p| reachable[p]) {
+                    let found = &dominators[predecessor];
+                    common = Some(match common {
+                        Some(common) => common.intersection(found).copied().collect(),
+                        None => found.clone(),
+                    });
+                }
+                let mut next = common.unwrap_or_default();
+                next.insert(block);
+                if next != dominators[block] {
+                    dominators[block] = next;
+                    changed = true;
+                }
+            }
+        }
+        dominators
+    }
+
+    /// The edges that go back to a block dominating where they leave from:
+    /// the ones that close a loop.
+    pub fn back_edges(&self) -> Vec<&Edge> {
+        let dominators = self.dominators();
+        self.edges
+            .iter()
+            .filter(|edge| dominators[edge.from].contains(&edge.to))
+            .collect()
+    }
+}
+
+impl<'a> Builder<'a> {
+    fn block(&mut self) -> usize {
+        self.cfg.blocks.push(BasicBlock::default());
+        self.cfg.blocks.len() - 1
+    }
+
+    fn connect(&mut self, pending: Pending, to: usize) {
+        for (from, kind) in pending {
+            self.cfg.edges.push(Edge { from, to, kind });
+        }
+    }
+
+    /// The block to add a statement to, started when there is none.
+    fn open(&mut self) -> usize {
+        if let Some(block) = self.current {
+            return block;
+        }
+        let block = self.block();
+        let pending = std::mem::take(&mut self.pending);
+        self.connect(pending, block);
+        self.current = Some(block);
+        block
+    }
+
+    /// Ends the current block, falling through to whatever comes next.
+    fn close(&mut self) {
+        if let Some(block) = self.current.take() {
+            self.pending = vec![(block, EdgeKind::Jump)];
+        }
+    }
+
+    /// Adds `step` as the last of its block, leaving nothing open.
+    fn end(&mut self, step: Step<'a>) -> usize {
+        let block = self.open();
+        self.cfg.blocks[block].steps.push(step);
+        self.current = None;
+        block
+    }
+
+    fn sequence(&mut self, body: &'a [Node]) {
+        for node in body {
+            self.node(node);
+        }
+    }
+
+    fn node(&mut self, node: &'a Node) {
+        match node {
+            Node::Assignment(_) | Node::FunctionCall(_) => {
+                let block = self.open();
+                self.cfg.blocks[block].steps.push(Step::Statement(node));
+            }
+            Node::Return(_) => {
+                let block = self.end(Step::Statement(node));
+                self.connect(vec![(block, EdgeKind::Jump)], EXIT);
+            }
+            Node::Break(_) => {
+                let block = self.end(Step::Statement(node));
+                // Outside a loop the statement is reported elsewhere; the
+                // flow just stops there.
+                if let Some(enclosing) = self.loops.last_mut() {
+                    enclosing.breaks.push((block, EdgeKind::Jump));
+                }
+            }
+            Node::Continue(_) => {
+                let block = self.end(Step::Statement(node));
+                if let Some(header) = self.loops.last().map(|enclosing| enclosing.header) {
+                    self.connect(vec![(block, EdgeKind::Jump)], header);
+                }
+            }
+            Node::If(statement) => self.if_statement(statement),
+            Node::Loop(statement) => self.loop_statement(statement),
+            Node::Comment(_) => {}
+        }
+    }
+
+    /// Runs `body` after the edges in `pending`, returning the edges out of
+    /// its end.
+    fn branch(&mut self, pending: Pending, body: &'a [Node]) -> Pending {
+        self.pending = pending;
+        self.sequence(body);
+        self.close();
+        std::mem::take(&mut self.pending)
+    }
+
+    fn if_statement(&mut self, statement: &'a If) {
+        let mut decision = self.end(Step::Condition(&statement.condition));
+        let mut after = self.branch(vec![(decision, EdgeKind::True)], &statement.body);
+        for branch in &statement.else_ifs {
+            self.pending = vec![(decision, EdgeKind::False)];
+            decision = self.end(Step::Condition(&branch.condition));
+            after.extend(self.branch(vec![(decision, EdgeKind::True)], &branch.body));
+        }
+        let otherwise = vec![(decision, EdgeKind::False)];
+        match &statement.else_branch {
+            Some(branch) => after.extend(self.branch(otherwise, &branch.body)),
+            None => after.extend(otherwise),
+        }
+        self.pending = after;
+    }
+
+    fn loop_statement(&mut self, statement: &'a Loop) {
+        // The condition gets a block of its own, for the body to come back to.
+        self.close();
+        let step = match &statement.loop_type {
+            LoopType::While(condition) => Step::Condition(condition),
+            LoopType::For { item, list } => Step::Next { item, list },
+        };
+        let header = self.end(step);
+        self.loops.push(Enclosing {
+            header,
+            breaks: Vec::new(),
+        });
+        let end = self.branch(vec![(header, EdgeKind::True)], &statement.body);
+        self.connect(end, header);
+        let enclosing = self.loops.pop().expect("the loop was pushed above");
+        self.pending = vec![(header, EdgeKind::False)];
+        self.pending.extend(enclosing.breaks);
+    }
+}
@@ -0,0 +1,304 @@
+use crate::models::{
+    diagnostics::{Diagnostic, Fix, TextEdit},
+    nodes::{
+        condition::Condition,
+        expression::Expression,
+        loops::{Loop, LoopType},
+        node::Node,
+        operator::{ArithmeticOperator, ComparisonOperator, LogicalOperator},
+        program::Program,
+        walk::{Scope, walk},
+    },
+    stdlib,
+};
+
+/// Walks every routine in the program and reports unreachable statements,
+/// routines that only return a value on some paths, and loops whose
+/// condition never changes.
+pub fn check(program: &Program) -> Vec<Diagnostic> {
+    let mut flow = ControlFlow::default();
+    for function in &program.functions {
+        let name = format!("function '{}'", function.name);
+        flow.check_routine(&function.body, function.line, &name);
+    }
+    flow.check_routine(&program.start.body, program.start.line, "start");
+    flow.diagnostics
+}
+
+#[derive(Default)]
+struct ControlFlow {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl ControlFlow {
+    fn check_routine(&mut self, body: &[Node], line: usize, name: &str) {
+        let exit = self.check_block(body, 0);
+        if exit.is_none() && contains_return(body) {
+            self.diagnostics.push(Diagnostic::warning(
+                line,
+                format!("{name} does not return a value on every path"),
+            ));
+        }
+    }
+
+    /// Returns the line of the statement that stops control from reaching
+    /// the end of `body`, or `None` when the block can fall through.
+    fn check_block(&mut self, body: &[Node], loop_depth: usize) -> Option<usize> {
+        let mut exit = None;
+        let mut reported = false;
+        for node in body {
+            if matches!(node, Node::Comment(_)) {
+                continue;
+            }
+            if let Some(line) = exit {
+                if !reported {
+                    let mut diagnostic = Diagnostic::warning(
+                        node.line(),
+                        format!(
+                            "unreachable code: control never gets past line {}",
+                            line + 1
+                        ),
+                    );
+                    if let Some(last) = body.last()
+                        && node.line() > line
+                    {
+                        diagnostic = diagnostic.with_fix(Fix::new(
+                            "remove unreachable code",
+                            vec![TextEdit::delete_lines(node.line(), last.end_line())],
+                        ));
+                    }
+                    self.diagnostics.push(diagnostic);
+                    reported = true;
+                }
+                self.check_node(node, loop_depth);
+            } else {
+                exit = self.check_node(node, loop_depth);
+            }
+        }
+        exit
+    }
+
+    fn check_node(&mut self, node: &Node, loop_depth: usize) -> Option<usize> {
+        match node {
+            Node::Return(val) => Some(val.line),
+            Node::Break(val) => {
+                if loop_depth == 0 {
+                    self.diagnostics
+                        .push(Diagnostic::error(val.line, "'break' outside of a loop"));
+                }
+                Some(val.line)
+            }
+            Node::Continue(val) => {
+                if loop_depth == 0 {
+                    self.diagnostics
+                        .push(Diagnostic::error(val.line, "'continue' outside of a loop"));
+                }
+                Some(val.line)
+            }
+            Node::If(val) => {
+                let mut all_exit = self.check_block(&val.body, loop_depth).is_some();
+                for else_if in &val.else_ifs {
+                    all_exit &= self.check_block(&else_if.body, loop_depth).is_some();
+                }
+                match &val.else_branch {
+                    Some(else_branch) => {
+                        all_exit &= self.check_block(&else_branch.body, loop_depth).is_some();
+                    }
+                    None => all_exit = false,
+                }
+                all_exit.then_some(val.line)
+            }
+            Node::Loop(val) => self.check_loop(val, loop_depth),
+            Node::Assignment(_) | Node::FunctionCall(_) | Node::Comment(_) => None,
+        }
+    }
+
+    fn check_loop(&mut self, node: &Loop, loop_depth: usize) -> Option<usize> {
+        self.check_block(&node.body, loop_depth + 1);
+
+        let LoopType::While(condition) = &node.loop_type else {
+            return None;
+        };
+
+        match constant_condition(condition) {
+            Some(true) => {
+                self.diagnostics.push(Diagnostic::warning(
+                    node.line,
+                    format!("loop condition '{condition}' is always true"),
+                ));
+                (!contains_break(&node.body)).then_some(node.line)
+            }
+            Some(false) => {
+                self.diagnostics.push(Diagnostic::warning(
+                    node.line,
+                    format!("loop condition '{condition}' is always false, the body never runs"),
+                ));
+                None
+            }
+            None => {
+                let variables = condition.variables();
+                if !variables.is_empty()
+                    && is_repeatable(condition)
+                    && !contains_break(&node.body)
+                    && !contains_return(&node.body)
+                    && !assigns_any(&node.body, &variables)
+                {
+                    self.diagnostics.push(Diagnostic::warning(
+                        node.line,
+                        format!(
+                            "loop at line {} does not change any variable in its condition",
+                            node.line + 1
+                        ),
+                    ));
+                }
+                None
+            }
+        }
+    }
+}
+
+fn contains_return(body: &[Node]) -> bool {
+    body.iter().any(|node| match node {
+        Node::Return(_) => true,
+        Node::If(val) => {
+            contains_return(&val.body)
+                || val
+                    .else_ifs
+                    .iter()
+                    .any(|branch| contains_return(&branch.body))
+                || val
+                    .else_branch
+                    .as_ref()
+                    .is_some_and(|branch| contains_return(&branch.body))
+        }
+        Node::Loop(val) => contains_return(&val.body),
+        _ => false,
+    })
+}
+
+/// Looks for a `break` that leaves the enclosing loop, ignoring nested loops.
+fn contains_break(body: &[Node]) -> bool {
+    body.iter().any(|node| match node {
+        Node::Break(_) => true,
+        Node::If(val) => {
+            contains_break(&val.body)
+                || val
+                    .else_ifs
+                    .iter()
+                    .any(|branch| contains_break(&branch.body))
+                || val
+                    .else_branch
+                    .as_ref()
+                    .is_some_and(|branch| contains_break(&branch.body))
+        }
+        _ => false,
+    })
+}
+
+/// Whether the condition gives the same answer as long as its variables
+/// keep their values, i.e. it calls nothing but built-ins other than
+/// `random`.
+fn is_repeatable(condition: &Condition) -> bool {
+    let mut repeatable = true;
+    condition.visit(&mut |expression| {
+        if let Expression::FunctionCall { name, .. } = expression {
+            repeatable &= name != "random" && stdlib::builtin(name).is_some();
+        }
+    });
+    repeatable
+}
+
+/// Whether any statement in `body`, however deeply nested, writes one of
+/// `variables`, either as a whole or one of its elements or fields.
+fn assigns_any(body: &[Node], variables: &[&str]) -> bool {
+    let mut found = false;
+    walk(body, Scope::default(), &mut |node, _| match node {
+        Node::Assignment(val) => {
+            let mut target = &val.target;
+            while let Expression::Index { target: inner, .. }
+            | Expression::Field { target: inner, .. } = target
+            {
+                target = inner;
+            }
+            if let Expression::Variable(name) = target {
+                found |= variables.contains(&name.as_str());
+            }
+        }
+        Node::Loop(val) => {
+            if let LoopType::For { item, .. } = &val.loop_type {
+                found |= variables.contains(&item.as_str());
+            }
+        }
+        _ => {}
+    });
+    found
+}
+
+fn constant_condition(condition: &Condition) -> Option<bool> {
+    match condition {
+        Condition::Value(value) => constant_value(value),
+        Condition::ComparisonOperator {
+            left,
+            operator,
+            right,
+        } => {
+            let (left, right) = (constant_number(left)?, constant_number(right)?);
+            Some(match operator.as_ref() {
+                ComparisonOperator::Eq => left == right,
+                ComparisonOperator::Neq => left != right,
+                ComparisonOperator::Gt => left > right,
+                ComparisonOperator::Lt => left < right,
+                ComparisonOperator::Gte => left >= right,
+                ComparisonOperator::Lte => left <= right,
+            })
+        }
+        Condition::LogicalOperator {
+            left,
+            operator,
+            right,
+        } => {
+            let (left, right) = (constant_condition(left), constant_condition(right));
+            match (operator.as_ref(), left, right) {
+                (LogicalOperator::And, Some(false), _) | (LogicalOperator::And, _, Some(false)) => {
+                    Some(false)
+                }
+                (LogicalOperator::Or, Some(true), _) | (LogicalOperator::Or, _, Some(true)) => {
+                    Some(true)
+                }
+                (LogicalOperator::And, Some(true), Some(true)) => Some(true),
+                (LogicalOperator::Or, Some(false), Some(false)) => Some(false),
+                _ => None,
+            }
+        }
+    }
+}
+
+fn constant_value(value: &Expression) -> Option<bool> {
+    match value {
+        Expression::Literal(value) if value == "true" => Some(true),
+        Expression::Literal(value) if value == "false" => Some(false),
+        Expression::Not(inner) => constant_value(inner).map(|value| !value),
+        _ => constant_number(value).map(|number| number != 0.0),
+    }
+}
+
+fn constant_number(expression: &Expression) -> Option<f64> {
+    match expression {
+        Expression::Literal(value) => value.parse().ok(),
+        Expression::BinaryOp {
+            left,
+            operator,
+            right,
+        } => {
+            let (left, right) = (constant_number(left)?, constant_number(right)?);
+            Some(match operator.as_ref() {
+                ArithmeticOperator::Addition => left + right,
+                ArithmeticOperator::Subtraction => left - right,
+                ArithmeticOperator::Multiplication => left * right,
+                ArithmeticOperator::Division => left / right,
+                ArithmeticOperator::Modulo => left % right,
+            })
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::HashSet;
+
+use crate::models::nodes::{
+    condition::Condition,
+    loops::LoopType,
+    node::Node,
+    program::Program,
+    walk::{Scope, walk},
+};
+
+/// Size and complexity numbers for a single function or the `start` block.
+pub struct Metrics {
+    pub name: String,
+    /// One plus every decision point: `if`, `elseif`, loops and each `&&` or
+    /// `||` in their conditions.
+    pub cyclomatic: usize,
+    pub max_nesting: usize,
+    pub statements: usize,
+    /// Distinct parameters, loop variables and assignment targets.
+    pub variables: usize,
+}
+
+pub fn measure(program: &Program) -> Vec<Metrics> {
+    let mut metrics: Vec<Metrics> = program
+        .functions
+        .iter()
+        .map(|function| {
+            measure_routine(
+                format!("function '{}'", function.name),
+                &function.body,
+                &function.params,
+            )
+        })
+        .collect();
+    metrics.push(measure_routine(
+        "start".to_string(),
+        &program.start.body,
+        &[],
+    ));
+    metrics
+}
+
+fn measure_routine(name: String, body: &[Node], params: &[String]) -> Metrics {
+    let mut cyclomatic = 1;
+    let mut max_nesting = 0;
+    let mut statements = 0;
+    let mut variables: HashSet<&str> = params.iter().map(String::as_str).collect();
+
+    walk(body, Scope::default(), &mut |node, scope| {
+        if !node.blocks().is_empty() {
+            max_nesting = max_nesting.max(scope.depth + 1);
+        }
+        match node {
+            Node::Comment(_) => return,
+            Node::If(val) => {
+                cyclomatic += 1 + val.else_ifs.len() + logical_operators(&val.condition);
+                for branch in &val.else_ifs {
+                    cyclomatic += logical_operators(&branch.condition);
+                }
+            }
+            Node::Loop(val) => {
+                cyclomatic += 1;
+                match &val.loop_type {
+                    LoopType::While(condition) => cyclomatic += logical_operators(condition),
+                    LoopType::For { item, .. } => {
+                        variables.insert(item);
+                    }
+                }
+            }
+            Node::Assignment(val) => {
+                variables.extend(val.var());
+            }
+            _ => {}
+        }
+        statements += 1;
+    });
+
+    Metrics {
+        name,
+        cyclomatic,
+        max_nesting,
+        statements,
+        variables: variables.len(),
+    }
+}
+
+fn logical_operators(condition: &Condition) -> usize {
+    match condition {
+        Condition::LogicalOperator { left, right, .. } => {
+            1 + logical_operators(left) + logical_operators(right)
+        }
+        _ => 0,
+    }
+}
@@ -0,0 +1,18 @@
+pub mod cfg;
+pub mod control_flow;
+pub mod metrics;
+pub mod types;
+
+use crate::models::{
+    diagnostics::Diagnostic,
+    lint::{self, config::LintConfig},
+    nodes::program::Program,
+};
+
+pub fn analyze(program: &Program, lint_config: &LintConfig) -> Vec<Diagnostic> {
+    let mut diagnostics = control_flow::check(program);
+    diagnostics.extend(types::check(program));
+    diagnostics.extend(lint::lint(program, lint_config));
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.severity));
+    diagnostics
+}
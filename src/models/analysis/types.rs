@@ -0,0 +1,588 @@
+use core::fmt;
+use std::collections::HashMap;
+
+use crate::models::{
+    diagnostics::Diagnostic,
+    nodes::{
+        condition::Condition,
+        expression::Expression,
+        loops::LoopType,
+        node::Node,
+        operator::{ArithmeticOperator, ComparisonOperator},
+        program::Program,
+        walk::{Scope, walk},
+    },
+    stdlib::{self, Kind},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Type {
+    Int,
+    Real,
+    String,
+    Bool,
+    Array(Box<Type>),
+    Record(Vec<(String, Type)>),
+    Unknown,
+}
+
+impl Type {
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Type::Int | Type::Real)
+    }
+
+    /// Whether anything about the type is still unknown, e.g. `array<?>`.
+    pub fn is_partial(&self) -> bool {
+        match self {
+            Type::Unknown => true,
+            Type::Array(element) => element.is_partial(),
+            Type::Record(fields) => fields.iter().any(|(_, field)| field.is_partial()),
+            _ => false,
+        }
+    }
+
+    /// The most specific type that both values fit into, or `None` when they
+    /// cannot be mixed. Integers widen to reals and unknowns take the other
+    /// side's type.
+    pub fn unify(&self, other: &Type) -> Option<Type> {
+        match (self, other) {
+            (Type::Unknown, other) | (other, Type::Unknown) => Some(other.clone()),
+            (Type::Int, Type::Real) | (Type::Real, Type::Int) => Some(Type::Real),
+            (Type::Array(a), Type::Array(b)) => Some(Type::Array(Box::new(a.unify(b)?))),
+            (Type::Record(a), Type::Record(b)) => {
+                if a.len() != b.len() {
+                    return None;
+                }
+                let fields = a
+                    .iter()
+                    .zip(b)
+                    .map(|((name, a), (other, b))| {
+                        (name == other).then(|| a.unify(b).map(|t| (name.clone(), t)))?
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(Type::Record(fields))
+            }
+            (a, b) if a == b => Some(a.clone()),
+            _ => None,
+        }
+    }
+
+    /// The type of a literal as written in the source, e.g. `"a"` or `1.5`.
+    pub fn of_literal(text: &str) -> Option<Type> {
+        if text.starts_with('"') {
+            Some(Type::String)
+        } else if text == "true" || text == "false" {
+            Some(Type::Bool)
+        } else if text.parse::<i64>().is_ok() {
+            Some(Type::Int)
+        } else if text.parse::<f64>().is_ok() {
+            Some(Type::Real)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "int"),
+            Type::Real => write!(f, "real"),
+            Type::String => write!(f, "string"),
+            Type::Bool => write!(f, "bool"),
+            Type::Array(element) => write!(f, "array<{element}>"),
+            Type::Record(fields) => {
+                write!(f, "{{")?;
+                for (i, (name, field)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{name}: {field}")?;
+                }
+                write!(f, "}}")
+            }
+            Type::Unknown => write!(f, "?"),
+        }
+    }
+}
+
+/// Infers the type of every variable and reports operations that mix
+/// incompatible types, such as adding a string to a number or comparing an
+/// array with an int.
+pub fn check(program: &Program) -> Vec<Diagnostic> {
+    infer(program, &HashMap::new(), &HashMap::new()).diagnostics
+}
+
+/// Fills in the inferred type of each assignment so the preview can show it.
+pub fn annotate(program: &mut Program) {
+    let types = infer(program, &HashMap::new(), &HashMap::new()).assignments;
+    for function in &mut program.functions {
+        annotate_block(&mut function.body, &types);
+    }
+    annotate_block(&mut program.start.body, &types);
+}
+
+fn annotate_block(body: &mut [Node], types: &HashMap<usize, Type>) {
+    for node in body {
+        match node {
+            Node::Assignment(val) => {
+                val.annotation = types.get(&val.line).map(Type::to_string);
+            }
+            Node::Loop(val) => annotate_block(&mut val.body, types),
+            Node::If(val) => {
+                annotate_block(&mut val.body, types);
+                for branch in &mut val.else_ifs {
+                    annotate_block(&mut branch.body, types);
+                }
+                if let Some(branch) = &mut val.else_branch {
+                    annotate_block(&mut branch.body, types);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The types of a whole program, with the parameter types of each function
+/// taken from the arguments it is called with, for code generators that have
+/// to spell every type out.
+pub struct Typing {
+    /// Parameter types of each function.
+    pub params: HashMap<String, Vec<Type>>,
+    /// Return type of each function, `Unknown` for one that returns nothing.
+    pub returns: HashMap<String, Type>,
+    /// Type of every variable, parameter and loop item by routine name.
+    pub variables: HashMap<String, HashMap<String, Type>>,
+    inference: Inference,
+}
+
+impl Typing {
+    pub fn new(program: &Program) -> Self {
+        let mut params = HashMap::new();
+        let mut returns = HashMap::new();
+        let mut inference = infer(program, &params, &returns);
+        // Each pass carries the types one call further, so a chain of calls
+        // settles within one pass per function.
+        for _ in 0..=program.functions.len() {
+            if inference.params == params && inference.returns == returns {
+                break;
+            }
+            params = inference.params.clone();
+            returns = inference.returns.clone();
+            inference = infer(program, &params, &returns);
+        }
+        Self {
+            params: inference.params.clone(),
+            returns: inference.returns.clone(),
+            variables: inference.variables.clone(),
+            inference,
+        }
+    }
+
+    /// The type of `expression` with the variables in `env`.
+    pub fn expression(&mut self, expression: &Expression, env: &HashMap<String, Type>) -> Type {
+        self.inference.expression(expression, env, 0)
+    }
+}
+
+/// Infers the types of the program, starting each function from the given
+/// parameter and return types, which earlier passes found.
+fn infer(
+    program: &Program,
+    params: &HashMap<String, Vec<Type>>,
+    returns: &HashMap<String, Type>,
+) -> Inference {
+    let mut inference = Inference {
+        arities: program
+            .functions
+            .iter()
+            .map(|function| (function.name.clone(), function.params.len()))
+            .collect(),
+        returns: returns.clone(),
+        ..Inference::default()
+    };
+    for function in &program.functions {
+        let types = params.get(&function.name);
+        let mut env: HashMap<String, Type> = function
+            .params
+            .iter()
+            .enumerate()
+            .map(|(i, param)| {
+                let param_type = types.and_then(|types| types.get(i)).cloned();
+                (param.clone(), param_type.unwrap_or(Type::Unknown))
+            })
+            .collect();
+        let returns = inference.check_routine(&function.body, &mut env);
+        inference.returns.insert(function.name.clone(), returns);
+        inference.variables.insert(function.name.clone(), env);
+    }
+    let mut env = HashMap::new();
+    inference.check_routine(&program.start.body, &mut env);
+    inference.variables.insert("start".to_string(), env);
+    for test in &program.tests {
+        let env = HashMap::new();
+        let actual = inference.expression(&test.call, &env, test.line);
+        let expected = inference.expression(&test.expected, &env, test.line);
+        if actual.unify(&expected).is_none() {
+            inference.error(
+                test.line,
+                format!("test expects {expected} but the call returns {actual}"),
+            );
+        }
+    }
+    inference
+}
+
+#[derive(Default)]
+struct Inference {
+    diagnostics: Vec<Diagnostic>,
+    /// Parameter count of every function in the program.
+    arities: HashMap<String, usize>,
+    /// Inferred return type of every function checked so far.
+    returns: HashMap<String, Type>,
+    /// Type of the value assigned on each line.
+    assignments: HashMap<usize, Type>,
+    /// Parameter types of each function, from the arguments of its calls.
+    params: HashMap<String, Vec<Type>>,
+    /// Variables of each routine with the type they ended up with.
+    variables: HashMap<String, HashMap<String, Type>>,
+}
+
+impl Inference {
+    fn check_routine(&mut self, body: &[Node], env: &mut HashMap<String, Type>) -> Type {
+        let mut returns = Type::Unknown;
+        walk(body, Scope::default(), &mut |node, _| {
+            let line = node.line();
+            match node {
+                Node::Assignment(val) => {
+                    let value = self.expression(&val.value, env, line);
+                    let Some(var) = val.var() else {
+                        let element = self.expression(&val.target, env, line);
+                        if element.unify(&value).is_none() {
+                            self.error(
+                                line,
+                                format!(
+                                    "cannot assign {value} to '{}' of type {element}",
+                                    val.target
+                                ),
+                            );
+                        }
+                        return;
+                    };
+                    let value = match env.get(var) {
+                        Some(previous) => previous.unify(&value).unwrap_or_else(|| {
+                            self.diagnostics.push(Diagnostic::warning(
+                                line,
+                                format!("'{var}' was {previous} but is now assigned {value}"),
+                            ));
+                            value
+                        }),
+                        None => value,
+                    };
+                    if !value.is_partial() {
+                        self.assignments.insert(line, value.clone());
+                    }
+                    env.insert(var.to_string(), value);
+                }
+                Node::Loop(val) => match &val.loop_type {
+                    LoopType::While(condition) => self.condition(condition, env, line),
+                    LoopType::For { item, list } => {
+                        let item_type = match self.expression(list, env, line) {
+                            Type::Array(element) => *element,
+                            Type::String => Type::String,
+                            Type::Unknown => Type::Unknown,
+                            other => {
+                                self.error(line, format!("cannot loop over {other}"));
+                                Type::Unknown
+                            }
+                        };
+                        env.insert(item.clone(), item_type);
+                    }
+                },
+                Node::If(val) => {
+                    self.condition(&val.condition, env, line);
+                    for branch in &val.else_ifs {
+                        self.condition(&branch.condition, env, line);
+                    }
+                }
+                Node::FunctionCall(val) => {
+                    self.call(&val.name, &val.args, env, line);
+                }
+                Node::Return(val) => {
+                    let value = self.expression(&val.value, env, line);
+                    returns = returns.unify(&value).unwrap_or_else(|| {
+                        self.error(
+                            line,
+                            format!("returns {value} but earlier returns give {returns}"),
+                        );
+                        returns.clone()
+                    });
+                }
+                Node::Break(_) | Node::Continue(_) | Node::Comment(_) => {}
+            }
+        });
+        returns
+    }
+
+    fn error(&mut self, line: usize, message: String) {
+        self.diagnostics.push(Diagnostic::error(line, message));
+    }
+
+    fn condition(&mut self, condition: &Condition, env: &HashMap<String, Type>, line: usize) {
+        match condition {
+            Condition::ComparisonOperator {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.expression(left, env, line);
+                let right = self.expression(right, env, line);
+                let ordered =
+                    !matches!(**operator, ComparisonOperator::Eq | ComparisonOperator::Neq);
+                let comparable = match left.unify(&right) {
+                    Some(Type::Unknown) => true,
+                    Some(common) => !ordered || common.is_numeric() || common == Type::String,
+                    None => false,
+                };
+                if !comparable {
+                    self.error(line, format!("cannot compare {left} {operator} {right}"));
+                }
+            }
+            Condition::LogicalOperator { left, right, .. } => {
+                self.condition(left, env, line);
+                self.condition(right, env, line);
+            }
+            Condition::Value(value) => {
+                let found = self.expression(value, env, line);
+                if !matches!(found, Type::Bool | Type::Unknown) {
+                    self.error(
+                        line,
+                        format!("condition '{value}' is {found}, expected bool"),
+                    );
+                }
+            }
+        }
+    }
+
+    fn expression(
+        &mut self,
+        expression: &Expression,
+        env: &HashMap<String, Type>,
+        line: usize,
+    ) -> Type {
+        match expression {
+            Expression::Literal(value) => Type::of_literal(value).unwrap_or(Type::Unknown),
+            Expression::Variable(name) => env.get(name).cloned().unwrap_or(Type::Unknown),
+            Expression::BinaryOp {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.expression(left, env, line);
+                let right = self.expression(right, env, line);
+                self.arithmetic(&left, operator, &right).unwrap_or_else(|| {
+                    self.error(
+                        line,
+                        format!("cannot apply '{operator}' to {left} and {right}"),
+                    );
+                    Type::Unknown
+                })
+            }
+            Expression::FunctionCall { name, args } => {
+                self.call(name, args, env, line).unwrap_or_else(|| {
+                    self.error(line, format!("'{name}' does not return a value"));
+                    Type::Unknown
+                })
+            }
+            Expression::Not(inner) => {
+                let inner = self.expression(inner, env, line);
+                if !matches!(inner, Type::Bool | Type::Unknown) {
+                    self.error(line, format!("'!' expects bool but found {inner}"));
+                }
+                Type::Bool
+            }
+            Expression::Array(items) => {
+                let mut element = Type::Unknown;
+                for item in items {
+                    let item = self.expression(item, env, line);
+                    element = element.unify(&item).unwrap_or_else(|| {
+                        self.error(line, format!("array mixes {element} and {item}"));
+                        Type::Unknown
+                    });
+                }
+                Type::Array(Box::new(element))
+            }
+            Expression::Record(fields) => Type::Record(
+                fields
+                    .iter()
+                    .map(|(name, value)| (name.clone(), self.expression(value, env, line)))
+                    .collect(),
+            ),
+            Expression::Index { target, index } => {
+                let target = self.expression(target, env, line);
+                let index = self.expression(index, env, line);
+                if !matches!(index, Type::Int | Type::Unknown) {
+                    self.error(line, format!("index must be int but found {index}"));
+                }
+                match target {
+                    Type::Array(element) => *element,
+                    Type::String => Type::String,
+                    Type::Unknown => Type::Unknown,
+                    other => {
+                        self.error(line, format!("cannot index into {other}"));
+                        Type::Unknown
+                    }
+                }
+            }
+            Expression::Field { target, name } => match self.expression(target, env, line) {
+                Type::Record(fields) => match fields.into_iter().find(|(field, _)| field == name) {
+                    Some((_, field)) => field,
+                    None => {
+                        self.error(line, format!("record has no field '{name}'"));
+                        Type::Unknown
+                    }
+                },
+                Type::Unknown => Type::Unknown,
+                other => {
+                    self.error(line, format!("{other} has no field '{name}'"));
+                    Type::Unknown
+                }
+            },
+        }
+    }
+
+    /// Checks a call against the function's parameter count or the built-in
+    /// signature and returns its result type, or `None` for a built-in that
+    /// gives back nothing.
+    fn call(
+        &mut self,
+        name: &str,
+        args: &[Expression],
+        env: &HashMap<String, Type>,
+        line: usize,
+    ) -> Option<Type> {
+        let found: Vec<Type> = args
+            .iter()
+            .map(|arg| self.expression(arg, env, line))
+            .collect();
+
+        if let Some(&arity) = self.arities.get(name) {
+            let params = self
+                .params
+                .entry(name.to_string())
+                .or_insert_with(|| vec![Type::Unknown; arity]);
+            for (param, found) in params.iter_mut().zip(&found) {
+                if let Some(unified) = param.unify(found) {
+                    *param = unified;
+                }
+            }
+            if arity != args.len() {
+                self.error(
+                    line,
+                    format!(
+                        "'{name}' expects {arity} argument(s) but got {}",
+                        args.len()
+                    ),
+                );
+            }
+            return Some(self.returns.get(name).cloned().unwrap_or(Type::Unknown));
+        }
+
+        let Some(builtin) = stdlib::builtin(name) else {
+            self.diagnostics.push(Diagnostic::warning(
+                line,
+                format!("unknown function '{name}'"),
+            ));
+            return Some(Type::Unknown);
+        };
+        if !builtin.accepts_count(args.len()) {
+            self.error(
+                line,
+                format!(
+                    "'{name}' expects {} argument(s) but got {}",
+                    builtin.arity(),
+                    args.len()
+                ),
+            );
+            return Some(result(builtin.returns, &[]));
+        }
+        for (i, found) in found.iter().enumerate() {
+            let Some(kind) = builtin.param(i) else {
+                continue;
+            };
+            if !accepts(kind, found) {
+                self.error(
+                    line,
+                    format!(
+                        "'{name}' expects {kind} for argument {} but found {found}",
+                        i + 1
+                    ),
+                );
+            }
+        }
+        if builtin.returns == Kind::Nothing {
+            return None;
+        }
+        if let (Kind::SameAsFirst, [Type::Array(element), rest @ ..]) =
+            (builtin.returns, found.as_slice())
+        {
+            let mut element = *element.clone();
+            for item in rest {
+                element = element.unify(item).unwrap_or_else(|| {
+                    self.error(line, format!("array mixes {element} and {item}"));
+                    Type::Unknown
+                });
+            }
+            return Some(Type::Array(Box::new(element)));
+        }
+        Some(result(builtin.returns, &found))
+    }
+
+    /// The result of `left operator right`, or `None` when the operands do
+    /// not support it.
+    fn arithmetic(&self, left: &Type, operator: &ArithmeticOperator, right: &Type) -> Option<Type> {
+        let numeric = |t: &Type| t.is_numeric() || *t == Type::Unknown;
+        match (operator, left, right) {
+            (ArithmeticOperator::Addition, Type::String, Type::String | Type::Unknown)
+            | (ArithmeticOperator::Addition, Type::Unknown, Type::String) => Some(Type::String),
+            (ArithmeticOperator::Addition, Type::Array(_), Type::Array(_)) => left.unify(right),
+            (_, _, _) if numeric(left) && numeric(right) => left.unify(right),
+            _ => None,
+        }
+    }
+}
+
+/// Whether a value of type `found` can be passed where `kind` is expected.
+/// Values of unknown type are given the benefit of the doubt.
+fn accepts(kind: Kind, found: &Type) -> bool {
+    if *found == Type::Unknown {
+        return true;
+    }
+    match kind {
+        Kind::Int => *found == Type::Int,
+        Kind::Real | Kind::Number => found.is_numeric(),
+        Kind::String => *found == Type::String,
+        Kind::Bool => *found == Type::Bool,
+        Kind::Array => matches!(found, Type::Array(_)),
+        Kind::Ints => matches!(found, Type::Array(element) if accepts(Kind::Int, element)),
+        Kind::Sequence => matches!(found, Type::Array(_) | Type::String),
+        Kind::Any | Kind::SameAsFirst | Kind::Nothing => true,
+    }
+}
+
+/// The type a built-in returns given the types of its arguments.
+fn result(kind: Kind, args: &[Type]) -> Type {
+    match kind {
+        Kind::Int => Type::Int,
+        Kind::Real => Type::Real,
+        Kind::String => Type::String,
+        Kind::Bool => Type::Bool,
+        Kind::Ints => Type::Array(Box::new(Type::Int)),
+        Kind::Array => Type::Array(Box::new(Type::Unknown)),
+        Kind::SameAsFirst => args
+            .iter()
+            .try_fold(Type::Unknown, |result, arg| result.unify(arg))
+            .unwrap_or(Type::Unknown),
+        Kind::Number | Kind::Sequence | Kind::Any | Kind::Nothing => Type::Unknown,
+    }
+}
@@ -0,0 +1,145 @@
+/// Directions a line leaves a cell in, combined into the box-drawing
+/// character that joins them.
+pub const UP: u8 = 1;
+pub const DOWN: u8 = 2;
+pub const LEFT: u8 = 4;
+pub const RIGHT: u8 = 8;
+
+#[derive(Clone, Copy, Default)]
+enum Cell {
+    #[default]
+    Empty,
+    Lines(u8),
+    Text(char),
+}
+
+/// Where the block for a source line was drawn.
+#[derive(Clone)]
+pub struct Region {
+    pub line: usize,
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// A diagram drawn on a grid of characters, with lines that join up into
+/// box-drawing characters where they meet.
+pub struct Canvas {
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<Vec<Cell>>,
+    pub regions: Vec<Region>,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![vec![Cell::Empty; width]; height],
+            regions: Vec::new(),
+        }
+    }
+
+    /// Adds line directions to a cell, unless text is written there.
+    pub fn add(&mut self, x: usize, y: usize, directions: u8) {
+        let cell = &mut self.cells[y][x];
+        *cell = match *cell {
+            Cell::Empty => Cell::Lines(directions),
+            Cell::Lines(existing) => Cell::Lines(existing | directions),
+            Cell::Text(character) => Cell::Text(character),
+        };
+    }
+
+    pub fn text(&mut self, x: usize, y: usize, text: &str) {
+        for (offset, character) in text.chars().enumerate() {
+            self.cells[y][x + offset] = Cell::Text(character);
+        }
+    }
+
+    /// A straight line between two cells, joining whatever already runs
+    /// through them.
+    pub fn line(&mut self, x0: usize, y0: usize, x1: usize, y1: usize) {
+        if (x0, y0) == (x1, y1) {
+            return;
+        }
+        if y0 == y1 {
+            let (from, to) = (x0.min(x1), x0.max(x1));
+            for x in from..=to {
+                let left = if x > from { LEFT } else { 0 };
+                let right = if x < to { RIGHT } else { 0 };
+                self.add(x, y0, left | right);
+            }
+        } else {
+            let (from, to) = (y0.min(y1), y0.max(y1));
+            for y in from..=to {
+                let up = if y > from { UP } else { 0 };
+                let down = if y < to { DOWN } else { 0 };
+                self.add(x0, y, up | down);
+            }
+        }
+    }
+
+    pub fn rectangle(&mut self, x: usize, y: usize, width: usize, height: usize) {
+        let (right, bottom) = (x + width - 1, y + height - 1);
+        self.line(x, y, right, y);
+        self.line(x, bottom, right, bottom);
+        self.line(x, y, x, bottom);
+        self.line(right, y, right, bottom);
+    }
+
+    /// Draws `other` with its top left corner at `x`, `y`.
+    pub fn blit(&mut self, other: &Canvas, x: usize, y: usize) {
+        for (row, cells) in other.cells.iter().enumerate() {
+            for (column, cell) in cells.iter().enumerate() {
+                match *cell {
+                    Cell::Empty => {}
+                    Cell::Lines(directions) => self.add(x + column, y + row, directions),
+                    Cell::Text(_) => self.cells[y + row][x + column] = *cell,
+                }
+            }
+        }
+        self.regions
+            .extend(other.regions.iter().map(|region| Region {
+                x: region.x + x,
+                y: region.y + y,
+                ..*region
+            }));
+    }
+
+    /// The drawing as text, one string per row.
+    pub fn rows(&self) -> Vec<String> {
+        self.cells
+            .iter()
+            .map(|cells| {
+                cells
+                    .iter()
+                    .map(|cell| match *cell {
+                        Cell::Empty => ' ',
+                        Cell::Lines(directions) => glyph(directions),
+                        Cell::Text(character) => character,
+                    })
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+}
+
+fn glyph(directions: u8) -> char {
+    match directions {
+        d if d == UP | DOWN | LEFT | RIGHT => '┼',
+        d if d == UP | DOWN | RIGHT => '├',
+        d if d == UP | DOWN | LEFT => '┤',
+        d if d == DOWN | LEFT | RIGHT => '┬',
+        d if d == UP | LEFT | RIGHT => '┴',
+        d if d == DOWN | RIGHT => '┌',
+        d if d == DOWN | LEFT => '┐',
+        d if d == UP | RIGHT => '└',
+        d if d == UP | LEFT => '┘',
+        d if d & (UP | DOWN) != 0 && d & (LEFT | RIGHT) == 0 => '│',
+        _ => '─',
+    }
+}
@@ -0,0 +1,311 @@
+use core::fmt;
+
+use color_eyre::eyre::{Result, eyre};
+
+use crate::models::{
+    interpreter::{
+        Frame, Observer, Operation, builtins::Random, limits::Limits, value::Value, vm::Vm,
+    },
+    nodes::program::Program,
+};
+
+/// Input sizes measured when none are given.
+pub const DEFAULT_SIZES: &[usize] = &[10, 20, 40, 80, 160, 320];
+
+/// Generated array elements lie between `0` and this bound.
+const MAX_ELEMENT: i64 = 1000;
+
+/// How to generate one argument for an input of size `n`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Input {
+    /// `n` random ints.
+    Random,
+    /// `n` ints in ascending order.
+    Sorted,
+    /// `n` ints in descending order.
+    Reversed,
+    /// The size itself.
+    Size,
+    /// The same value for every size, written as source text.
+    Fixed(String),
+}
+
+impl Input {
+    pub fn parse(text: &str) -> Self {
+        match text {
+            "random" => Input::Random,
+            "sorted" => Input::Sorted,
+            "reversed" => Input::Reversed,
+            "n" => Input::Size,
+            other => Input::Fixed(other.to_string()),
+        }
+    }
+
+    /// A random array for the first parameter and the size for the rest,
+    /// which suits sorting and searching functions.
+    pub fn defaults(params: usize) -> Vec<Self> {
+        (0..params)
+            .map(|i| if i == 0 { Input::Random } else { Input::Size })
+            .collect()
+    }
+
+    /// The array kind that follows this one, for cycling through them.
+    pub fn next(&self) -> Self {
+        match self {
+            Input::Random => Input::Sorted,
+            Input::Sorted => Input::Reversed,
+            _ => Input::Random,
+        }
+    }
+
+    fn generate<O: Observer>(&self, size: usize, vm: &mut Vm<O>) -> Result<Value> {
+        let mut random = Random::with_seed(size as u64);
+        let ints = |values: Vec<i64>| Value::Array(values.into_iter().map(Value::Int).collect());
+        let mut values: Vec<i64> = (0..size).map(|_| random.below(MAX_ELEMENT)).collect();
+        Ok(match self {
+            Input::Random => ints(values),
+            Input::Sorted => {
+                values.sort();
+                ints(values)
+            }
+            Input::Reversed => {
+                values.sort_by(|a, b| b.cmp(a));
+                ints(values)
+            }
+            Input::Size => Value::Int(size as i64),
+            Input::Fixed(text) => vm.evaluate_input(text)?,
+        })
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Input::Random => write!(f, "random"),
+            Input::Sorted => write!(f, "sorted"),
+            Input::Reversed => write!(f, "reversed"),
+            Input::Size => write!(f, "n"),
+            Input::Fixed(text) => write!(f, "{text}"),
+        }
+    }
+}
+
+/// A growth class that operation counts are fitted against.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Growth {
+    Constant,
+    Logarithmic,
+    Linear,
+    Linearithmic,
+    Quadratic,
+    Cubic,
+    /// `bⁿ` for a base `b` estimated from the counts.
+    Exponential(f64),
+}
+
+impl Growth {
+    const ALL: [Growth; 7] = [
+        Growth::Constant,
+        Growth::Logarithmic,
+        Growth::Linear,
+        Growth::Linearithmic,
+        Growth::Quadratic,
+        Growth::Cubic,
+        Growth::Exponential(2.0),
+    ];
+
+    pub fn value(self, n: f64) -> f64 {
+        match self {
+            Growth::Constant => 1.0,
+            Growth::Logarithmic => n.log2().max(1.0),
+            Growth::Linear => n,
+            Growth::Linearithmic => n * n.log2().max(1.0),
+            Growth::Quadratic => n * n,
+            Growth::Cubic => n * n * n,
+            Growth::Exponential(base) => base.powf(n),
+        }
+    }
+}
+
+impl fmt::Display for Growth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Growth::Constant => "1",
+            Growth::Logarithmic => "log n",
+            Growth::Linear => "n",
+            Growth::Linearithmic => "n log n",
+            Growth::Quadratic => "n²",
+            Growth::Cubic => "n³",
+            Growth::Exponential(base) => return write!(f, "{base:.2}ⁿ"),
+        };
+        write!(f, "{label}")
+    }
+}
+
+/// Operations counted in one run of the measured function.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sample {
+    pub size: usize,
+    pub comparisons: usize,
+    pub assignments: usize,
+    pub array_accesses: usize,
+}
+
+impl Sample {
+    pub fn total(&self) -> usize {
+        self.comparisons + self.assignments + self.array_accesses
+    }
+}
+
+impl Observer for Sample {
+    fn statement(&mut self, _line: usize, _frames: &[Frame], _output: &[String]) -> Result<()> {
+        Ok(())
+    }
+
+    fn operation(&mut self, operation: Operation<'_>) {
+        match operation {
+            Operation::Comparison => self.comparisons += 1,
+            Operation::Assignment => self.assignments += 1,
+            Operation::ArrayAccess { .. } => self.array_accesses += 1,
+        }
+    }
+}
+
+/// Operation counts of a function over inputs of growing size.
+pub struct Measurement {
+    pub function: String,
+    pub inputs: Vec<Input>,
+    pub samples: Vec<Sample>,
+    /// The growth class closest to the total counts, and its factor.
+    pub fit: Option<(Growth, f64)>,
+    /// The runtime error that stopped the measurement early, if any.
+    pub error: Option<String>,
+}
+
+impl Measurement {
+    /// Calls `function` once per size with arguments generated from
+    /// `inputs`, one per parameter, and counts the operations of each call.
+    pub fn record(
+        program: &Program,
+        function: &str,
+        inputs: &[Input],
+        sizes: &[usize],
+        limits: &Limits,
+    ) -> Result<Self> {
+        let Some(params) = program
+            .functions
+            .iter()
+            .find(|candidate| candidate.name == function)
+            .map(|function| function.params.len())
+        else {
+            return Err(eyre!("no function named '{function}'"));
+        };
+        if params != inputs.len() {
+            return Err(eyre!(
+                "'{function}' takes {params} argument(s) but {} input(s) were given",
+                inputs.len()
+            ));
+        }
+
+        let mut samples = Vec::new();
+        let mut error = None;
+        for &size in sizes {
+            let mut vm = Vm::with_observer(program, Sample::default());
+            vm.limits = limits.clone();
+            let args = inputs
+                .iter()
+                .map(|input| input.generate(size, &mut vm))
+                .collect::<Result<Vec<_>>>()?;
+            if let Err(e) = vm.call(function, args, 0) {
+                error = Some(format!("n = {size}: {e}"));
+                break;
+            }
+            samples.push(Sample {
+                size,
+                ..vm.observer
+            });
+        }
+
+        Ok(Self {
+            function: function.to_string(),
+            inputs: inputs.to_vec(),
+            fit: fit(&samples),
+            samples,
+            error,
+        })
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = "size,comparisons,assignments,array_accesses,total\n".to_string();
+        for sample in &self.samples {
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                sample.size,
+                sample.comparisons,
+                sample.assignments,
+                sample.array_accesses,
+                sample.total()
+            ));
+        }
+        csv
+    }
+}
+
+/// Fits `total ≈ c · growth(n)` for every growth class and returns the one
+/// with the smallest relative error. Needs at least two samples.
+fn fit(samples: &[Sample]) -> Option<(Growth, f64)> {
+    if samples.len() < 2 {
+        return None;
+    }
+    Growth::ALL
+        .into_iter()
+        .filter_map(|growth| match growth {
+            Growth::Exponential(_) => exponential_base(samples).map(Growth::Exponential),
+            growth => Some(growth),
+        })
+        .filter_map(|growth| {
+            let points: Vec<(f64, f64)> = samples
+                .iter()
+                .map(|sample| {
+                    (
+                        growth.value(sample.size as f64),
+                        sample.total().max(1) as f64,
+                    )
+                })
+                .collect();
+            if points.iter().any(|(x, _)| !x.is_finite()) {
+                return None;
+            }
+            // Least squares on the relative error, so small sizes count as
+            // much as large ones.
+            let numerator: f64 = points.iter().map(|(x, y)| x / y).sum();
+            let denominator: f64 = points.iter().map(|(x, y)| (x / y).powi(2)).sum();
+            let factor = numerator / denominator;
+            let error: f64 = points
+                .iter()
+                .map(|(x, y)| ((y - factor * x) / y).powi(2))
+                .sum();
+            Some((growth, factor, error))
+        })
+        .min_by(|a, b| a.2.total_cmp(&b.2))
+        .map(|(growth, factor, _)| (growth, factor))
+}
+
+/// The base `b` of the exponential `c · bⁿ` closest to the counts, from a
+/// straight line through their logarithms. `None` unless the counts grow.
+fn exponential_base(samples: &[Sample]) -> Option<f64> {
+    let points: Vec<(f64, f64)> = samples
+        .iter()
+        .map(|sample| (sample.size as f64, (sample.total().max(1) as f64).ln()))
+        .collect();
+    let count = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / count;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / count;
+    let covariance: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let base = (covariance / variance).exp();
+    (base.is_finite() && base > 1.0).then_some(base)
+}
@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+
+use color_eyre::eyre::Result;
+
+use crate::models::{
+    interpreter::{Frame, Observer},
+    nodes::{
+        node::Node,
+        program::Program,
+        walk::{Scope, walk},
+    },
+};
+
+/// How often each statement ran, over one or more runs of a program.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Coverage {
+    /// Runs by zero-based line. Every statement's line is present, so the
+    /// ones that never ran show up as `0`.
+    pub hits: BTreeMap<usize, usize>,
+}
+
+impl Coverage {
+    /// Every statement of the program, none of them run yet.
+    pub fn new(program: &Program) -> Self {
+        let mut hits = BTreeMap::new();
+        for routine in program.routines() {
+            walk(routine.body, Scope::default(), &mut |node, _| {
+                if !matches!(node, Node::Comment(_)) {
+                    hits.insert(node.line(), 0);
+                }
+            });
+        }
+        Self { hits }
+    }
+
+    pub fn hit(&mut self, line: usize) {
+        *self.hits.entry(line).or_default() += 1;
+    }
+
+    pub fn add(&mut self, other: &Coverage) {
+        for (&line, &count) in &other.hits {
+            *self.hits.entry(line).or_default() += count;
+        }
+    }
+
+    /// Statement lines that ran at least once.
+    pub fn covered(&self) -> usize {
+        self.hits.values().filter(|&&count| count > 0).count()
+    }
+
+    /// How hot a line ran from `0` to `1` compared to the hottest one, on a
+    /// log scale so a loop body stands out without hiding the lines that
+    /// ran only a few times. `None` for lines without a statement.
+    pub fn heat(&self, line: usize) -> Option<f64> {
+        let count = *self.hits.get(&line)?;
+        let hottest = self.hits.values().copied().max().unwrap_or_default();
+        if hottest <= 1 {
+            return Some(count as f64);
+        }
+        Some((count as f64).ln_1p() / (hottest as f64).ln_1p())
+    }
+
+    /// The coverage as an lcov tracefile for `source`, so runs can be
+    /// compared with the usual tools. Lines are one-based there.
+    pub fn to_lcov(&self, source: &str) -> String {
+        let mut lcov = format!("TN:\nSF:{source}\n");
+        for (line, count) in &self.hits {
+            lcov.push_str(&format!("DA:{},{count}\n", line + 1));
+        }
+        lcov.push_str(&format!(
+            "LF:{}\nLH:{}\nend_of_record\n",
+            self.hits.len(),
+            self.covered()
+        ));
+        lcov
+    }
+}
+
+impl Observer for Coverage {
+    fn statement(&mut self, _line: usize, _frames: &[Frame], _output: &[String]) -> Result<()> {
+        Ok(())
+    }
+
+    fn step(&mut self, line: usize) {
+        self.hit(line);
+    }
+}
@@ -0,0 +1,242 @@
+use std::{collections::BTreeSet, time::Duration};
+
+use color_eyre::eyre::{Result, eyre};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{
+    coverage::Coverage,
+    interpreter::{
+        Frame, Interpreter, Observer, Operation, RuntimeError, limits::Limits, value::Value,
+    },
+    nodes::program::Program,
+};
+
+/// An element of an array variable that a statement read or wrote.
+pub struct Access {
+    pub array: String,
+    pub index: usize,
+    pub write: bool,
+}
+
+/// The program state just before a statement runs.
+pub struct Snapshot {
+    pub line: usize,
+    /// Number of calls on the stack, `1` inside `start`.
+    pub depth: usize,
+    pub routine: String,
+    pub variables: Vec<(String, Value)>,
+    /// How many lines of output had been printed at this point.
+    pub output: usize,
+    /// Elements the statement touched while it ran, not counting those in
+    /// functions it called.
+    pub accesses: Vec<Access>,
+}
+
+/// Values a session keeps copies of across all its snapshots. A run that
+/// needs more is stopped there, so a long loop can't use up the memory.
+const HISTORY_LIMIT: usize = 4_000_000;
+
+#[derive(Default)]
+struct Recorder {
+    snapshots: Vec<Snapshot>,
+    /// Values copied into the snapshots so far.
+    recorded: usize,
+    /// Whether the run was stopped for reaching [`HISTORY_LIMIT`].
+    truncated: bool,
+}
+
+impl Observer for Recorder {
+    fn statement(&mut self, line: usize, frames: &[Frame], output: &[String]) -> Result<()> {
+        let Some(frame) = frames.last() else {
+            return Ok(());
+        };
+        let size = 1 + frame
+            .variables
+            .iter()
+            .map(|(_, value)| value.size())
+            .sum::<usize>();
+        if self.recorded + size > HISTORY_LIMIT {
+            self.truncated = true;
+            return Err(eyre!("debug history truncated"));
+        }
+        self.recorded += size;
+        self.snapshots.push(Snapshot {
+            line,
+            depth: frames.len(),
+            routine: frame.name.clone(),
+            variables: frame.variables.clone(),
+            output: output.len(),
+            accesses: Vec::new(),
+        });
+        Ok(())
+    }
+
+    fn operation(&mut self, operation: Operation<'_>) {
+        if let Operation::ArrayAccess {
+            array: Some(array),
+            index,
+            write,
+        } = operation
+            && let Some(snapshot) = self.snapshots.last_mut()
+        {
+            snapshot.accesses.push(Access {
+                array: array.to_string(),
+                index,
+                write,
+            });
+        }
+    }
+}
+
+/// A debugging session over a recorded run. The program is executed once up
+/// front and stepping moves through the recorded statements, so stepping
+/// never changes what the program does. Only the first part of a run too
+/// long to keep in memory is recorded.
+pub struct Session {
+    pub snapshots: Vec<Snapshot>,
+    /// Index of the statement about to run; equal to `snapshots.len()` once
+    /// the program has finished.
+    pub position: usize,
+    pub output: Vec<String>,
+    /// The runtime error that ended the run, if any.
+    pub error: Option<RuntimeError>,
+    /// Whether the run was cut short because its history grew too large.
+    pub truncated: bool,
+}
+
+impl Session {
+    pub fn start(program: &Program, limits: &Limits) -> Self {
+        let mut interpreter = Interpreter::with_observer(program, Recorder::default());
+        interpreter.limits = limits.clone();
+        let result = interpreter.run();
+        let truncated = interpreter.observer.truncated;
+        let snapshots = interpreter.observer.snapshots;
+        let error = result.err().filter(|_| !truncated).map(|error| {
+            match error.downcast::<RuntimeError>() {
+                Ok(error) => error,
+                Err(error) => {
+                    let line = snapshots.last().map_or(0, |snapshot| snapshot.line);
+                    RuntimeError::new(line, error.to_string())
+                }
+            }
+        });
+        Self {
+            snapshots,
+            position: 0,
+            output: interpreter.output,
+            error,
+            truncated,
+        }
+    }
+
+    /// The lines the whole run went through, however far the session has
+    /// stepped.
+    pub fn coverage(&self, program: &Program) -> Coverage {
+        let mut coverage = Coverage::new(program);
+        for snapshot in &self.snapshots {
+            coverage.hit(snapshot.line);
+        }
+        coverage
+    }
+
+    pub fn current(&self) -> Option<&Snapshot> {
+        self.snapshots.get(self.position)
+    }
+
+    /// The line of the statement about to run, or of the error that ended
+    /// the run.
+    pub fn line(&self) -> Option<usize> {
+        match (self.current(), &self.error) {
+            (Some(snapshot), _) => Some(snapshot.line),
+            (None, Some(error)) => Some(error.line),
+            (None, None) => None,
+        }
+    }
+
+    /// Elements touched by the statement that ran just before the current
+    /// one, or an empty list when that statement ran in another routine.
+    pub fn last_accesses(&self) -> &[Access] {
+        let Some(previous) = self.position.checked_sub(1) else {
+            return &[];
+        };
+        let previous = &self.snapshots[previous];
+        match self.current() {
+            Some(current) if current.depth != previous.depth => &[],
+            _ => &previous.accesses,
+        }
+    }
+
+    /// Output printed before the current statement, or all of it once the
+    /// program has finished.
+    pub fn visible_output(&self) -> &[String] {
+        match self.current() {
+            Some(snapshot) => &self.output[..snapshot.output],
+            None => &self.output,
+        }
+    }
+
+    /// Moves to the next statement, entering any function it calls.
+    pub fn step_into(&mut self) {
+        self.position = (self.position + 1).min(self.snapshots.len());
+    }
+
+    /// Moves to the next statement in the same routine or a caller, running
+    /// any calls made by the current statement to completion.
+    pub fn step_over(&mut self) {
+        let Some(depth) = self.current().map(|snapshot| snapshot.depth) else {
+            return;
+        };
+        self.advance_until(|snapshot| snapshot.depth <= depth);
+    }
+
+    /// Runs until the next statement on a breakpoint line, or to the end.
+    pub fn resume(&mut self, breakpoints: &BTreeSet<usize>) {
+        self.advance_until(|snapshot| breakpoints.contains(&snapshot.line));
+    }
+
+    fn advance_until(&mut self, stop: impl Fn(&Snapshot) -> bool) {
+        self.position = self
+            .snapshots
+            .iter()
+            .enumerate()
+            .skip(self.position + 1)
+            .find(|(_, snapshot)| stop(snapshot))
+            .map_or(self.snapshots.len(), |(i, _)| i);
+    }
+}
+
+/// Automatic stepping through a session, for watching an algorithm run.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct Playback {
+    #[serde(skip)]
+    pub playing: bool,
+    pub steps_per_second: u64,
+}
+
+impl Playback {
+    const MAX_STEPS_PER_SECOND: u64 = 64;
+
+    /// Time between two steps while playing.
+    pub fn interval(&self) -> Option<Duration> {
+        self.playing
+            .then(|| Duration::from_millis(1000 / self.steps_per_second.max(1)))
+    }
+
+    pub fn faster(&mut self) {
+        self.steps_per_second = (self.steps_per_second * 2).min(Self::MAX_STEPS_PER_SECOND);
+    }
+
+    pub fn slower(&mut self) {
+        self.steps_per_second = (self.steps_per_second / 2).max(1);
+    }
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Self {
+            playing: false,
+            steps_per_second: 4,
+        }
+    }
+}
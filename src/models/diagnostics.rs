@@ -0,0 +1,169 @@
+use core::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        };
+        write!(f, "{label}")
+    }
+}
+
+/// A zero-based line and character column in the editor buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize) -> Self {
+        Self { line, column }
+    }
+
+    /// The end of the last line with text on it, so an insertion there
+    /// lands before any trailing line breaks.
+    pub fn end_of_document() -> Self {
+        Self::new(usize::MAX, 0)
+    }
+}
+
+/// Replaces the text between `start` and `end` with `text`.
+#[derive(Clone, Debug)]
+pub struct TextEdit {
+    pub start: Position,
+    pub end: Position,
+    pub text: String,
+}
+
+impl TextEdit {
+    pub fn insert(at: Position, text: impl Into<String>) -> Self {
+        Self {
+            start: at,
+            end: at,
+            text: text.into(),
+        }
+    }
+
+    pub fn replace(start: Position, end: Position, text: impl Into<String>) -> Self {
+        Self {
+            start,
+            end,
+            text: text.into(),
+        }
+    }
+
+    /// Removes the lines `first..=last` including their line breaks.
+    pub fn delete_lines(first: usize, last: usize) -> Self {
+        Self::replace(Position::new(first, 0), Position::new(last + 1, 0), "")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Fix {
+    pub title: String,
+    pub edits: Vec<TextEdit>,
+}
+
+impl Fix {
+    pub fn new(title: impl Into<String>, edits: Vec<TextEdit>) -> Self {
+        Self {
+            title: title.into(),
+            edits,
+        }
+    }
+
+    pub fn apply(&self, source: &str) -> String {
+        let mut text = source.to_string();
+        let mut edits: Vec<&TextEdit> = self.edits.iter().collect();
+        edits.sort_by_key(|edit| edit.start);
+
+        for edit in edits.into_iter().rev() {
+            let start = offset(&text, edit.start);
+            let end = offset(&text, edit.end).max(start);
+            text.replace_range(start..end, &edit.text);
+        }
+        text
+    }
+}
+
+/// Converts a position into a byte offset, clamping it to the line and
+/// document it points past.
+fn offset(text: &str, position: Position) -> usize {
+    if position == Position::end_of_document() {
+        return text.trim_end_matches(['\n', '\r']).len();
+    }
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match text[line_start..].find('\n') {
+            Some(newline) => line_start += newline + 1,
+            None => return text.len(),
+        }
+    }
+
+    let line = text[line_start..].split('\n').next().unwrap_or_default();
+    let column = line
+        .char_indices()
+        .nth(position.column)
+        .map_or(line.len(), |(index, _)| index);
+    line_start + column
+}
+
+/// A message about the source, anchored to a zero-based editor line.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub severity: Severity,
+    pub message: String,
+    pub rule: Option<&'static str>,
+    pub fix: Option<Fix>,
+}
+
+impl Diagnostic {
+    pub fn error(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            severity: Severity::Error,
+            message: message.into(),
+            rule: None,
+            fix: None,
+        }
+    }
+
+    pub fn warning(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            severity: Severity::Warning,
+            message: message.into(),
+            rule: None,
+            fix: None,
+        }
+    }
+
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.line + 1, self.severity, self.message)?;
+        if let Some(rule) = self.rule {
+            write!(f, " [{rule}]")?;
+        }
+        if let Some(fix) = &self.fix {
+            write!(f, " (fix: {})", fix.title)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,336 @@
+use crate::models::{
+    canvas::{Canvas, LEFT, RIGHT, Region},
+    flowchart::{Shape, condition, expression, statement},
+    nodes::{
+        condition::{Condition, Else, ElseIf},
+        loops::{Loop, LoopType},
+        node::Node,
+        program::Program,
+    },
+};
+
+/// Blank rows between the flowcharts of two routines.
+const ROUTINE_GAP: usize = 2;
+
+/// A flowchart laid out on a grid of characters, top to bottom: the
+/// condition of an `if` branches to `yes` below it and `no` to its right,
+/// and a loop's body returns along its left side.
+struct Drawing {
+    canvas: Canvas,
+    /// The column the flow comes in at on the top row.
+    entry: usize,
+    /// The column the flow leaves at on the bottom row, unless it never gets
+    /// past the end, as after a `return`.
+    exit: Option<usize>,
+    /// Whether the flow coming in ends in an arrow head at `entry`.
+    arrow: bool,
+}
+
+/// The flowcharts of every function and `start`, one below the other.
+pub fn draw(program: &Program) -> Canvas {
+    let mut charts: Vec<Drawing> = program
+        .functions
+        .iter()
+        .map(|function| {
+            let title = format!("{}({})", function.name, function.params.join(", "));
+            routine(title, function.line, &function.body)
+        })
+        .collect();
+    charts.push(routine(
+        "start".to_string(),
+        program.start.line,
+        &program.start.body,
+    ));
+    let width = charts
+        .iter()
+        .map(|chart| chart.canvas.width)
+        .max()
+        .unwrap_or(0);
+    let height = charts
+        .iter()
+        .map(|chart| chart.canvas.height)
+        .sum::<usize>()
+        + ROUTINE_GAP * charts.len().saturating_sub(1);
+    let mut canvas = Canvas::new(width, height);
+    let mut y = 0;
+    for chart in &charts {
+        canvas.blit(&chart.canvas, 0, y);
+        y += chart.canvas.height + ROUTINE_GAP;
+    }
+    canvas
+}
+
+fn routine(title: String, line: usize, body: &[Node]) -> Drawing {
+    let mut items = vec![shape(Shape::Terminal, &title, Some(line), true)];
+    let body = sequence(body);
+    let exits = body.exit.is_some();
+    if body.canvas.height > 0 {
+        items.push(body);
+    }
+    if exits {
+        items.push(shape(Shape::Terminal, "end", None, false));
+    }
+    stack(items)
+}
+
+fn sequence(body: &[Node]) -> Drawing {
+    stack(body.iter().filter_map(node).collect())
+}
+
+fn node(node: &Node) -> Option<Drawing> {
+    Some(match node {
+        Node::Assignment(assignment) => shape(
+            Shape::Process,
+            &statement(node),
+            Some(assignment.line),
+            true,
+        ),
+        Node::FunctionCall(call) => shape(Shape::Process, &statement(node), Some(call.line), true),
+        Node::Return(value) => shape(Shape::Terminal, &statement(node), Some(value.line), false),
+        Node::Break(value) => shape(Shape::Process, &statement(node), Some(value.line), false),
+        Node::Continue(value) => shape(Shape::Process, &statement(node), Some(value.line), false),
+        Node::If(statement) => if_chain(
+            &statement.condition,
+            Some(statement.line),
+            &statement.body,
+            &statement.else_ifs,
+            statement.else_branch.as_ref(),
+        ),
+        Node::Loop(statement) => loop_statement(statement),
+        Node::Comment(_) => return None,
+    })
+}
+
+/// A single block holding `text`. The flow goes on below it when `exits`.
+fn shape(shape: Shape, text: &str, line: Option<usize>, exits: bool) -> Drawing {
+    let length = text.chars().count();
+    let width = length + 4;
+    let mut drawing = Drawing::new(width, 3);
+    match shape {
+        Shape::Terminal | Shape::Process => {
+            drawing.canvas.rectangle(0, 0, width, 3);
+            drawing.canvas.text(2, 1, text);
+            if shape == Shape::Terminal {
+                drawing.canvas.text(0, 0, "╭");
+                drawing.canvas.text(width - 1, 0, "╮");
+                drawing.canvas.text(0, 2, "╰");
+                drawing.canvas.text(width - 1, 2, "╯");
+            }
+        }
+        Shape::Decision => {
+            drawing.canvas.text(1, 0, "╱");
+            drawing.canvas.text(width - 2, 0, "╲");
+            drawing.canvas.text(0, 1, "<");
+            drawing.canvas.text(2, 1, text);
+            drawing.canvas.text(width - 1, 1, ">");
+            drawing.canvas.text(1, 2, "╲");
+            drawing.canvas.text(width - 2, 2, "╱");
+            for x in 2..width - 2 {
+                drawing.canvas.add(x, 0, LEFT | RIGHT);
+                drawing.canvas.add(x, 2, LEFT | RIGHT);
+            }
+        }
+    }
+    if let Some(line) = line {
+        drawing.canvas.regions.push(Region {
+            line,
+            x: 0,
+            y: 0,
+            width,
+            height: 3,
+        });
+    }
+    drawing.entry = width / 2;
+    drawing.exit = exits.then_some(width / 2);
+    drawing.arrow = true;
+    drawing
+}
+
+/// `items` one below the other, centred on a common column and joined by
+/// arrows wherever the flow gets past an item.
+fn stack(items: Vec<Drawing>) -> Drawing {
+    let Some(axis) = items.iter().map(|item| item.entry).max() else {
+        let mut empty = Drawing::new(1, 0);
+        empty.exit = Some(0);
+        return empty;
+    };
+    let width = items
+        .iter()
+        .map(|item| axis - item.entry + item.canvas.width)
+        .max()
+        .unwrap_or(0);
+    let height = items.iter().map(|item| item.canvas.height).sum::<usize>() + items.len() - 1;
+    let mut drawing = Drawing::new(width, height);
+    drawing.entry = axis;
+    drawing.arrow = items[0].arrow;
+    let mut y = 0;
+    let mut exit: Option<usize> = None;
+    for (index, item) in items.iter().enumerate() {
+        drawing.canvas.blit(&item.canvas, axis - item.entry, y);
+        if index > 0
+            && let Some(bottom) = exit
+        {
+            drawing.canvas.line(axis, bottom, axis, y);
+            if item.arrow {
+                drawing.canvas.text(axis, y, "▼");
+            }
+        }
+        exit = item.exit.map(|_| y + item.canvas.height - 1);
+        y += item.canvas.height + 1;
+    }
+    drawing.exit = exit.map(|_| axis);
+    drawing
+}
+
+/// An `if` with its `elseif`s and `else`, each `elseif` drawn as an `if`
+/// in the `no` branch of the one before.
+fn if_chain(
+    test: &Condition,
+    line: Option<usize>,
+    body: &[Node],
+    else_ifs: &[ElseIf],
+    else_branch: Option<&Else>,
+) -> Drawing {
+    let otherwise = match else_ifs.split_first() {
+        Some((next, rest)) => Some(if_chain(
+            &next.condition,
+            None,
+            &next.body,
+            rest,
+            else_branch,
+        )),
+        None => else_branch
+            .map(|branch| sequence(&branch.body))
+            .filter(|drawing| drawing.canvas.height > 0),
+    };
+    let decision = shape(Shape::Decision, &condition(test), line, false);
+    let then = sequence(body);
+
+    let axis = decision.entry.max(then.entry);
+    let decision_x = axis - decision.entry;
+    let then_x = axis - then.entry;
+    let decision_right = decision_x + decision.canvas.width;
+    // Room for the `no` label after the condition.
+    let right_edge = (decision_right + 2).max(then_x + then.canvas.width);
+    let branch_x = right_edge + 1 + otherwise.as_ref().map_or(0, |other| other.entry);
+    let lower = then
+        .canvas
+        .height
+        .max(otherwise.as_ref().map_or(0, |other| other.canvas.height));
+    let merge = 4 + lower;
+    let then_exits = then.exit.is_some();
+    let otherwise_exits = otherwise.as_ref().is_none_or(|other| other.exit.is_some());
+    let exits = then_exits || otherwise_exits;
+
+    let width = (branch_x + 1).max(
+        otherwise
+            .as_ref()
+            .map_or(0, |other| right_edge + 1 + other.canvas.width),
+    );
+    let mut drawing = Drawing::new(width, if exits { merge + 1 } else { merge });
+    drawing.canvas.blit(&decision.canvas, decision_x, 0);
+    drawing.entry = axis;
+    drawing.arrow = true;
+
+    let then_bottom = if then.canvas.height == 0 {
+        2
+    } else {
+        drawing.canvas.blit(&then.canvas, then_x, 4);
+        drawing.canvas.line(axis, 2, axis, 4);
+        if then.arrow {
+            drawing.canvas.text(axis, 4, "▼");
+        }
+        4 + then.canvas.height - 1
+    };
+    drawing.canvas.text(axis + 1, 3, "yes");
+    if then_exits {
+        drawing.canvas.line(axis, then_bottom, axis, merge);
+    }
+
+    drawing.canvas.line(decision_right - 1, 1, branch_x, 1);
+    drawing.canvas.text(decision_right, 1, "no");
+    let otherwise_bottom = match &otherwise {
+        Some(other) => {
+            drawing.canvas.blit(&other.canvas, right_edge + 1, 4);
+            drawing.canvas.line(branch_x, 1, branch_x, 4);
+            if other.arrow {
+                drawing.canvas.text(branch_x, 4, "▼");
+            }
+            4 + other.canvas.height - 1
+        }
+        None => 1,
+    };
+    if otherwise_exits {
+        drawing
+            .canvas
+            .line(branch_x, otherwise_bottom, branch_x, merge);
+        drawing.canvas.line(branch_x, merge, axis, merge);
+    }
+    drawing.exit = exits.then_some(axis);
+    drawing
+}
+
+/// A loop: its condition, the body below it under `yes` and a line back
+/// up the left side into the top, and `no` leading out on the right.
+fn loop_statement(statement: &Loop) -> Drawing {
+    let text = match &statement.loop_type {
+        LoopType::While(test) => condition(test),
+        LoopType::For { item, list } => format!("next {item} in {}", expression(list)),
+    };
+    let decision = shape(Shape::Decision, &text, Some(statement.line), false);
+    let body = sequence(&statement.body);
+
+    // Two columns on the left for the line back up.
+    let axis = decision.entry.max(body.entry) + 2;
+    let decision_x = axis - decision.entry;
+    let body_x = axis - body.entry;
+    let decision_right = decision_x + decision.canvas.width;
+    let right_edge = (decision_right + 2).max(body_x + body.canvas.width);
+    let branch_x = right_edge + 1;
+    let body_exits = body.exit.is_some();
+    let back = 5 + body.canvas.height;
+    let out = if body_exits { back + 1 } else { back };
+
+    let mut drawing = Drawing::new(branch_x + 1, out + 1);
+    drawing.canvas.blit(&decision.canvas, decision_x, 1);
+    drawing.entry = axis;
+    drawing.canvas.line(axis, 0, axis, 1);
+    drawing.canvas.text(axis, 1, "▼");
+
+    let body_bottom = if body.canvas.height == 0 {
+        3
+    } else {
+        drawing.canvas.blit(&body.canvas, body_x, 5);
+        drawing.canvas.line(axis, 3, axis, 5);
+        if body.arrow {
+            drawing.canvas.text(axis, 5, "▼");
+        }
+        5 + body.canvas.height - 1
+    };
+    drawing.canvas.text(axis + 1, 4, "yes");
+    if body_exits {
+        drawing.canvas.line(axis, body_bottom, axis, back);
+        drawing.canvas.line(axis, back, 0, back);
+        drawing.canvas.line(0, back, 0, 0);
+        drawing.canvas.line(0, 0, axis, 0);
+        drawing.canvas.text(axis - 1, 0, "▶");
+    }
+
+    drawing.canvas.line(decision_right - 1, 2, branch_x, 2);
+    drawing.canvas.text(decision_right, 2, "no");
+    drawing.canvas.line(branch_x, 2, branch_x, out);
+    drawing.canvas.line(branch_x, out, axis, out);
+    drawing.exit = Some(axis);
+    drawing
+}
+
+impl Drawing {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            canvas: Canvas::new(width, height),
+            entry: 0,
+            exit: None,
+            arrow: false,
+        }
+    }
+}
@@ -0,0 +1,319 @@
+pub mod layout;
+
+use crate::models::nodes::{
+    condition::{Condition, If},
+    expression::Expression,
+    loops::{Loop, LoopType},
+    node::Node,
+    operator::{ArithmeticOperator, LogicalOperator},
+    program::Program,
+};
+
+/// How a block of a flowchart is drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shape {
+    /// Where a routine starts or ends.
+    Terminal,
+    /// A statement that runs and moves on.
+    Process,
+    /// A condition with a `yes` and a `no` way out.
+    Decision,
+}
+
+pub struct Block {
+    pub shape: Shape,
+    pub text: String,
+}
+
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    /// `yes` or `no` for the ways out of a decision.
+    pub label: Option<&'static str>,
+}
+
+/// The flowchart of one routine: blocks indexed by position, the first the
+/// start terminal and the second the end terminal.
+pub struct Flowchart {
+    pub name: String,
+    pub blocks: Vec<Block>,
+    pub edges: Vec<Edge>,
+}
+
+/// One flowchart per function, followed by the one for `start`.
+pub fn flowcharts(program: &Program) -> Vec<Flowchart> {
+    let mut charts: Vec<Flowchart> = program
+        .functions
+        .iter()
+        .map(|function| {
+            let title = format!("{}({})", function.name, function.params.join(", "));
+            Flowchart::new(&function.name, title, &function.body)
+        })
+        .collect();
+    charts.push(Flowchart::new(
+        "start",
+        "start".to_string(),
+        &program.start.body,
+    ));
+    charts
+}
+
+const END: usize = 1;
+
+/// Blocks waiting for an edge to whatever comes next, with the label the
+/// edge will carry.
+type Pending = Vec<(usize, Option<&'static str>)>;
+
+/// The loop being built, for `break` and `continue` to find.
+struct Enclosing {
+    condition: usize,
+    breaks: Pending,
+}
+
+impl Flowchart {
+    fn new(name: &str, title: String, body: &[Node]) -> Self {
+        let mut chart = Flowchart {
+            name: name.to_string(),
+            blocks: Vec::new(),
+            edges: Vec::new(),
+        };
+        let start = chart.block(Shape::Terminal, title);
+        chart.block(Shape::Terminal, "end".to_string());
+        let pending = chart.sequence(body, vec![(start, None)], &mut Vec::new());
+        chart.connect(pending, END);
+        chart
+    }
+
+    fn block(&mut self, shape: Shape, text: String) -> usize {
+        self.blocks.push(Block { shape, text });
+        self.blocks.len() - 1
+    }
+
+    fn connect(&mut self, pending: Pending, to: usize) {
+        for (from, label) in pending {
+            self.edges.push(Edge { from, to, label });
+        }
+    }
+
+    /// A new block that every pending block leads to.
+    fn follow(&mut self, pending: Pending, shape: Shape, text: String) -> usize {
+        let block = self.block(shape, text);
+        self.connect(pending, block);
+        block
+    }
+
+    fn sequence(
+        &mut self,
+        body: &[Node],
+        mut pending: Pending,
+        loops: &mut Vec<Enclosing>,
+    ) -> Pending {
+        for node in body {
+            pending = self.node(node, pending, loops);
+        }
+        pending
+    }
+
+    fn node(&mut self, node: &Node, pending: Pending, loops: &mut Vec<Enclosing>) -> Pending {
+        match node {
+            Node::Assignment(_) | Node::FunctionCall(_) => {
+                let block = self.follow(pending, Shape::Process, statement(node));
+                vec![(block, None)]
+            }
+            Node::Return(_) => {
+                let block = self.follow(pending, Shape::Process, statement(node));
+                self.connect(vec![(block, None)], END);
+                Vec::new()
+            }
+            Node::If(statement) => self.if_statement(statement, pending, loops),
+            Node::Loop(statement) => self.loop_statement(statement, pending, loops),
+            Node::Break(_) => {
+                // Outside a loop the statement is reported elsewhere; the
+                // flow just stops there.
+                if let Some(enclosing) = loops.last_mut() {
+                    enclosing.breaks.extend(pending);
+                }
+                Vec::new()
+            }
+            Node::Continue(_) => {
+                if let Some(enclosing) = loops.last() {
+                    let condition = enclosing.condition;
+                    self.connect(pending, condition);
+                }
+                Vec::new()
+            }
+            Node::Comment(_) => pending,
+        }
+    }
+
+    fn if_statement(
+        &mut self,
+        statement: &If,
+        pending: Pending,
+        loops: &mut Vec<Enclosing>,
+    ) -> Pending {
+        let text = condition(&statement.condition);
+        let mut decision = self.follow(pending, Shape::Decision, text);
+        let mut after = self.sequence(&statement.body, vec![(decision, Some("yes"))], loops);
+        for branch in &statement.else_ifs {
+            decision = self.follow(
+                vec![(decision, Some("no"))],
+                Shape::Decision,
+                condition(&branch.condition),
+            );
+            after.extend(self.sequence(&branch.body, vec![(decision, Some("yes"))], loops));
+        }
+        let otherwise = vec![(decision, Some("no"))];
+        match &statement.else_branch {
+            Some(branch) => after.extend(self.sequence(&branch.body, otherwise, loops)),
+            None => after.extend(otherwise),
+        }
+        after
+    }
+
+    fn loop_statement(
+        &mut self,
+        statement: &Loop,
+        pending: Pending,
+        loops: &mut Vec<Enclosing>,
+    ) -> Pending {
+        let text = match &statement.loop_type {
+            LoopType::While(test) => condition(test),
+            LoopType::For { item, list } => format!("next {item} in {}", expression(list)),
+        };
+        let decision = self.follow(pending, Shape::Decision, text);
+        loops.push(Enclosing {
+            condition: decision,
+            breaks: Vec::new(),
+        });
+        let end = self.sequence(&statement.body, vec![(decision, Some("yes"))], loops);
+        self.connect(end, decision);
+        let enclosing = loops.pop().expect("the loop was pushed above");
+        let mut after = vec![(decision, Some("no"))];
+        after.extend(enclosing.breaks);
+        after
+    }
+}
+
+/// How tightly an expression binds, loosest first.
+const OR: u8 = 1;
+const AND: u8 = 2;
+const COMPARE: u8 = 3;
+const SUM: u8 = 4;
+const PRODUCT: u8 = 5;
+const ATOM: u8 = 6;
+
+/// A statement as a single line of text: `if` and loops by their keyword
+/// alone, since their bodies are drawn on their own.
+pub fn statement(node: &Node) -> String {
+    match node {
+        Node::Assignment(assignment) => format!(
+            "{} ← {}",
+            expression(&assignment.target),
+            expression(&assignment.value)
+        ),
+        Node::FunctionCall(call) => {
+            let args: Vec<String> = call.args.iter().map(expression).collect();
+            format!("{}({})", call.name, args.join(", "))
+        }
+        Node::Return(value) => format!("return {}", expression(&value.value)),
+        Node::If(_) => "if".to_string(),
+        Node::Loop(_) => "loop".to_string(),
+        Node::Break(_) => "break".to_string(),
+        Node::Continue(_) => "continue".to_string(),
+        Node::Comment(comment) => format!("-- {}", comment.value.trim()),
+    }
+}
+
+/// `expression` as pseudocode, with only the parentheses it needs.
+pub fn expression(expression: &Expression) -> String {
+    expression_text(expression).0
+}
+
+/// `condition` as pseudocode, with only the parentheses it needs.
+pub fn condition(condition: &Condition) -> String {
+    condition_text(condition).0
+}
+
+fn operand(expression: &Expression, precedence: u8) -> String {
+    let (text, binds) = expression_text(expression);
+    parenthesize(text, binds, precedence)
+}
+
+fn expression_text(expression: &Expression) -> (String, u8) {
+    match expression {
+        Expression::BinaryOp {
+            left,
+            operator,
+            right,
+        } => {
+            let precedence = match **operator {
+                ArithmeticOperator::Addition | ArithmeticOperator::Subtraction => SUM,
+                _ => PRODUCT,
+            };
+            let left = operand(left, precedence);
+            let right = operand(right, precedence + 1);
+            (format!("{left} {operator} {right}"), precedence)
+        }
+        Expression::FunctionCall { name, args } => {
+            let args: Vec<String> = args.iter().map(|arg| operand(arg, OR)).collect();
+            (format!("{name}({})", args.join(", ")), ATOM)
+        }
+        Expression::Not(inner) => (format!("!{}", operand(inner, ATOM)), ATOM),
+        Expression::Array(items) => {
+            let items: Vec<String> = items.iter().map(|item| operand(item, OR)).collect();
+            (format!("[{}]", items.join(", ")), ATOM)
+        }
+        Expression::Record(fields) => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(name, value)| format!("{name}: {}", operand(value, OR)))
+                .collect();
+            (format!("{{{}}}", fields.join(", ")), ATOM)
+        }
+        Expression::Index { target, index } => (
+            format!("{}[{}]", operand(target, ATOM), operand(index, OR)),
+            ATOM,
+        ),
+        Expression::Field { target, name } => (format!("{}.{name}", operand(target, ATOM)), ATOM),
+        Expression::Variable(_) | Expression::Literal(_) => (expression.to_string(), ATOM),
+    }
+}
+
+fn condition_text(condition: &Condition) -> (String, u8) {
+    match condition {
+        Condition::ComparisonOperator {
+            left,
+            operator,
+            right,
+        } => {
+            let left = operand(left, SUM);
+            let right = operand(right, SUM);
+            (format!("{left} {operator} {right}"), COMPARE)
+        }
+        Condition::LogicalOperator {
+            left,
+            operator,
+            right,
+        } => {
+            let precedence = match **operator {
+                LogicalOperator::And => AND,
+                LogicalOperator::Or => OR,
+            };
+            let (left, binds) = condition_text(left);
+            let left = parenthesize(left, binds, precedence);
+            let (right, binds) = condition_text(right);
+            let right = parenthesize(right, binds, precedence + 1);
+            (format!("{left} {operator} {right}"), precedence)
+        }
+        Condition::Value(value) => expression_text(value),
+    }
+}
+
+fn parenthesize(text: String, binds: u8, precedence: u8) -> String {
+    if binds < precedence {
+        format!("({text})")
+    } else {
+        text
+    }
+}
@@ -0,0 +1,160 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::models::{interpreter::value::Value, stdlib::Builtin};
+
+/// A small xorshift generator behind `random`, so runs don't need an
+/// external crate.
+pub struct Random {
+    state: u64,
+    /// Numbers drawn so far.
+    pub draws: usize,
+}
+
+impl Random {
+    pub fn seeded() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64);
+        Self::with_seed(nanos)
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        // xorshift gets stuck on zero.
+        Self {
+            state: seed | 1,
+            draws: 0,
+        }
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        self.draws += 1;
+        x
+    }
+
+    /// A number from `0` up to but not including `bound`.
+    pub fn below(&mut self, bound: i64) -> i64 {
+        (self.next() % bound as u64) as i64
+    }
+}
+
+/// Applies a built-in that returns a value. The argument count has already
+/// been checked against the signature; the types are checked here.
+pub fn apply(builtin: &Builtin, args: Vec<Value>, random: &mut Random) -> Result<Value, String> {
+    let mismatch = |args: &[Value]| {
+        let found: Vec<&str> = args.iter().map(Value::type_name).collect();
+        format!(
+            "'{}' takes ({}) but got ({})",
+            builtin.name,
+            builtin.parameters(),
+            found.join(", ")
+        )
+    };
+
+    let value = match (builtin.name, args.as_slice()) {
+        ("length", [Value::Array(items)]) => Value::Int(items.len() as i64),
+        ("length", [Value::String(text)]) => Value::Int(text.chars().count() as i64),
+        ("abs", [Value::Int(value)]) => value
+            .checked_abs()
+            .map(Value::Int)
+            .ok_or_else(|| "integer overflow".to_string())?,
+        ("abs", [Value::Real(value)]) => Value::Real(value.abs()),
+        ("min" | "max", [Value::Int(left), Value::Int(right)]) => {
+            Value::Int(if builtin.name == "min" {
+                *left.min(right)
+            } else {
+                *left.max(right)
+            })
+        }
+        ("min" | "max", [left, right]) => {
+            let (Some(left), Some(right)) = (left.as_real(), right.as_real()) else {
+                return Err(mismatch(&args));
+            };
+            Value::Real(if builtin.name == "min" {
+                left.min(right)
+            } else {
+                left.max(right)
+            })
+        }
+        ("sqrt", [value]) => match value.as_real() {
+            Some(value) if value < 0.0 => {
+                return Err(format!("cannot take the square root of {value}"));
+            }
+            Some(value) => Value::Real(value.sqrt()),
+            None => return Err(mismatch(&args)),
+        },
+        ("pow", [base, exponent]) => match (base.as_real(), exponent.as_real()) {
+            (Some(base), Some(exponent)) => Value::Real(base.powf(exponent)),
+            _ => return Err(mismatch(&args)),
+        },
+        ("floor" | "ceil" | "round", [Value::Int(value)]) => Value::Int(*value),
+        ("floor" | "ceil" | "round", [Value::Real(value)]) => {
+            let rounded = match builtin.name {
+                "floor" => value.floor(),
+                "ceil" => value.ceil(),
+                _ => value.round(),
+            };
+            to_int(rounded)?
+        }
+        ("random", [Value::Int(bound)]) if *bound <= 0 => {
+            return Err(format!("'random' needs a positive bound but got {bound}"));
+        }
+        ("random", [Value::Int(bound)]) => Value::Int(random.below(*bound)),
+        ("range", [Value::Int(from), Value::Int(to)]) => {
+            Value::Array((*from..*to).map(Value::Int).collect())
+        }
+        ("append", [Value::Array(items), value]) => {
+            let mut items = items.clone();
+            items.push(value.clone());
+            Value::Array(items)
+        }
+        ("contains", [Value::Array(items), value]) => Value::Bool(items.contains(value)),
+        ("contains", [Value::String(text), Value::String(part)]) => {
+            Value::Bool(text.contains(part.as_str()))
+        }
+        ("substring", [Value::String(text), Value::Int(from), Value::Int(to)]) => {
+            let chars: Vec<char> = text.chars().collect();
+            let (Ok(start), Ok(end)) = (usize::try_from(*from), usize::try_from(*to)) else {
+                return Err(format!("substring {from}..{to} is out of bounds"));
+            };
+            if start > end || end > chars.len() {
+                return Err(format!(
+                    "substring {from}..{to} is out of bounds for length {}",
+                    chars.len()
+                ));
+            }
+            Value::String(chars[start..end].iter().collect())
+        }
+        ("to_upper", [Value::String(text)]) => Value::String(text.to_uppercase()),
+        ("to_lower", [Value::String(text)]) => Value::String(text.to_lowercase()),
+        ("to_string", [value]) => Value::String(value.to_output()),
+        ("to_int", [Value::Int(value)]) => Value::Int(*value),
+        ("to_int", [Value::Real(value)]) => to_int(value.trunc())?,
+        ("to_int", [Value::String(text)]) => match text.trim().parse() {
+            Ok(value) => Value::Int(value),
+            Err(_) => return Err(format!("cannot convert \"{text}\" to int")),
+        },
+        ("to_real", [Value::String(text)]) => match text.trim().parse() {
+            Ok(value) => Value::Real(value),
+            Err(_) => return Err(format!("cannot convert \"{text}\" to real")),
+        },
+        ("to_real", [value]) => match value.as_real() {
+            Some(value) => Value::Real(value),
+            None => return Err(mismatch(&args)),
+        },
+        _ => return Err(mismatch(&args)),
+    };
+    Ok(value)
+}
+
+fn to_int(value: f64) -> Result<Value, String> {
+    if value.is_finite() && value >= i64::MIN as f64 && value <= i64::MAX as f64 {
+        Ok(Value::Int(value as i64))
+    } else {
+        Err(format!("{value} does not fit in an int"))
+    }
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+/// How far a program may run before the interpreter stops it with an error.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Limits {
+    /// Statements and loop iterations executed in total.
+    pub max_steps: usize,
+    /// Calls on the stack at once, counting `start`.
+    pub max_depth: usize,
+    /// Polled every few thousand steps; returning `true` cancels the run.
+    #[serde(skip)]
+    pub interrupt: Option<fn() -> bool>,
+}
+
+impl Limits {
+    /// How many steps run between two polls of [`Limits::interrupt`].
+    pub const POLL_INTERVAL: usize = 4096;
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_steps: 1_000_000,
+            max_depth: 256,
+            interrupt: None,
+        }
+    }
+}
@@ -0,0 +1,708 @@
+pub mod builtins;
+pub mod limits;
+pub mod value;
+pub mod vm;
+
+use core::fmt;
+use std::collections::HashMap;
+
+use color_eyre::eyre::Result;
+
+use crate::models::{
+    interpreter::{builtins::Random, limits::Limits, value::Value},
+    nodes::{
+        condition::Condition,
+        expression::Expression,
+        function::Function,
+        loops::LoopType,
+        node::Node,
+        operator::LogicalOperator,
+        parsing::{Parsable, tokenize},
+        program::Program,
+        walk::{Scope, walk},
+    },
+    stdlib::{self, Builtin},
+};
+
+/// A failure while running the program, anchored to the statement that
+/// caused it.
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl RuntimeError {
+    pub fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (line {})", self.message, self.line + 1)
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+fn fail<T>(line: usize, message: impl Into<String>) -> Result<T> {
+    Err(RuntimeError::new(line, message).into())
+}
+
+/// The variables of one running function or of the `start` block, in the
+/// order they were first assigned.
+pub struct Frame {
+    pub name: String,
+    pub variables: Vec<(String, Value)>,
+}
+
+impl Frame {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            variables: Vec::new(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.variables
+            .iter()
+            .find(|(var, _)| var == name)
+            .map(|(_, value)| value)
+    }
+
+    fn get_mut(&mut self, name: &str) -> Option<&mut Value> {
+        self.variables
+            .iter_mut()
+            .find(|(var, _)| var == name)
+            .map(|(_, value)| value)
+    }
+
+    fn set(&mut self, name: &str, value: Value) {
+        match self.get_mut(name) {
+            Some(slot) => *slot = value,
+            None => {
+                self.variables.push((name.to_string(), value));
+            }
+        }
+    }
+}
+
+/// What a statement asks the enclosing block to do next.
+enum Flow {
+    Next,
+    Break,
+    Continue,
+    Return(Value),
+}
+
+/// One step on the way from a variable to the element being assigned.
+enum Step {
+    Index(Value),
+    Field(String),
+}
+
+/// A basic step of work, reported to the observer as it happens.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operation<'a> {
+    /// Two values compared with `<`, `==` and the like.
+    Comparison,
+    /// A variable, element or field written, including a loop variable
+    /// taking its next value.
+    Assignment,
+    /// An array element read or written. `array` names the variable when the
+    /// element is indexed directly on it, as in `xs[i]`.
+    ArrayAccess {
+        array: Option<&'a str>,
+        index: usize,
+        write: bool,
+    },
+}
+
+/// Watches a running program. Returning an error from a hook stops the run
+/// with that error.
+pub trait Observer {
+    /// Called before each statement runs, and again at a loop's header
+    /// before every further iteration. `frames` is the call stack with the
+    /// running routine last.
+    fn statement(&mut self, line: usize, frames: &[Frame], output: &[String]) -> Result<()>;
+
+    /// Called when a routine finishes normally, before its frame is dropped.
+    fn exit(&mut self, _frames: &[Frame], _output: &[String]) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called for every comparison, assignment and array access.
+    fn operation(&mut self, _operation: Operation<'_>) {}
+
+    /// Called whenever a statement or loop iteration counts as a step, by
+    /// both the interpreter and the VM.
+    fn step(&mut self, _line: usize) {}
+}
+
+impl Observer for () {
+    fn statement(&mut self, _line: usize, _frames: &[Frame], _output: &[String]) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Runs a parsed program by walking its syntax tree.
+pub struct Interpreter<'a, O: Observer = ()> {
+    program: &'a Program,
+    functions: HashMap<&'a str, &'a Function>,
+    frames: Vec<Frame>,
+    /// Lines written by `print`.
+    pub output: Vec<String>,
+    pub observer: O,
+    pub random: Random,
+    pub limits: Limits,
+    /// Statements and loop iterations run so far.
+    steps: usize,
+}
+
+impl<'a, O: Observer> Interpreter<'a, O> {
+    pub fn with_observer(program: &'a Program, observer: O) -> Self {
+        Self {
+            program,
+            functions: program
+                .functions
+                .iter()
+                .map(|function| (function.name.as_str(), function))
+                .collect(),
+            frames: Vec::new(),
+            output: Vec::new(),
+            observer,
+            random: Random::seeded(),
+            limits: Limits::default(),
+            steps: 0,
+        }
+    }
+
+    /// Runs the `start` block and returns the value it returns, if any.
+    pub fn run(&mut self) -> Result<Option<Value>> {
+        let program = self.program;
+        self.frames.push(Frame::new("start"));
+        let flow = self.execute_routine(&program.start.body);
+        self.frames.pop();
+        match flow? {
+            Flow::Return(value) => Ok(Some(value)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Calls a function defined in the program, or a built-in one. Functions
+    /// in the program shadow built-ins of the same name.
+    pub fn call(&mut self, name: &str, args: Vec<Value>, line: usize) -> Result<Option<Value>> {
+        let Some(function) = self.functions.get(name).copied() else {
+            return self.call_builtin(name, args, line);
+        };
+
+        if self.frames.len() >= self.limits.max_depth {
+            return fail(
+                line,
+                format!(
+                    "recursion is deeper than {} calls in '{name}'",
+                    self.limits.max_depth
+                ),
+            );
+        }
+
+        if function.params.len() != args.len() {
+            return fail(
+                line,
+                format!(
+                    "'{name}' expects {} argument(s) but got {}",
+                    function.params.len(),
+                    args.len()
+                ),
+            );
+        }
+
+        let mut frame = Frame::new(name);
+        for (param, arg) in function.params.iter().zip(args) {
+            frame.set(param, arg);
+        }
+        self.frames.push(frame);
+        let flow = self.execute_routine(&function.body);
+        self.frames.pop();
+        match flow? {
+            Flow::Return(value) => Ok(Some(value)),
+            _ => Ok(None),
+        }
+    }
+
+    fn call_builtin(&mut self, name: &str, args: Vec<Value>, line: usize) -> Result<Option<Value>> {
+        let Some(builtin) = stdlib::builtin(name) else {
+            return fail(line, format!("unknown function '{name}'"));
+        };
+        call_builtin(
+            builtin,
+            args,
+            line,
+            &mut self.output,
+            &mut self.random,
+            &mut self.steps,
+            &self.limits,
+        )
+    }
+
+    /// Evaluates an expression typed by the user, such as an argument given
+    /// on the command line.
+    pub fn evaluate_input(&mut self, text: &str) -> Result<Value> {
+        let tokens = tokenize(text);
+        let (expression, rest) = Expression::parse(&tokens)?;
+        if let Some(extra) = rest.first() {
+            return fail(extra.line, format!("unexpected '{extra}' in '{text}'"));
+        }
+        self.evaluate_expression(&expression, 0)
+    }
+
+    /// Evaluates an expression outside of any routine, such as the call and
+    /// the expected value of a test.
+    pub fn evaluate_expression(&mut self, expression: &Expression, line: usize) -> Result<Value> {
+        self.frames.push(Frame::new("input"));
+        let value = self.evaluate(expression, line);
+        self.frames.pop();
+        value
+    }
+
+    fn execute_routine(&mut self, body: &'a [Node]) -> Result<Flow> {
+        let flow = self.execute_block(body)?;
+        self.observer.exit(&self.frames, &self.output)?;
+        Ok(flow)
+    }
+
+    fn frame(&mut self) -> &mut Frame {
+        self.frames
+            .last_mut()
+            .expect("the interpreter always runs inside a frame")
+    }
+
+    fn execute_block(&mut self, body: &'a [Node]) -> Result<Flow> {
+        for node in body {
+            match self.execute(node)? {
+                Flow::Next => {}
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    /// Counts a step against the limits and tells the observer about it.
+    fn notify(&mut self, line: usize) -> Result<()> {
+        count_step(&mut self.steps, &self.limits, line)?;
+        self.observer.step(line);
+        self.observer.statement(line, &self.frames, &self.output)
+    }
+
+    fn execute(&mut self, node: &'a Node) -> Result<Flow> {
+        let line = node.line();
+        if !matches!(node, Node::Comment(_)) {
+            self.notify(line)?;
+        }
+        match node {
+            Node::Assignment(val) => {
+                let value = self.evaluate(&val.value, line)?;
+                self.assign(&val.target, value, line)?;
+            }
+            Node::If(val) => {
+                if self.test(&val.condition, line)? {
+                    return self.execute_block(&val.body);
+                }
+                for branch in &val.else_ifs {
+                    if self.test(&branch.condition, line)? {
+                        return self.execute_block(&branch.body);
+                    }
+                }
+                if let Some(branch) = &val.else_branch {
+                    return self.execute_block(&branch.body);
+                }
+            }
+            Node::Loop(val) => match &val.loop_type {
+                LoopType::While(condition) => {
+                    let watched = condition.variables();
+                    // Without variables the condition is constant; a loop
+                    // like `while true` is left to the step limit.
+                    let can_leave = watched.is_empty() || exits(&val.body);
+                    let mut watch = (!can_leave).then(|| Watch::new(self.state()));
+                    while self.test(condition, line)? {
+                        match self.execute_block(&val.body)? {
+                            Flow::Break => break,
+                            Flow::Return(value) => return Ok(Flow::Return(value)),
+                            Flow::Next | Flow::Continue => {}
+                        }
+                        if let Some(watch) = &mut watch {
+                            watch.check(self.state(), line)?;
+                        }
+                        self.notify(line)?;
+                    }
+                }
+                LoopType::For { item, list } => {
+                    let items = match self.evaluate(list, line)? {
+                        Value::Array(items) => items,
+                        Value::String(text) => text
+                            .chars()
+                            .map(|ch| Value::String(ch.to_string()))
+                            .collect(),
+                        other => {
+                            return fail(line, format!("cannot loop over {}", other.type_name()));
+                        }
+                    };
+                    for (i, value) in items.into_iter().enumerate() {
+                        if i > 0 {
+                            self.notify(line)?;
+                        }
+                        self.observer.operation(Operation::Assignment);
+                        self.frame().set(item, value);
+                        match self.execute_block(&val.body)? {
+                            Flow::Break => break,
+                            Flow::Return(value) => return Ok(Flow::Return(value)),
+                            Flow::Next | Flow::Continue => {}
+                        }
+                    }
+                }
+            },
+            Node::FunctionCall(val) => {
+                let args = self.evaluate_all(&val.args, line)?;
+                self.call(&val.name, args, line)?;
+            }
+            Node::Return(val) => return Ok(Flow::Return(self.evaluate(&val.value, line)?)),
+            Node::Break(_) => return Ok(Flow::Break),
+            Node::Continue(_) => return Ok(Flow::Continue),
+            Node::Comment(_) => {}
+        }
+        Ok(Flow::Next)
+    }
+
+    /// Everything the rest of a loop in the current routine can depend on:
+    /// its variables, how much was printed and how many random numbers were
+    /// drawn.
+    fn state(&mut self) -> State {
+        let values = self
+            .frame()
+            .variables
+            .iter()
+            .map(|(_, value)| Some(value.clone()))
+            .collect();
+        (values, self.output.len(), self.random.draws)
+    }
+
+    fn test(&mut self, condition: &Condition, line: usize) -> Result<bool> {
+        match condition {
+            Condition::ComparisonOperator {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.evaluate(left, line)?;
+                let right = self.evaluate(right, line)?;
+                self.observer.operation(Operation::Comparison);
+                left.compare(operator, &right)
+                    .or_else(|message| fail(line, message))
+            }
+            Condition::LogicalOperator {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.test(left, line)?;
+                match operator.as_ref() {
+                    LogicalOperator::And if !left => Ok(false),
+                    LogicalOperator::Or if left => Ok(true),
+                    _ => self.test(right, line),
+                }
+            }
+            Condition::Value(value) => match self.evaluate(value, line)? {
+                Value::Bool(value) => Ok(value),
+                other => fail(
+                    line,
+                    format!(
+                        "condition '{value}' is {}, expected bool",
+                        other.type_name()
+                    ),
+                ),
+            },
+        }
+    }
+
+    fn evaluate_all(&mut self, expressions: &[Expression], line: usize) -> Result<Vec<Value>> {
+        expressions
+            .iter()
+            .map(|expression| self.evaluate(expression, line))
+            .collect()
+    }
+
+    fn evaluate(&mut self, expression: &Expression, line: usize) -> Result<Value> {
+        match expression {
+            Expression::Literal(text) => match Value::from_literal(text) {
+                Some(value) => Ok(value),
+                None => fail(line, format!("invalid literal '{text}'")),
+            },
+            Expression::Variable(name) => match self.frame().get(name) {
+                Some(value) => Ok(value.clone()),
+                None => fail(line, format!("'{name}' is not defined")),
+            },
+            Expression::BinaryOp {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.evaluate(left, line)?;
+                let right = self.evaluate(right, line)?;
+                left.arithmetic(operator, &right)
+                    .or_else(|message| fail(line, message))
+            }
+            Expression::FunctionCall { name, args } => {
+                let args = self.evaluate_all(args, line)?;
+                match self.call(name, args, line)? {
+                    Some(value) => Ok(value),
+                    None => fail(line, format!("'{name}' does not return a value")),
+                }
+            }
+            Expression::Not(inner) => match self.evaluate(inner, line)? {
+                Value::Bool(value) => Ok(Value::Bool(!value)),
+                other => fail(
+                    line,
+                    format!("'!' expects bool but found {}", other.type_name()),
+                ),
+            },
+            Expression::Array(items) => Ok(Value::Array(self.evaluate_all(items, line)?)),
+            Expression::Record(fields) => {
+                let mut values = Vec::new();
+                for (name, value) in fields {
+                    values.push((name.clone(), self.evaluate(value, line)?));
+                }
+                Ok(Value::Record(values))
+            }
+            Expression::Index { target, index } => {
+                let array = match target.as_ref() {
+                    Expression::Variable(name) => Some(name.as_str()),
+                    _ => None,
+                };
+                let target = self.evaluate(target, line)?;
+                let index = self.evaluate(index, line)?;
+                match target {
+                    Value::Array(items) => {
+                        let position = position(&index, items.len(), line)?;
+                        self.observer.operation(Operation::ArrayAccess {
+                            array,
+                            index: position,
+                            write: false,
+                        });
+                        Ok(items[position].clone())
+                    }
+                    Value::String(text) => {
+                        let chars: Vec<char> = text.chars().collect();
+                        let position = position(&index, chars.len(), line)?;
+                        Ok(Value::String(chars[position].to_string()))
+                    }
+                    other => fail(line, format!("cannot index into {}", other.type_name())),
+                }
+            }
+            Expression::Field { target, name } => match self.evaluate(target, line)? {
+                Value::Record(fields) => {
+                    match fields.into_iter().find(|(field, _)| field == name) {
+                        Some((_, value)) => Ok(value),
+                        None => fail(line, format!("record has no field '{name}'")),
+                    }
+                }
+                other => fail(line, format!("{} has no field '{name}'", other.type_name())),
+            },
+        }
+    }
+
+    fn assign(&mut self, target: &Expression, value: Value, line: usize) -> Result<()> {
+        let mut steps = Vec::new();
+        let mut root = target;
+        let name = loop {
+            match root {
+                Expression::Variable(name) => break name,
+                Expression::Index { target, index } => {
+                    steps.push(Step::Index(self.evaluate(index, line)?));
+                    root = target;
+                }
+                Expression::Field { target, name } => {
+                    steps.push(Step::Field(name.clone()));
+                    root = target;
+                }
+                _ => return fail(line, format!("cannot assign to '{target}'")),
+            }
+        };
+
+        self.observer.operation(Operation::Assignment);
+        if steps.is_empty() {
+            self.frame().set(name, value);
+            return Ok(());
+        }
+
+        let direct = steps.len() == 1;
+        let Some(mut slot) = self.frames.last_mut().and_then(|frame| frame.get_mut(name)) else {
+            return fail(line, format!("'{name}' is not defined"));
+        };
+        for step in steps.iter().rev() {
+            slot = match (slot, step) {
+                (Value::Array(items), Step::Index(index)) => {
+                    let position = position(index, items.len(), line)?;
+                    self.observer.operation(Operation::ArrayAccess {
+                        array: direct.then_some(name.as_str()),
+                        index: position,
+                        write: true,
+                    });
+                    &mut items[position]
+                }
+                (Value::Record(fields), Step::Field(name)) => {
+                    match fields.iter_mut().find(|(field, _)| field == name) {
+                        Some((_, value)) => value,
+                        None => return fail(line, format!("record has no field '{name}'")),
+                    }
+                }
+                (other, _) => {
+                    return fail(line, format!("cannot assign into {}", other.type_name()));
+                }
+            };
+        }
+        *slot = value;
+        Ok(())
+    }
+}
+
+/// Checks that `index` is an int pointing inside a sequence of `len`
+/// items.
+fn position(index: &Value, len: usize, line: usize) -> Result<usize> {
+    let Value::Int(index) = index else {
+        return fail(
+            line,
+            format!("index must be int but found {}", index.type_name()),
+        );
+    };
+    match usize::try_from(*index) {
+        Ok(position) if position < len => Ok(position),
+        _ => fail(
+            line,
+            format!("index {index} is out of bounds for length {len}"),
+        ),
+    }
+}
+
+/// Counts one more step and fails once the limit is passed or the user
+/// interrupted the run.
+fn count_step(steps: &mut usize, limits: &Limits, line: usize) -> Result<()> {
+    *steps += 1;
+    if *steps > limits.max_steps {
+        return out_of_steps(limits, line);
+    }
+    if steps.is_multiple_of(Limits::POLL_INTERVAL)
+        && limits.interrupt.is_some_and(|interrupt| interrupt())
+    {
+        return fail(line, "stopped by key press");
+    }
+    Ok(())
+}
+
+fn out_of_steps<T>(limits: &Limits, line: usize) -> Result<T> {
+    fail(
+        line,
+        format!(
+            "stopped after {} steps, the program may not terminate",
+            limits.max_steps
+        ),
+    )
+}
+
+/// Checks the argument count of a built-in and runs it, writing to `output`
+/// for `print`. A `range` costs a step per item, paid before it is built so
+/// a huge one fails the step limit instead of running out of memory.
+fn call_builtin(
+    builtin: &Builtin,
+    args: Vec<Value>,
+    line: usize,
+    output: &mut Vec<String>,
+    random: &mut Random,
+    steps: &mut usize,
+    limits: &Limits,
+) -> Result<Option<Value>> {
+    if !builtin.accepts_count(args.len()) {
+        return fail(
+            line,
+            format!(
+                "'{}' expects {} argument(s) but got {}",
+                builtin.name,
+                builtin.arity(),
+                args.len()
+            ),
+        );
+    }
+    if builtin.name == "range"
+        && let [Value::Int(from), Value::Int(to)] = args.as_slice()
+    {
+        let length = usize::try_from(to.saturating_sub(*from)).unwrap_or(0);
+        *steps = steps.saturating_add(length);
+        if *steps > limits.max_steps {
+            return out_of_steps(limits, line);
+        }
+    }
+    if builtin.name == "print" {
+        let text: Vec<String> = args.iter().map(Value::to_output).collect();
+        output.push(text.join(" "));
+        return Ok(None);
+    }
+    builtins::apply(builtin, args, random)
+        .map(Some)
+        .or_else(|message| fail(line, message))
+}
+
+/// The values of a routine's variables, how many lines were printed and how
+/// many random numbers were drawn.
+type State = (Vec<Option<Value>>, usize, usize);
+
+/// Catches a `while` loop that comes back to a state it was in before, which
+/// means it runs forever. Brent's method keeps one earlier state, replaced
+/// at iterations 1, 2, 4, 8 and so on, so a cycle of any length is caught
+/// without remembering every state: `let i = i` on the first iteration and
+/// `let x = (x + 1) % 3` a few iterations later.
+struct Watch {
+    saved: State,
+    iterations: usize,
+}
+
+impl Watch {
+    fn new(state: State) -> Self {
+        Self {
+            saved: state,
+            iterations: 0,
+        }
+    }
+
+    /// Fails when `state`, reached at the end of an iteration, was seen
+    /// before.
+    fn check(&mut self, state: State, line: usize) -> Result<()> {
+        if state == self.saved {
+            return fail(
+                line,
+                format!(
+                    "loop at line {} does not change any variable in its condition",
+                    line + 1
+                ),
+            );
+        }
+        self.iterations += 1;
+        if self.iterations.is_power_of_two() {
+            self.saved = state;
+        }
+        Ok(())
+    }
+}
+
+/// Whether a loop body contains a `break` or `return` that could end the
+/// loop some other way than through its condition.
+fn exits(body: &[Node]) -> bool {
+    let mut found = false;
+    walk(body, Scope::default(), &mut |node, _| {
+        found |= matches!(node, Node::Break(_) | Node::Return(_));
+    });
+    found
+}
@@ -0,0 +1,215 @@
+use core::fmt;
+
+use crate::models::nodes::operator::{ArithmeticOperator, ComparisonOperator};
+
+/// A runtime value. Arrays and records are copied on assignment.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Real(f64),
+    String(String),
+    Bool(bool),
+    Array(Vec<Value>),
+    Record(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn from_literal(text: &str) -> Option<Value> {
+        if let Some(text) = text.strip_prefix('"') {
+            return Some(Value::String(text.strip_suffix('"')?.to_string()));
+        }
+        match text {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => text
+                .parse()
+                .map(Value::Int)
+                .or_else(|_| text.parse().map(Value::Real))
+                .ok(),
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "int",
+            Value::Real(_) => "real",
+            Value::String(_) => "string",
+            Value::Bool(_) => "bool",
+            Value::Array(_) => "array",
+            Value::Record(_) => "record",
+        }
+    }
+
+    pub fn as_real(&self) -> Option<f64> {
+        match self {
+            Value::Int(value) => Some(*value as f64),
+            Value::Real(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// How many values this one is made of, itself included, as a measure
+    /// of what a copy of it costs.
+    pub fn size(&self) -> usize {
+        match self {
+            Value::Array(items) => 1 + items.iter().map(Value::size).sum::<usize>(),
+            Value::Record(fields) => {
+                1 + fields.iter().map(|(_, value)| value.size()).sum::<usize>()
+            }
+            _ => 1,
+        }
+    }
+
+    /// The text `print` writes: like [`fmt::Display`] but without quotes
+    /// around a top-level string.
+    pub fn to_output(&self) -> String {
+        match self {
+            Value::String(value) => value.clone(),
+            other => other.to_string(),
+        }
+    }
+
+    /// Applies an arithmetic operator. Two ints stay an int, so `/` divides
+    /// without a remainder; any real operand makes the result real.
+    pub fn arithmetic(
+        &self,
+        operator: &ArithmeticOperator,
+        other: &Value,
+    ) -> Result<Value, String> {
+        let mismatch = || {
+            format!(
+                "cannot apply '{operator}' to {} and {}",
+                self.type_name(),
+                other.type_name()
+            )
+        };
+
+        match (self, other) {
+            (Value::Int(left), Value::Int(right)) => {
+                let (left, right) = (*left, *right);
+                if right == 0
+                    && matches!(
+                        operator,
+                        ArithmeticOperator::Division | ArithmeticOperator::Modulo
+                    )
+                {
+                    return Err("division by zero".to_string());
+                }
+                let result = match operator {
+                    ArithmeticOperator::Addition => left.checked_add(right),
+                    ArithmeticOperator::Subtraction => left.checked_sub(right),
+                    ArithmeticOperator::Multiplication => left.checked_mul(right),
+                    ArithmeticOperator::Division => left.checked_div(right),
+                    ArithmeticOperator::Modulo => left.checked_rem(right),
+                };
+                result
+                    .map(Value::Int)
+                    .ok_or_else(|| "integer overflow".to_string())
+            }
+            (Value::String(left), Value::String(right))
+                if matches!(operator, ArithmeticOperator::Addition) =>
+            {
+                Ok(Value::String(format!("{left}{right}")))
+            }
+            (Value::Array(left), Value::Array(right))
+                if matches!(operator, ArithmeticOperator::Addition) =>
+            {
+                Ok(Value::Array(left.iter().chain(right).cloned().collect()))
+            }
+            _ => {
+                let (Some(left), Some(right)) = (self.as_real(), other.as_real()) else {
+                    return Err(mismatch());
+                };
+                if right == 0.0
+                    && matches!(
+                        operator,
+                        ArithmeticOperator::Division | ArithmeticOperator::Modulo
+                    )
+                {
+                    return Err("division by zero".to_string());
+                }
+                Ok(Value::Real(match operator {
+                    ArithmeticOperator::Addition => left + right,
+                    ArithmeticOperator::Subtraction => left - right,
+                    ArithmeticOperator::Multiplication => left * right,
+                    ArithmeticOperator::Division => left / right,
+                    ArithmeticOperator::Modulo => left % right,
+                }))
+            }
+        }
+    }
+
+    /// Compares two values. Ints and reals compare by value, strings in
+    /// alphabetical order, and everything else only for equality.
+    pub fn compare(&self, operator: &ComparisonOperator, other: &Value) -> Result<bool, String> {
+        // Two ints compare exactly; going through `f64` would make ints past
+        // 2^53 equal to their neighbours.
+        let ordering = match (self, other, self.as_real(), other.as_real()) {
+            (Value::Int(left), Value::Int(right), _, _) => Some(left.cmp(right)),
+            (_, _, Some(left), Some(right)) => left.partial_cmp(&right),
+            _ => match (self, other) {
+                (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+                _ if self.type_name() == other.type_name() => None,
+                _ => {
+                    return Err(format!(
+                        "cannot compare {} with {}",
+                        self.type_name(),
+                        other.type_name()
+                    ));
+                }
+            },
+        };
+
+        match (operator, ordering) {
+            (ComparisonOperator::Eq, _) => Ok(ordering.map_or(self == other, |o| o.is_eq())),
+            (ComparisonOperator::Neq, _) => Ok(ordering.map_or(self != other, |o| o.is_ne())),
+            (_, None) => Err(format!(
+                "cannot order values of type {} with '{operator}'",
+                self.type_name()
+            )),
+            (ComparisonOperator::Gt, Some(ordering)) => Ok(ordering.is_gt()),
+            (ComparisonOperator::Lt, Some(ordering)) => Ok(ordering.is_lt()),
+            (ComparisonOperator::Gte, Some(ordering)) => Ok(ordering.is_ge()),
+            (ComparisonOperator::Lte, Some(ordering)) => Ok(ordering.is_le()),
+        }
+    }
+}
+
+fn write_list<T: fmt::Display>(f: &mut fmt::Formatter<'_>, items: &[T]) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{item}")?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(value) => write!(f, "{value}"),
+            Value::Real(value) if value.fract() == 0.0 && value.is_finite() => {
+                write!(f, "{value:.1}")
+            }
+            Value::Real(value) => write!(f, "{value}"),
+            Value::String(value) => write!(f, "\"{value}\""),
+            Value::Bool(value) => write!(f, "{value}"),
+            Value::Array(items) => {
+                write!(f, "[")?;
+                write_list(f, items)?;
+                write!(f, "]")
+            }
+            Value::Record(fields) => {
+                write!(f, "{{")?;
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{name}: {value}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
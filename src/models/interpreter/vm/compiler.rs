@@ -0,0 +1,484 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::models::{
+    interpreter::{exits, value::Value},
+    nodes::{
+        condition::Condition,
+        expression::Expression,
+        loops::LoopType,
+        node::Node,
+        operator::{ArithmeticOperator, ComparisonOperator, LogicalOperator},
+        program::Program,
+    },
+    stdlib::{self, Builtin},
+};
+
+/// One step on the way from a variable to the element being assigned.
+pub enum Path {
+    /// An index, taken from the stack.
+    Index,
+    Field(String),
+}
+
+/// A single VM instruction. Jump targets are positions in the same chunk.
+pub enum Instruction {
+    /// Counts a statement against the limits.
+    Step,
+    Push(Value),
+    Load(usize),
+    /// Fails unless the variable is defined, without loading it.
+    Require(usize),
+    Store(usize),
+    /// Pops the indices of `path`, then the value, and writes the value into
+    /// an element or field of the variable.
+    StoreInto {
+        slot: usize,
+        path: Vec<Path>,
+    },
+    Arithmetic(ArithmeticOperator),
+    Compare(ComparisonOperator),
+    Not,
+    /// Checks that a condition made of a single expression, shown here as
+    /// text, is a bool.
+    Truth(String),
+    Jump(usize),
+    /// Pops a bool and jumps when it is false.
+    JumpIfFalse(usize),
+    /// Pops a bool and jumps when it is true.
+    JumpIfTrue(usize),
+    Array(usize),
+    Record(Vec<String>),
+    /// Pops an index and indexes the value below it.
+    Index,
+    /// Pops an index and indexes the variable in place, without copying it.
+    IndexVariable(usize),
+    Field(String),
+    /// Calls a function of the program. `want` says whether its value is
+    /// used, which makes returning nothing an error.
+    Call {
+        function: usize,
+        args: usize,
+        want: bool,
+    },
+    CallBuiltin {
+        builtin: &'static Builtin,
+        args: usize,
+        want: bool,
+    },
+    Fail(String),
+    Return,
+    ReturnNothing,
+    /// Pops the list of a `for` loop and starts iterating over it.
+    Iterate,
+    /// Assigns the next item to the loop variable, or ends the loop and
+    /// jumps to `exit` when there is none.
+    Next {
+        slot: usize,
+        exit: usize,
+    },
+    /// Drops the innermost `for` loop after a `break`.
+    EndIterate,
+    /// Starts watching a `while` loop for a state that repeats.
+    Watch,
+    /// Fails when the routine is back in a state seen since `Watch`.
+    Check,
+    /// Stops watching once the loop is left.
+    Unwatch,
+}
+
+/// The compiled body of a function, the `start` block or an expression.
+pub struct Chunk {
+    pub name: String,
+    /// The slot of each parameter, in order.
+    pub params: Vec<usize>,
+    /// Variable names by slot.
+    pub names: Vec<String>,
+    pub code: Vec<Instruction>,
+    /// The source line of each instruction, for errors.
+    pub lines: Vec<usize>,
+}
+
+/// Every routine of a program, compiled.
+pub struct Code {
+    pub start: Rc<Chunk>,
+    pub functions: Vec<Rc<Chunk>>,
+    /// Function positions by name. Later definitions replace earlier ones.
+    pub index: HashMap<String, usize>,
+}
+
+impl Code {
+    pub fn new(program: &Program) -> Self {
+        let index: HashMap<String, usize> = program
+            .functions
+            .iter()
+            .enumerate()
+            .map(|(i, function)| (function.name.clone(), i))
+            .collect();
+        let functions = program
+            .functions
+            .iter()
+            .map(|function| {
+                let mut compiler = Compiler::new(&function.name, &index);
+                let params = function
+                    .params
+                    .iter()
+                    .map(|param| compiler.slot(param))
+                    .collect();
+                compiler.chunk.params = params;
+                compiler.block(&function.body);
+                compiler.emit(Instruction::ReturnNothing, function.line);
+                Rc::new(compiler.chunk)
+            })
+            .collect();
+
+        let mut compiler = Compiler::new("start", &index);
+        compiler.block(&program.start.body);
+        compiler.emit(Instruction::ReturnNothing, program.start.line);
+        Self {
+            start: Rc::new(compiler.chunk),
+            functions,
+            index,
+        }
+    }
+
+    /// Compiles an expression evaluated outside of any routine.
+    pub fn expression(&self, expression: &Expression, line: usize) -> Chunk {
+        let mut compiler = Compiler::new("input", &self.index);
+        compiler.expression(expression, line);
+        compiler.emit(Instruction::Return, line);
+        compiler.chunk
+    }
+}
+
+/// Jumps out of the innermost loop, patched once its end is known.
+#[derive(Default)]
+struct Exits {
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
+}
+
+struct Compiler<'a> {
+    functions: &'a HashMap<String, usize>,
+    chunk: Chunk,
+    loops: Vec<Exits>,
+}
+
+impl<'a> Compiler<'a> {
+    fn new(name: &str, functions: &'a HashMap<String, usize>) -> Self {
+        Self {
+            functions,
+            chunk: Chunk {
+                name: name.to_string(),
+                params: Vec::new(),
+                names: Vec::new(),
+                code: Vec::new(),
+                lines: Vec::new(),
+            },
+            loops: Vec::new(),
+        }
+    }
+
+    fn slot(&mut self, name: &str) -> usize {
+        match self.chunk.names.iter().position(|var| var == name) {
+            Some(slot) => slot,
+            None => {
+                self.chunk.names.push(name.to_string());
+                self.chunk.names.len() - 1
+            }
+        }
+    }
+
+    fn emit(&mut self, instruction: Instruction, line: usize) -> usize {
+        self.chunk.code.push(instruction);
+        self.chunk.lines.push(line);
+        self.chunk.code.len() - 1
+    }
+
+    fn here(&self) -> usize {
+        self.chunk.code.len()
+    }
+
+    /// Points the jump at `at` to `target`.
+    fn patch(&mut self, at: usize, target: usize) {
+        match &mut self.chunk.code[at] {
+            Instruction::Jump(to) | Instruction::JumpIfFalse(to) | Instruction::JumpIfTrue(to) => {
+                *to = target
+            }
+            Instruction::Next { exit, .. } => *exit = target,
+            _ => unreachable!("only jumps are patched"),
+        }
+    }
+
+    fn block(&mut self, body: &[Node]) {
+        for node in body {
+            self.statement(node);
+        }
+    }
+
+    fn statement(&mut self, node: &Node) {
+        let line = node.line();
+        if !matches!(node, Node::Comment(_)) {
+            self.emit(Instruction::Step, line);
+        }
+        match node {
+            Node::Assignment(val) => {
+                self.expression(&val.value, line);
+                self.assign(&val.target, line);
+            }
+            Node::If(val) => {
+                let mut ends = Vec::new();
+                let branches = std::iter::once((&val.condition, &val.body)).chain(
+                    val.else_ifs
+                        .iter()
+                        .map(|branch| (&branch.condition, &branch.body)),
+                );
+                for (condition, body) in branches {
+                    self.condition(condition, line);
+                    let skip = self.emit(Instruction::JumpIfFalse(0), line);
+                    self.block(body);
+                    ends.push(self.emit(Instruction::Jump(0), line));
+                    let next = self.here();
+                    self.patch(skip, next);
+                }
+                if let Some(branch) = &val.else_branch {
+                    self.block(&branch.body);
+                }
+                let end = self.here();
+                for at in ends {
+                    self.patch(at, end);
+                }
+            }
+            Node::Loop(val) => match &val.loop_type {
+                LoopType::While(condition) => {
+                    // Mirrors the interpreter: a loop that can only be left
+                    // through its condition fails once the routine comes
+                    // back to a state it was in before.
+                    let watched = condition.variables();
+                    let watched = !watched.is_empty() && !exits(&val.body);
+                    if watched {
+                        self.emit(Instruction::Watch, line);
+                    }
+                    let top = self.here();
+                    self.condition(condition, line);
+                    let leave = self.emit(Instruction::JumpIfFalse(0), line);
+                    self.loops.push(Exits::default());
+                    self.block(&val.body);
+                    let exits = self.loops.pop().unwrap_or_default();
+                    let next = self.here();
+                    if watched {
+                        self.emit(Instruction::Check, line);
+                    }
+                    self.emit(Instruction::Step, line);
+                    self.emit(Instruction::Jump(top), line);
+                    let end = self.here();
+                    if watched {
+                        self.emit(Instruction::Unwatch, line);
+                    }
+                    self.patch(leave, end);
+                    for at in exits.continues {
+                        self.patch(at, next);
+                    }
+                    for at in exits.breaks {
+                        self.patch(at, end);
+                    }
+                }
+                LoopType::For { item, list } => {
+                    self.expression(list, line);
+                    self.emit(Instruction::Iterate, line);
+                    let slot = self.slot(item);
+                    let top = self.emit(Instruction::Next { slot, exit: 0 }, line);
+                    self.loops.push(Exits::default());
+                    self.block(&val.body);
+                    let exits = self.loops.pop().unwrap_or_default();
+                    self.emit(Instruction::Jump(top), line);
+                    let broken = self.emit(Instruction::EndIterate, line);
+                    let end = self.here();
+                    self.patch(top, end);
+                    for at in exits.continues {
+                        self.patch(at, top);
+                    }
+                    for at in exits.breaks {
+                        self.patch(at, broken);
+                    }
+                }
+            },
+            Node::FunctionCall(val) => self.call(&val.name, &val.args, false, line),
+            Node::Return(val) => {
+                self.expression(&val.value, line);
+                self.emit(Instruction::Return, line);
+            }
+            // Outside of a loop, `break` and `continue` end the routine.
+            Node::Break(_) => match self.loops.len() {
+                0 => {
+                    self.emit(Instruction::ReturnNothing, line);
+                }
+                _ => {
+                    let at = self.emit(Instruction::Jump(0), line);
+                    self.loops.last_mut().unwrap().breaks.push(at);
+                }
+            },
+            Node::Continue(_) => match self.loops.len() {
+                0 => {
+                    self.emit(Instruction::ReturnNothing, line);
+                }
+                _ => {
+                    let at = self.emit(Instruction::Jump(0), line);
+                    self.loops.last_mut().unwrap().continues.push(at);
+                }
+            },
+            Node::Comment(_) => {}
+        }
+    }
+
+    /// Leaves a bool on the stack, skipping the right side of `&&` and `||`
+    /// when the left one decides.
+    fn condition(&mut self, condition: &Condition, line: usize) {
+        match condition {
+            Condition::ComparisonOperator {
+                left,
+                operator,
+                right,
+            } => {
+                self.expression(left, line);
+                self.expression(right, line);
+                self.emit(Instruction::Compare(**operator), line);
+            }
+            Condition::LogicalOperator {
+                left,
+                operator,
+                right,
+            } => {
+                self.condition(left, line);
+                let (decided, value) = match operator.as_ref() {
+                    LogicalOperator::And => (Instruction::JumpIfFalse(0), false),
+                    LogicalOperator::Or => (Instruction::JumpIfTrue(0), true),
+                };
+                let short = self.emit(decided, line);
+                self.condition(right, line);
+                let done = self.emit(Instruction::Jump(0), line);
+                let decided = self.emit(Instruction::Push(Value::Bool(value)), line);
+                self.patch(short, decided);
+                let end = self.here();
+                self.patch(done, end);
+            }
+            Condition::Value(value) => {
+                self.expression(value, line);
+                self.emit(Instruction::Truth(value.to_string()), line);
+            }
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression, line: usize) {
+        match expression {
+            Expression::Literal(text) => {
+                let instruction = match Value::from_literal(text) {
+                    Some(value) => Instruction::Push(value),
+                    None => Instruction::Fail(format!("invalid literal '{text}'")),
+                };
+                self.emit(instruction, line);
+            }
+            Expression::Variable(name) => {
+                let slot = self.slot(name);
+                self.emit(Instruction::Load(slot), line);
+            }
+            Expression::BinaryOp {
+                left,
+                operator,
+                right,
+            } => {
+                self.expression(left, line);
+                self.expression(right, line);
+                self.emit(Instruction::Arithmetic(**operator), line);
+            }
+            Expression::FunctionCall { name, args } => self.call(name, args, true, line),
+            Expression::Not(inner) => {
+                self.expression(inner, line);
+                self.emit(Instruction::Not, line);
+            }
+            Expression::Array(items) => {
+                for item in items {
+                    self.expression(item, line);
+                }
+                self.emit(Instruction::Array(items.len()), line);
+            }
+            Expression::Record(fields) => {
+                for (_, value) in fields {
+                    self.expression(value, line);
+                }
+                let names = fields.iter().map(|(name, _)| name.clone()).collect();
+                self.emit(Instruction::Record(names), line);
+            }
+            Expression::Index { target, index } => match target.as_ref() {
+                Expression::Variable(name) => {
+                    let slot = self.slot(name);
+                    self.emit(Instruction::Require(slot), line);
+                    self.expression(index, line);
+                    self.emit(Instruction::IndexVariable(slot), line);
+                }
+                target => {
+                    self.expression(target, line);
+                    self.expression(index, line);
+                    self.emit(Instruction::Index, line);
+                }
+            },
+            Expression::Field { target, name } => {
+                self.expression(target, line);
+                self.emit(Instruction::Field(name.clone()), line);
+            }
+        }
+    }
+
+    fn call(&mut self, name: &str, args: &[Expression], want: bool, line: usize) {
+        for arg in args {
+            self.expression(arg, line);
+        }
+        let instruction = match (self.functions.get(name), stdlib::builtin(name)) {
+            (Some(&function), _) => Instruction::Call {
+                function,
+                args: args.len(),
+                want,
+            },
+            (None, Some(builtin)) => Instruction::CallBuiltin {
+                builtin,
+                args: args.len(),
+                want,
+            },
+            (None, None) => Instruction::Fail(format!("unknown function '{name}'")),
+        };
+        self.emit(instruction, line);
+    }
+
+    /// Expects the value on the stack. Indices are evaluated from the
+    /// outermost one inwards, as the interpreter does.
+    fn assign(&mut self, target: &Expression, line: usize) {
+        let mut path = Vec::new();
+        let mut root = target;
+        let name = loop {
+            match root {
+                Expression::Variable(name) => break name,
+                Expression::Index { target, index } => {
+                    self.expression(index, line);
+                    path.push(Path::Index);
+                    root = target;
+                }
+                Expression::Field { target, name } => {
+                    path.push(Path::Field(name.clone()));
+                    root = target;
+                }
+                _ => {
+                    self.emit(
+                        Instruction::Fail(format!("cannot assign to '{target}'")),
+                        line,
+                    );
+                    return;
+                }
+            }
+        };
+        let slot = self.slot(name);
+        if path.is_empty() {
+            self.emit(Instruction::Store(slot), line);
+        } else {
+            self.emit(Instruction::StoreInto { slot, path }, line);
+        }
+    }
+}
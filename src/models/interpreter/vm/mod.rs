@@ -0,0 +1,820 @@
+pub mod compiler;
+
+use std::{rc::Rc, vec};
+
+use color_eyre::eyre::Result;
+
+use crate::models::{
+    interpreter::{
+        Observer, Operation, State, Watch,
+        builtins::Random,
+        call_builtin, count_step, fail,
+        limits::Limits,
+        position,
+        value::Value,
+        vm::compiler::{Chunk, Code, Instruction, Path},
+    },
+    nodes::{
+        expression::Expression,
+        parsing::{Parsable, tokenize},
+        program::Program,
+    },
+    stdlib,
+};
+
+/// The variables of one running routine, by slot.
+struct Frame {
+    chunk: Rc<Chunk>,
+    /// The next instruction to run.
+    pc: usize,
+    slots: Vec<Option<Value>>,
+    /// The items left in each running `for` loop, innermost last, and
+    /// whether the loop has taken its first item.
+    loops: Vec<(vec::IntoIter<Value>, bool)>,
+    /// What each running `while` loop watches, innermost last.
+    watches: Vec<Watch>,
+    /// Whether the caller uses the value this routine returns.
+    want: bool,
+}
+
+impl Frame {
+    fn new(chunk: Rc<Chunk>, want: bool) -> Self {
+        let count = chunk.names.len();
+        Self {
+            chunk,
+            pc: 0,
+            slots: vec![None; count],
+            loops: Vec::new(),
+            watches: Vec::new(),
+            want,
+        }
+    }
+
+    fn set(&mut self, slot: usize, value: Value) {
+        self.slots[slot] = Some(value);
+    }
+}
+
+/// Runs a program compiled to bytecode on a stack machine. Gives the same
+/// output, values and errors as the [`Interpreter`](super::Interpreter), and
+/// takes the same steps, but resolves variables to slots up front and reads
+/// array elements without copying the array.
+///
+/// The observer hears about steps and operations only; there are no named
+/// frames to show it at each statement.
+pub struct Vm<O: Observer = ()> {
+    code: Code,
+    frames: Vec<Frame>,
+    stack: Vec<Value>,
+    /// Lines written by `print`.
+    pub output: Vec<String>,
+    pub observer: O,
+    pub random: Random,
+    pub limits: Limits,
+    /// Statements and loop iterations run so far.
+    steps: usize,
+}
+
+impl Vm {
+    pub fn new(program: &Program) -> Self {
+        Self::with_observer(program, ())
+    }
+}
+
+impl<O: Observer> Vm<O> {
+    pub fn with_observer(program: &Program, observer: O) -> Self {
+        Self {
+            code: Code::new(program),
+            frames: Vec::new(),
+            stack: Vec::new(),
+            output: Vec::new(),
+            observer,
+            random: Random::seeded(),
+            limits: Limits::default(),
+            steps: 0,
+        }
+    }
+
+    /// Runs the `start` block and returns the value it returns, if any.
+    pub fn run(&mut self) -> Result<Option<Value>> {
+        let base = self.frames.len();
+        self.frames
+            .push(Frame::new(Rc::clone(&self.code.start), false));
+        self.execute(base)
+    }
+
+    /// Calls a function defined in the program, or a built-in one. Functions
+    /// in the program shadow built-ins of the same name.
+    pub fn call(&mut self, name: &str, args: Vec<Value>, line: usize) -> Result<Option<Value>> {
+        let Some(&function) = self.code.index.get(name) else {
+            let Some(builtin) = stdlib::builtin(name) else {
+                return fail(line, format!("unknown function '{name}'"));
+            };
+            return call_builtin(
+                builtin,
+                args,
+                line,
+                &mut self.output,
+                &mut self.random,
+                &mut self.steps,
+                &self.limits,
+            );
+        };
+        let base = self.frames.len();
+        self.enter(function, args, false, line)?;
+        self.execute(base)
+    }
+
+    /// Evaluates an expression typed by the user, such as an argument given
+    /// on the command line.
+    pub fn evaluate_input(&mut self, text: &str) -> Result<Value> {
+        let tokens = tokenize(text);
+        let (expression, rest) = Expression::parse(&tokens)?;
+        if let Some(extra) = rest.first() {
+            return fail(extra.line, format!("unexpected '{extra}' in '{text}'"));
+        }
+        self.evaluate_expression(&expression, 0)
+    }
+
+    /// Evaluates an expression outside of any routine, such as the call and
+    /// the expected value of a test.
+    pub fn evaluate_expression(&mut self, expression: &Expression, line: usize) -> Result<Value> {
+        let chunk = Rc::new(self.code.expression(expression, line));
+        let base = self.frames.len();
+        self.frames.push(Frame::new(chunk, true));
+        let value = self.execute(base)?;
+        Ok(value.expect("an expression always returns a value"))
+    }
+
+    fn frame(&mut self) -> &mut Frame {
+        self.frames
+            .last_mut()
+            .expect("the VM always runs inside a frame")
+    }
+
+    /// The values of the current routine's slots, how much was printed and
+    /// how many random numbers were drawn, as the interpreter's `state`
+    /// gives them.
+    fn state(&mut self) -> State {
+        let printed = self.output.len();
+        let draws = self.random.draws;
+        (self.frame().slots.clone(), printed, draws)
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack
+            .pop()
+            .expect("instructions only pop what was pushed")
+    }
+
+    fn pop_bool(&mut self) -> bool {
+        match self.pop() {
+            Value::Bool(value) => value,
+            _ => unreachable!("conditions always leave a bool"),
+        }
+    }
+
+    fn variable(&self, slot: usize, line: usize) -> Result<&Value> {
+        let frame = self
+            .frames
+            .last()
+            .expect("the VM always runs inside a frame");
+        match &frame.slots[slot] {
+            Some(value) => Ok(value),
+            None => fail(
+                line,
+                format!("'{}' is not defined", frame.chunk.names[slot]),
+            ),
+        }
+    }
+
+    /// Pushes the frame of a function call after the same checks as the
+    /// interpreter makes.
+    fn enter(&mut self, function: usize, args: Vec<Value>, want: bool, line: usize) -> Result<()> {
+        let chunk = Rc::clone(&self.code.functions[function]);
+        if self.frames.len() >= self.limits.max_depth {
+            return fail(
+                line,
+                format!(
+                    "recursion is deeper than {} calls in '{}'",
+                    self.limits.max_depth, chunk.name
+                ),
+            );
+        }
+        if chunk.params.len() != args.len() {
+            return fail(
+                line,
+                format!(
+                    "'{}' expects {} argument(s) but got {}",
+                    chunk.name,
+                    chunk.params.len(),
+                    args.len()
+                ),
+            );
+        }
+
+        let mut frame = Frame::new(chunk, want);
+        for (i, arg) in args.into_iter().enumerate() {
+            frame.set(frame.chunk.params[i], arg);
+        }
+        self.frames.push(frame);
+        Ok(())
+    }
+
+    /// Runs until the frame at `base` returns, and cleans up after an error
+    /// so the VM can be used again.
+    fn execute(&mut self, base: usize) -> Result<Option<Value>> {
+        let height = self.stack.len();
+        let result = self.dispatch(base);
+        if result.is_err() {
+            self.frames.truncate(base);
+            self.stack.truncate(height);
+        }
+        result
+    }
+
+    fn dispatch(&mut self, base: usize) -> Result<Option<Value>> {
+        let mut chunk = Rc::clone(&self.frame().chunk);
+        loop {
+            let frame = self.frame();
+            let pc = frame.pc;
+            frame.pc += 1;
+            let line = chunk.lines[pc];
+            match &chunk.code[pc] {
+                Instruction::Step => {
+                    count_step(&mut self.steps, &self.limits, line)?;
+                    self.observer.step(line);
+                }
+                Instruction::Push(value) => self.stack.push(value.clone()),
+                Instruction::Load(slot) => {
+                    let value = self.variable(*slot, line)?.clone();
+                    self.stack.push(value);
+                }
+                Instruction::Require(slot) => {
+                    self.variable(*slot, line)?;
+                }
+                Instruction::Store(slot) => {
+                    let value = self.pop();
+                    self.observer.operation(Operation::Assignment);
+                    self.frame().set(*slot, value);
+                }
+                Instruction::StoreInto { slot, path } => self.store_into(*slot, path, line)?,
+                Instruction::Arithmetic(operator) => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    let value = left
+                        .arithmetic(operator, &right)
+                        .or_else(|message| fail(line, message))?;
+                    self.stack.push(value);
+                }
+                Instruction::Compare(operator) => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    self.observer.operation(Operation::Comparison);
+                    let value = left
+                        .compare(operator, &right)
+                        .or_else(|message| fail(line, message))?;
+                    self.stack.push(Value::Bool(value));
+                }
+                Instruction::Not => match self.pop() {
+                    Value::Bool(value) => self.stack.push(Value::Bool(!value)),
+                    other => {
+                        return fail(
+                            line,
+                            format!("'!' expects bool but found {}", other.type_name()),
+                        );
+                    }
+                },
+                Instruction::Truth(text) => match self.stack.last() {
+                    Some(Value::Bool(_)) => {}
+                    other => {
+                        let found = other.map_or("nothing", Value::type_name);
+                        return fail(
+                            line,
+                            format!("condition '{text}' is {found}, expected bool"),
+                        );
+                    }
+                },
+                Instruction::Jump(target) => self.frame().pc = *target,
+                Instruction::JumpIfFalse(target) => {
+                    if !self.pop_bool() {
+                        self.frame().pc = *target;
+                    }
+                }
+                Instruction::JumpIfTrue(target) => {
+                    if self.pop_bool() {
+                        self.frame().pc = *target;
+                    }
+                }
+                Instruction::Array(count) => {
+                    let items = self.stack.split_off(self.stack.len() - count);
+                    self.stack.push(Value::Array(items));
+                }
+                Instruction::Record(names) => {
+                    let values = self.stack.split_off(self.stack.len() - names.len());
+                    self.stack
+                        .push(Value::Record(names.iter().cloned().zip(values).collect()));
+                }
+                Instruction::Index => {
+                    let index = self.pop();
+                    let target = self.pop();
+                    let value = read(&mut self.observer, &target, &index, None, line)?;
+                    self.stack.push(value);
+                }
+                Instruction::IndexVariable(slot) => {
+                    let index = self.pop();
+                    let frame = self
+                        .frames
+                        .last()
+                        .expect("the VM always runs inside a frame");
+                    let Some(target) = &frame.slots[*slot] else {
+                        return fail(line, format!("'{}' is not defined", chunk.names[*slot]));
+                    };
+                    let array = Some(chunk.names[*slot].as_str());
+                    let value = read(&mut self.observer, target, &index, array, line)?;
+                    self.stack.push(value);
+                }
+                Instruction::Field(name) => match self.pop() {
+                    Value::Record(fields) => {
+                        match fields.into_iter().find(|(field, _)| field == name) {
+                            Some((_, value)) => self.stack.push(value),
+                            None => return fail(line, format!("record has no field '{name}'")),
+                        }
+                    }
+                    other => {
+                        return fail(line, format!("{} has no field '{name}'", other.type_name()));
+                    }
+                },
+                Instruction::Call {
+                    function,
+                    args,
+                    want,
+                } => {
+                    let args = self.stack.split_off(self.stack.len() - args);
+                    self.enter(*function, args, *want, line)?;
+                    chunk = Rc::clone(&self.frame().chunk);
+                }
+                Instruction::CallBuiltin {
+                    builtin,
+                    args,
+                    want,
+                } => {
+                    let args = self.stack.split_off(self.stack.len() - args);
+                    let value = call_builtin(
+                        builtin,
+                        args,
+                        line,
+                        &mut self.output,
+                        &mut self.random,
+                        &mut self.steps,
+                        &self.limits,
+                    )?;
+                    match value {
+                        Some(value) if *want => self.stack.push(value),
+                        None if *want => {
+                            return fail(
+                                line,
+                                format!("'{}' does not return a value", builtin.name),
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+                Instruction::Fail(message) => return fail(line, message.as_str()),
+                Instruction::Return | Instruction::ReturnNothing => {
+                    let value = match &chunk.code[pc] {
+                        Instruction::Return => Some(self.pop()),
+                        _ => None,
+                    };
+                    let callee = self.frames.pop().expect("a routine returns from its frame");
+                    if self.frames.len() == base {
+                        return Ok(value);
+                    }
+                    chunk = Rc::clone(&self.frame().chunk);
+                    let line = chunk.lines[self.frame().pc - 1];
+                    match value {
+                        Some(value) if callee.want => self.stack.push(value),
+                        None if callee.want => {
+                            return fail(
+                                line,
+                                format!("'{}' does not return a value", callee.chunk.name),
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+                Instruction::Iterate => {
+                    let items = match self.pop() {
+                        Value::Array(items) => items,
+                        Value::String(text) => text
+                            .chars()
+                            .map(|ch| Value::String(ch.to_string()))
+                            .collect(),
+                        other => {
+                            return fail(line, format!("cannot loop over {}", other.type_name()));
+                        }
+                    };
+                    self.frame().loops.push((items.into_iter(), false));
+                }
+                Instruction::Next { slot, exit } => {
+                    let frame = self
+                        .frames
+                        .last_mut()
+                        .expect("the VM always runs inside a frame");
+                    let (items, started) = frame
+                        .loops
+                        .last_mut()
+                        .expect("Next only runs inside a for loop");
+                    match items.next() {
+                        Some(value) => {
+                            if *started {
+                                count_step(&mut self.steps, &self.limits, line)?;
+                                self.observer.step(line);
+                            }
+                            *started = true;
+                            self.observer.operation(Operation::Assignment);
+                            frame.set(*slot, value);
+                        }
+                        None => {
+                            frame.loops.pop();
+                            frame.pc = *exit;
+                        }
+                    }
+                }
+                Instruction::EndIterate => {
+                    self.frame().loops.pop();
+                }
+                Instruction::Watch => {
+                    let state = self.state();
+                    self.frame().watches.push(Watch::new(state));
+                }
+                Instruction::Check => {
+                    let state = self.state();
+                    self.frame()
+                        .watches
+                        .last_mut()
+                        .expect("Check follows Watch")
+                        .check(state, line)?;
+                }
+                Instruction::Unwatch => {
+                    self.frame().watches.pop();
+                }
+            }
+        }
+    }
+
+    fn store_into(&mut self, slot: usize, path: &[Path], line: usize) -> Result<()> {
+        let count = path
+            .iter()
+            .filter(|step| matches!(step, Path::Index))
+            .count();
+        let mut indices = self.stack.split_off(self.stack.len() - count);
+        let value = self.pop();
+        self.observer.operation(Operation::Assignment);
+
+        let frame = self
+            .frames
+            .last_mut()
+            .expect("the VM always runs inside a frame");
+        let name = frame.chunk.names[slot].as_str();
+        let Some(mut target) = frame.slots[slot].as_mut() else {
+            return fail(line, format!("'{name}' is not defined"));
+        };
+        let direct = path.len() == 1;
+        for step in path.iter().rev() {
+            target = match (target, step) {
+                (Value::Array(items), Path::Index) => {
+                    let index = indices.pop().expect("one index per step");
+                    let position = position(&index, items.len(), line)?;
+                    self.observer.operation(Operation::ArrayAccess {
+                        array: direct.then_some(name),
+                        index: position,
+                        write: true,
+                    });
+                    &mut items[position]
+                }
+                (Value::Record(fields), Path::Field(name)) => {
+                    match fields.iter_mut().find(|(field, _)| field == name) {
+                        Some((_, value)) => value,
+                        None => return fail(line, format!("record has no field '{name}'")),
+                    }
+                }
+                (other, _) => {
+                    return fail(line, format!("cannot assign into {}", other.type_name()));
+                }
+            };
+        }
+        *target = value;
+        Ok(())
+    }
+}
+
+/// Reads an element of an array or a character of a string.
+fn read<O: Observer>(
+    observer: &mut O,
+    target: &Value,
+    index: &Value,
+    array: Option<&str>,
+    line: usize,
+) -> Result<Value> {
+    match target {
+        Value::Array(items) => {
+            let position = position(index, items.len(), line)?;
+            observer.operation(Operation::ArrayAccess {
+                array,
+                index: position,
+                write: false,
+            });
+            Ok(items[position].clone())
+        }
+        Value::String(text) => {
+            let chars: Vec<char> = text.chars().collect();
+            let position = position(index, chars.len(), line)?;
+            Ok(Value::String(chars[position].to_string()))
+        }
+        other => fail(line, format!("cannot index into {}", other.type_name())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{complexity::Sample, interpreter::Interpreter};
+
+    /// Everything a run can be told apart by.
+    #[derive(Debug, PartialEq)]
+    struct Run {
+        output: Vec<String>,
+        result: Result<Option<Value>, String>,
+        operations: Sample,
+    }
+
+    /// Small limits, so runaway programs stop quickly and deep recursion
+    /// fails before the interpreter overflows the test thread's stack.
+    fn limits() -> Limits {
+        Limits {
+            max_steps: 5_000,
+            max_depth: 64,
+            ..Limits::default()
+        }
+    }
+
+    fn walk(program: &Program, call: Option<(&str, Vec<Value>)>) -> Run {
+        let mut interpreter = Interpreter::with_observer(program, Sample::default());
+        interpreter.limits = limits();
+        interpreter.random = Random::with_seed(7);
+        let result = match call {
+            Some((name, args)) => interpreter.call(name, args, 0),
+            None => interpreter.run(),
+        };
+        Run {
+            output: interpreter.output,
+            result: result.map_err(|e| e.to_string()),
+            operations: interpreter.observer,
+        }
+    }
+
+    fn compiled(program: &Program, call: Option<(&str, Vec<Value>)>) -> Run {
+        let mut vm = Vm::with_observer(program, Sample::default());
+        vm.limits = limits();
+        vm.random = Random::with_seed(7);
+        let result = match call {
+            Some((name, args)) => vm.call(name, args, 0),
+            None => vm.run(),
+        };
+        Run {
+            output: vm.output,
+            result: result.map_err(|e| e.to_string()),
+            operations: vm.observer,
+        }
+    }
+
+    fn assert_same(source: &str) {
+        let program = Program::new(source).expect("test programs parse");
+        assert_eq!(walk(&program, None), compiled(&program, None), "{source}");
+    }
+
+    const SORTING: &str = "
+function bubble_sort(xs)
+    let n = length(xs)
+    for i in range(0, n)
+        for j in range(0, n - i - 1)
+            if xs[j] > xs[j + 1]
+                let tmp = xs[j]
+                let xs[j] = xs[j + 1]
+                let xs[j + 1] = tmp
+            end
+        end
+    end
+    return xs
+end
+
+function binary_search(xs, target)
+    let low = 0
+    let high = length(xs) - 1
+    while low <= high
+        let mid = (low + high) / 2
+        if xs[mid] == target
+            return mid
+        elseif xs[mid] < target
+            let low = mid + 1
+        else
+            let high = mid - 1
+        end
+    end
+    return -1
+end
+
+start
+    let sorted = bubble_sort([5, 3, 9, 1, 4])
+    print(sorted)
+    print(binary_search(sorted, 9), binary_search(sorted, 2))
+";
+
+    #[test]
+    fn programs_run_the_same() {
+        let programs = [
+            "
+start
+    let a = 7
+    let b = 2
+    print(a + b, a - b, a * b, a / b, a % b)
+    print(a / 2.0, \"x\" + \"y\", [1, 2] + [3])
+    let s = \"hello\"
+    print(s[1], length(s), to_upper(s), substring(s, 1, 3))
+    print(random(100), random(100), sqrt(16), max(3, 9), pow(2, 10))
+",
+            "
+function classify(n)
+    if n < 0
+        return \"negative\"
+    elseif n == 0
+        return \"zero\"
+    elseif n > 100 || n == 42
+        return \"special\"
+    else
+        return \"positive\"
+    end
+end
+
+start
+    for x in [-5, 0, 42, 7, 1000]
+        print(classify(x))
+    end
+    let i = 0
+    let total = 0
+    while i < 20
+        let i = i + 1
+        if i % 2 == 0 && i != 10
+            continue
+        end
+        if i > 15
+            break
+        end
+        let total = total + i
+    end
+    print(i, total)
+    let done = false
+    if !done
+        print(\"not done\")
+    end
+    for ch in \"abc\"
+        for n in [1, 2, 3]
+            if n == 2
+                break
+            end
+            print(ch, n)
+        end
+    end
+",
+            SORTING,
+            "
+function fib(n)
+    if n < 2
+        return n
+    end
+    return fib(n - 1) + fib(n - 2)
+end
+
+start
+    print(fib(12))
+    let grid = [[1, 2], [3, 4]]
+    let grid[1][0] = 30
+    let p = {x: 1, y: [1, 2]}
+    let p.x = 5
+    let p.y[1] = 7
+    print(grid, p, p.y[1], grid[1][0], p.x)
+    return p
+",
+            "
+function early()
+    print(1)
+    break
+    print(2)
+end
+
+start
+    early()
+    let xs = append([1], 2)
+    print(xs, contains(xs, 2))
+    continue
+    print(3)
+",
+        ];
+        for source in programs {
+            assert_same(source);
+        }
+    }
+
+    #[test]
+    fn errors_are_the_same() {
+        let programs = [
+            "start\n    print(y)\n",
+            "start\n    let xs = [1]\n    print(xs[3])\n",
+            "start\n    let xs = [1]\n    let xs[-1] = 2\n",
+            "start\n    print(1 / 0)\n",
+            "start\n    let i = 0\n    while i < 3\n        print(i)\n    end\n",
+            "start\n    while true\n        print(1)\n    end\n",
+            "start\n    print(nope(1))\n",
+            "function f()\n    let x = 1\nend\n\nstart\n    let y = f()\n",
+            "start\n    let y = print(1)\n",
+            "function f(a)\n    return a\nend\n\nstart\n    print(f(1, 2))\n",
+            "function down(n)\n    return down(n + 1)\nend\n\nstart\n    print(down(0))\n",
+            "start\n    if 1\n        print(1)\n    end\n",
+            "start\n    print(!5)\n",
+            "start\n    for x in 5\n        print(x)\n    end\n",
+            "start\n    let p = {x: 1}\n    print(p.z)\n",
+            "start\n    let p = {x: 1}\n    let p.z = 2\n",
+            "start\n    let q[0] = 1\n",
+            "start\n    let n = 5\n    let n[0] = 1\n",
+            "start\n    if \"a\" < 1\n        print(1)\n    end\n",
+            "start\n    print(length(5))\n",
+            "start\n    print(9223372036854775807 + 1)\n",
+            "start\n    print(length(range(0, 10000000000)))\n",
+        ];
+        for source in programs {
+            assert_same(source);
+        }
+    }
+
+    /// A flag the body sets once a counter gets there ends its loop, although
+    /// the first iterations leave the condition's variable alone.
+    const FLAG: &str = "
+start
+    let found = false
+    let i = 0
+    while !found
+        let i = i + 1
+        if i == 10
+            let found = true
+        end
+    end
+    print(i)
+";
+
+    #[test]
+    fn only_repeated_states_are_stuck() {
+        let program = Program::new(FLAG).expect("test programs parse");
+        let run = walk(&program, None);
+        assert_eq!(run.output, ["10"]);
+        assert!(run.result.is_ok());
+        assert_same(FLAG);
+
+        for stuck in [
+            "start\n    let i = 0\n    while i < 3\n        let i = i\n    end\n",
+            "start\n    let x = 0\n    while x != 5\n        let x = (x + 1) % 3\n    end\n",
+        ] {
+            let program = Program::new(stuck).expect("test programs parse");
+            let error = walk(&program, None).result.expect_err(stuck);
+            assert!(error.contains("loop at line 3"), "{error}");
+            assert_same(stuck);
+        }
+    }
+
+    #[test]
+    fn calls_with_random_inputs_are_the_same() {
+        let program = Program::new(SORTING).expect("test programs parse");
+        let mut random = Random::with_seed(42);
+        for size in 0..40 {
+            let xs: Vec<Value> = (0..size)
+                .map(|_| Value::Int(random.below(50) - 25))
+                .collect();
+            let target = Value::Int(random.below(50) - 25);
+            let sort = || Some(("bubble_sort", vec![Value::Array(xs.clone())]));
+            assert_eq!(walk(&program, sort()), compiled(&program, sort()));
+
+            let mut sorted = xs.clone();
+            sorted.sort_by_key(|value| match value {
+                Value::Int(value) => *value,
+                _ => 0,
+            });
+            let search = || {
+                Some((
+                    "binary_search",
+                    vec![Value::Array(sorted.clone()), target.clone()],
+                ))
+            };
+            assert_eq!(walk(&program, search()), compiled(&program, search()));
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::diagnostics::Severity;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    Off,
+    Info,
+    Warning,
+    Error,
+}
+
+impl RuleLevel {
+    pub fn severity(self) -> Option<Severity> {
+        match self {
+            RuleLevel::Off => None,
+            RuleLevel::Info => Some(Severity::Info),
+            RuleLevel::Warning => Some(Severity::Warning),
+            RuleLevel::Error => Some(Severity::Error),
+        }
+    }
+}
+
+/// User settings read from `lint.json` in the app's config directory. Rules
+/// missing from `rules` keep their default severity.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct LintConfig {
+    pub rules: HashMap<String, RuleLevel>,
+    pub max_nesting_depth: usize,
+    pub allowed_numbers: Vec<f64>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            rules: HashMap::new(),
+            max_nesting_depth: 3,
+            allowed_numbers: vec![0.0, 1.0, 2.0],
+        }
+    }
+}
+
+impl LintConfig {
+    pub fn level(&self, rule: &str) -> Option<RuleLevel> {
+        self.rules.get(rule).copied()
+    }
+}
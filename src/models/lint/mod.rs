@@ -0,0 +1,111 @@
+pub mod config;
+mod rules;
+
+use std::collections::HashSet;
+
+use crate::models::{
+    diagnostics::{Diagnostic, Severity},
+    lint::config::LintConfig,
+    nodes::{
+        node::Node,
+        program::Program,
+        walk::{Scope, walk},
+    },
+};
+
+const ALLOW_PREFIX: &str = "lint:allow(";
+
+pub struct Violation {
+    pub line: usize,
+    pub detail: String,
+}
+
+/// A style check over the parsed program. The framework decides the final
+/// severity from the user's [`LintConfig`] and applies `lint:allow`
+/// suppressions, so rules only need to report where they fire.
+pub trait LintRule {
+    fn id(&self) -> &'static str;
+    fn default_severity(&self) -> Severity;
+    fn message(&self) -> &'static str;
+    fn check(&self, program: &Program, config: &LintConfig) -> Vec<Violation>;
+}
+
+pub fn rules() -> Vec<Box<dyn LintRule>> {
+    vec![
+        Box::new(rules::nesting_depth::NestingDepth),
+        Box::new(rules::magic_number::MagicNumber),
+        Box::new(rules::short_name::ShortName),
+        Box::new(rules::empty_block::EmptyBlock),
+        Box::new(rules::boolean_comparison::BooleanComparison),
+    ]
+}
+
+pub fn lint(program: &Program, config: &LintConfig) -> Vec<Diagnostic> {
+    let allowed = suppressions(program);
+    let mut diagnostics = Vec::new();
+
+    for rule in rules() {
+        let severity = match config.level(rule.id()) {
+            Some(level) => level.severity(),
+            None => Some(rule.default_severity()),
+        };
+        let Some(severity) = severity else {
+            continue;
+        };
+
+        for violation in rule.check(program, config) {
+            if allowed.contains(&(violation.line, rule.id().to_string())) {
+                continue;
+            }
+            diagnostics.push(Diagnostic {
+                line: violation.line,
+                severity,
+                message: format!("{}: {}", rule.message(), violation.detail),
+                rule: Some(rule.id()),
+                fix: None,
+            });
+        }
+    }
+
+    diagnostics
+}
+
+/// Collects `-- lint:allow(rule, ...)` comments. A comment that shares its
+/// line with a statement silences that line, a comment on its own line
+/// silences the next statement.
+fn suppressions(program: &Program) -> HashSet<(usize, String)> {
+    let mut statement_lines = Vec::new();
+    let mut directives = Vec::new();
+
+    for routine in program.routines() {
+        statement_lines.push(routine.line);
+        walk(routine.body, Scope::default(), &mut |node, _| match node {
+            Node::Comment(comment) => {
+                if let Some(rules) = comment
+                    .value
+                    .strip_prefix(ALLOW_PREFIX)
+                    .and_then(|rest| rest.strip_suffix(')'))
+                {
+                    directives.push((comment.line, rules.to_string()));
+                }
+            }
+            _ => statement_lines.push(node.line()),
+        });
+    }
+
+    let mut allowed = HashSet::new();
+    for (line, rules) in directives {
+        let target = if statement_lines.contains(&line) {
+            Some(line)
+        } else {
+            statement_lines.iter().copied().filter(|l| *l > line).min()
+        };
+        let Some(target) = target else {
+            continue;
+        };
+        for rule in rules.split(',') {
+            allowed.insert((target, rule.trim().to_string()));
+        }
+    }
+    allowed
+}
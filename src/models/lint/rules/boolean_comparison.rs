@@ -0,0 +1,64 @@
+use crate::models::{
+    diagnostics::Severity,
+    lint::{LintRule, Violation, config::LintConfig, rules::conditions},
+    nodes::{
+        condition::Condition,
+        expression::Expression,
+        program::Program,
+        walk::{Scope, walk},
+    },
+};
+
+pub struct BooleanComparison;
+
+impl LintRule for BooleanComparison {
+    fn id(&self) -> &'static str {
+        "boolean-comparison"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn message(&self) -> &'static str {
+        "redundant comparison with a boolean"
+    }
+
+    fn check(&self, program: &Program, _config: &LintConfig) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for routine in program.routines() {
+            walk(routine.body, Scope::default(), &mut |node, _| {
+                for condition in conditions(node) {
+                    let mut comparisons = Vec::new();
+                    collect_comparisons(condition, &mut comparisons);
+                    for comparison in comparisons {
+                        violations.push(Violation {
+                            line: node.line(),
+                            detail: format!("test the value directly instead of '{comparison}'"),
+                        });
+                    }
+                }
+            });
+        }
+        violations
+    }
+}
+
+fn collect_comparisons<'a>(condition: &'a Condition, comparisons: &mut Vec<&'a Condition>) {
+    match condition {
+        Condition::ComparisonOperator { left, right, .. }
+            if is_boolean(left) || is_boolean(right) =>
+        {
+            comparisons.push(condition);
+        }
+        Condition::LogicalOperator { left, right, .. } => {
+            collect_comparisons(left, comparisons);
+            collect_comparisons(right, comparisons);
+        }
+        _ => {}
+    }
+}
+
+fn is_boolean(expression: &Expression) -> bool {
+    matches!(expression, Expression::Literal(value) if value == "true" || value == "false")
+}
@@ -0,0 +1,50 @@
+use crate::models::{
+    diagnostics::Severity,
+    lint::{LintRule, Violation, config::LintConfig},
+    nodes::{
+        node::Node,
+        program::Program,
+        walk::{Scope, walk},
+    },
+};
+
+pub struct EmptyBlock;
+
+impl LintRule for EmptyBlock {
+    fn id(&self) -> &'static str {
+        "empty-block"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn message(&self) -> &'static str {
+        "empty block"
+    }
+
+    fn check(&self, program: &Program, _config: &LintConfig) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for routine in program.routines() {
+            if is_empty(routine.body) {
+                violations.push(Violation {
+                    line: routine.line,
+                    detail: format!("'{}' has no statements", routine.name),
+                });
+            }
+            walk(routine.body, Scope::default(), &mut |node, _| {
+                if node.blocks().into_iter().any(is_empty) {
+                    violations.push(Violation {
+                        line: node.line(),
+                        detail: "a branch or loop body has no statements".to_string(),
+                    });
+                }
+            });
+        }
+        violations
+    }
+}
+
+fn is_empty(body: &[Node]) -> bool {
+    body.iter().all(|node| matches!(node, Node::Comment(_)))
+}
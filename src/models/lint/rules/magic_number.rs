@@ -0,0 +1,86 @@
+use crate::models::{
+    diagnostics::Severity,
+    lint::{LintRule, Violation, config::LintConfig, rules::expressions},
+    nodes::{
+        expression::Expression,
+        node::Node,
+        program::Program,
+        walk::{Scope, walk},
+    },
+};
+
+pub struct MagicNumber;
+
+impl LintRule for MagicNumber {
+    fn id(&self) -> &'static str {
+        "magic-number"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn message(&self) -> &'static str {
+        "magic number"
+    }
+
+    fn check(&self, program: &Program, config: &LintConfig) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for routine in program.routines() {
+            walk(routine.body, Scope::default(), &mut |node, _| {
+                // `let limit = 100` is how a number gets its name, and array
+                // literals are data rather than tuning knobs.
+                if let Node::Assignment(val) = node
+                    && matches!(val.value, Expression::Literal(_) | Expression::Array(_))
+                {
+                    return;
+                }
+
+                let mut numbers = Vec::new();
+                for expression in expressions(node) {
+                    collect_numbers(expression, &mut numbers);
+                }
+                for number in numbers {
+                    let allowed = number
+                        .parse::<f64>()
+                        .is_ok_and(|value| config.allowed_numbers.contains(&value));
+                    if !allowed {
+                        violations.push(Violation {
+                            line: node.line(),
+                            detail: format!("give '{number}' a descriptive name"),
+                        });
+                    }
+                }
+            });
+        }
+        violations
+    }
+}
+
+fn collect_numbers<'a>(expression: &'a Expression, numbers: &mut Vec<&'a str>) {
+    match expression {
+        Expression::Literal(value) if value.parse::<f64>().is_ok() => numbers.push(value),
+        Expression::BinaryOp { left, right, .. } => {
+            collect_numbers(left, numbers);
+            collect_numbers(right, numbers);
+        }
+        Expression::FunctionCall { args, .. } | Expression::Array(args) => {
+            for arg in args {
+                collect_numbers(arg, numbers);
+            }
+        }
+        Expression::Record(fields) => {
+            for (_, value) in fields {
+                collect_numbers(value, numbers);
+            }
+        }
+        Expression::Index { target, index } => {
+            collect_numbers(target, numbers);
+            collect_numbers(index, numbers);
+        }
+        Expression::Not(inner) | Expression::Field { target: inner, .. } => {
+            collect_numbers(inner, numbers)
+        }
+        Expression::Literal(_) | Expression::Variable(_) => {}
+    }
+}
@@ -0,0 +1,60 @@
+pub mod boolean_comparison;
+pub mod empty_block;
+pub mod magic_number;
+pub mod nesting_depth;
+pub mod short_name;
+
+use crate::models::nodes::{
+    condition::Condition, expression::Expression, loops::LoopType, node::Node,
+};
+
+/// Conditions tested directly by this statement, including `elseif` branches.
+fn conditions(node: &Node) -> Vec<&Condition> {
+    match node {
+        Node::If(val) => {
+            let mut conditions = vec![&val.condition];
+            conditions.extend(val.else_ifs.iter().map(|branch| &branch.condition));
+            conditions
+        }
+        Node::Loop(val) => match &val.loop_type {
+            LoopType::While(condition) => vec![condition],
+            LoopType::For { .. } => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// Top-level expressions owned by this statement, including the operands of
+/// its conditions.
+fn expressions(node: &Node) -> Vec<&Expression> {
+    let mut expressions = Vec::new();
+    match node {
+        Node::Assignment(val) => expressions.push(&val.value),
+        Node::Return(val) => expressions.push(&val.value),
+        Node::FunctionCall(val) => expressions.extend(&val.args),
+        Node::Loop(val) => {
+            if let LoopType::For { list, .. } = &val.loop_type {
+                expressions.push(list);
+            }
+        }
+        _ => {}
+    }
+    for condition in conditions(node) {
+        condition_expressions(condition, &mut expressions);
+    }
+    expressions
+}
+
+fn condition_expressions<'a>(condition: &'a Condition, expressions: &mut Vec<&'a Expression>) {
+    match condition {
+        Condition::ComparisonOperator { left, right, .. } => {
+            expressions.push(left);
+            expressions.push(right);
+        }
+        Condition::LogicalOperator { left, right, .. } => {
+            condition_expressions(left, expressions);
+            condition_expressions(right, expressions);
+        }
+        Condition::Value(value) => expressions.push(value),
+    }
+}
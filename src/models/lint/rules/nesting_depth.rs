@@ -0,0 +1,40 @@
+use crate::models::{
+    diagnostics::Severity,
+    lint::{LintRule, Violation, config::LintConfig},
+    nodes::{
+        program::Program,
+        walk::{Scope, walk},
+    },
+};
+
+pub struct NestingDepth;
+
+impl LintRule for NestingDepth {
+    fn id(&self) -> &'static str {
+        "nesting-depth"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn message(&self) -> &'static str {
+        "nesting is too deep"
+    }
+
+    fn check(&self, program: &Program, config: &LintConfig) -> Vec<Violation> {
+        let max = config.max_nesting_depth;
+        let mut violations = Vec::new();
+        for routine in program.routines() {
+            walk(routine.body, Scope::default(), &mut |node, scope| {
+                if scope.depth == max && !node.blocks().is_empty() {
+                    violations.push(Violation {
+                        line: node.line(),
+                        detail: format!("this block goes past the maximum depth of {max}"),
+                    });
+                }
+            });
+        }
+        violations
+    }
+}
@@ -0,0 +1,49 @@
+use std::collections::HashSet;
+
+use crate::models::{
+    diagnostics::Severity,
+    lint::{LintRule, Violation, config::LintConfig},
+    nodes::{
+        node::Node,
+        program::Program,
+        walk::{Scope, walk},
+    },
+};
+
+pub struct ShortName;
+
+impl LintRule for ShortName {
+    fn id(&self) -> &'static str {
+        "short-name"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn message(&self) -> &'static str {
+        "single-letter variable name"
+    }
+
+    fn check(&self, program: &Program, _config: &LintConfig) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for routine in program.routines() {
+            let mut reported = HashSet::new();
+            walk(routine.body, Scope::default(), &mut |node, scope| {
+                let Node::Assignment(val) = node else {
+                    return;
+                };
+                let Some(var) = val.var() else {
+                    return;
+                };
+                if !scope.in_loop && var.chars().count() == 1 && reported.insert(var) {
+                    violations.push(Violation {
+                        line: val.line,
+                        detail: format!("'{var}' is not a loop counter, use a longer name"),
+                    });
+                }
+            });
+        }
+        violations
+    }
+}
@@ -1,2 +1,16 @@
+pub mod analysis;
+pub mod canvas;
+pub mod complexity;
+pub mod coverage;
+pub mod debugger;
+pub mod diagnostics;
+pub mod flowchart;
+pub mod interpreter;
+pub mod lint;
 pub mod nodes;
 pub mod state;
+pub mod stdlib;
+pub mod structogram;
+pub mod testing;
+pub mod trace;
+pub mod transpile;
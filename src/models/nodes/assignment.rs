@@ -1,16 +1,33 @@
 use crate::{
     indent_writeln,
-    models::nodes::{expression::Expression, formatter::IndentFormatter, parsing::Parsable},
+    models::nodes::{
+        expression::Expression,
+        formatter::IndentFormatter,
+        parsing::{Parsable, Token},
+    },
 };
 use color_eyre::eyre::{Result, eyre};
 
 pub struct Assignment {
-    var: String,
-    value: Expression,
+    /// A variable, or an element or field of one such as `xs[i]` or `p.x`.
+    pub target: Expression,
+    pub value: Expression,
+    pub line: usize,
+    /// Inferred type shown in the preview, filled in by the type checker.
+    pub annotation: Option<String>,
 }
 
 impl Assignment {
     const KEYWORD: &'static str = "let";
+
+    /// The variable name when the whole variable is assigned, `None` when
+    /// only an element or field of it changes.
+    pub fn var(&self) -> Option<&str> {
+        match &self.target {
+            Expression::Variable(name) => Some(name),
+            _ => None,
+        }
+    }
 }
 
 impl IndentFormatter for Assignment {
@@ -19,7 +36,17 @@ impl IndentFormatter for Assignment {
         f: &mut core::fmt::Formatter<'_>,
         indent_count: usize,
     ) -> color_eyre::Result<usize> {
-        indent_writeln!(f, indent_count, "{} = {}", self.var, self.value)
+        match &self.annotation {
+            Some(annotation) => indent_writeln!(
+                f,
+                indent_count,
+                "{}: {} = {}",
+                self.target,
+                annotation,
+                self.value
+            ),
+            None => indent_writeln!(f, indent_count, "{} = {}", self.target, self.value),
+        }
     }
 }
 
@@ -28,7 +55,7 @@ impl Parsable for Assignment {
         value.to_lowercase() == Self::KEYWORD
     }
 
-    fn parse<'a>(parts: &'a [&'a str]) -> Result<(Box<Self>, &'a [&'a str])> {
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])> {
         if parts.len() < 4 {
             return Err(eyre!(
                 "Assignment requires at least 4 parts: {} var = value",
@@ -44,27 +71,31 @@ impl Parsable for Assignment {
             return Err(eyre!("first element is not {}", Self::KEYWORD));
         }
 
-        if parts.len() < 3 {
-            return Err(eyre!(
-                "Assignment missing parts: expected 'var = expression'"
-            ));
+        let (target, parts) = Expression::parse(parts)?;
+        if !matches!(
+            *target,
+            Expression::Variable(_) | Expression::Index { .. } | Expression::Field { .. }
+        ) {
+            return Err(eyre!("Cannot assign to '{target}'"));
         }
 
-        let var = parts[0].to_string();
-
-        if parts[1] != "=" {
+        let Some((equals, parts)) = parts.split_first() else {
             return Err(eyre!(
-                "Expected '=' after variable name, found '{}'",
-                parts[1]
+                "Assignment missing parts: expected 'var = expression'"
             ));
+        };
+        if equals.text != "=" {
+            return Err(eyre!("Expected '=' after variable name, found '{equals}'"));
         }
 
-        let (expression, parts) = Expression::parse(&parts[2..])?;
+        let (expression, parts) = Expression::parse(parts)?;
 
         Ok((
             Box::new(Self {
-                var,
+                target: *target,
                 value: *expression,
+                line: start.line,
+                annotation: None,
             }),
             parts,
         ))
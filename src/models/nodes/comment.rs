@@ -2,11 +2,15 @@ use color_eyre::eyre::{Result, eyre};
 
 use crate::{
     indent_writeln,
-    models::nodes::{formatter::IndentFormatter, parsing::Parsable},
+    models::nodes::{
+        formatter::IndentFormatter,
+        parsing::{Parsable, Token},
+    },
 };
 
 pub struct Comment {
-    value: String,
+    pub value: String,
+    pub line: usize,
 }
 
 impl Comment {
@@ -28,7 +32,7 @@ impl Parsable for Comment {
         value.to_lowercase() == Comment::KEYWORD
     }
 
-    fn parse<'a>(parts: &'a [&'a str]) -> Result<(Box<Self>, &'a [&'a str])> {
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])> {
         let (start, parts) = parts
             .split_first()
             .ok_or(eyre!("can't get first element"))?;
@@ -38,19 +42,20 @@ impl Parsable for Comment {
 
         let split_pos = parts
             .iter()
-            .position(|part| Self::matches(part))
+            .position(|part| Self::matches(part) || part.line != start.line)
             .unwrap_or(parts.len());
 
         let (word_parts, parts) = parts.split_at(split_pos);
-        let parts = if split_pos < parts.len() {
-            &parts[1..]
-        } else {
-            parts
+        let parts = match parts.first() {
+            Some(next) if Self::matches(next) && next.line == start.line => &parts[1..],
+            _ => parts,
         };
+        let words: Vec<&str> = word_parts.iter().map(|part| part.text).collect();
 
         Ok((
             Box::new(Self {
-                value: word_parts.join(" "),
+                value: words.join(" "),
+                line: start.line,
             }),
             parts,
         ))
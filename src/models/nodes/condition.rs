@@ -5,41 +5,55 @@ use color_eyre::eyre::{Result, eyre};
 use crate::{
     indent_writeln,
     models::nodes::{
+        end::End,
         expression::Expression,
         formatter::IndentFormatter,
         node::Node,
         operator::{ComparisonOperator, LogicalOperator},
-        parsing::Parsable,
+        parsing::{Parsable, Token},
     },
 };
 
 pub struct If {
-    condition: Condition,
-    body: Vec<Node>,
+    pub condition: Condition,
+    pub body: Vec<Node>,
+    pub else_ifs: Vec<ElseIf>,
+    pub else_branch: Option<Else>,
+    pub line: usize,
+    pub end_line: usize,
+}
+
+impl If {
+    const KEYWORD: &'static str = "if";
 }
 
 impl IndentFormatter for If {
     fn fmt_indent(
         &self,
         f: &mut core::fmt::Formatter<'_>,
-        mut indent_count: usize,
+        indent_count: usize,
     ) -> color_eyre::Result<usize> {
-        indent_count = indent_writeln!(f, indent_count, "if {}", self.condition)?;
+        indent_writeln!(f, indent_count, "if {}", self.condition)?;
 
-        indent_count += 1;
         for node in &self.body {
-            indent_count = node.fmt_indent(f, indent_count)?;
+            node.fmt_indent(f, indent_count + 1)?;
+        }
+        for else_if in &self.else_ifs {
+            else_if.fmt_indent(f, indent_count)?;
+        }
+        if let Some(else_branch) = &self.else_branch {
+            else_branch.fmt_indent(f, indent_count)?;
         }
 
-        Ok(indent_count)
+        End.fmt_indent(f, indent_count)
     }
 }
 
 impl Parsable for If {
     fn matches(value: &str) -> bool {
-        value.to_lowercase() == "if"
+        value.to_lowercase() == Self::KEYWORD
     }
-    fn parse<'a>(parts: &'a [&'a str]) -> Result<(Box<Self>, &'a [&'a str])> {
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])> {
         if parts.is_empty() {
             return Err(eyre!("No parts to parse for if statement"));
         }
@@ -53,12 +67,32 @@ impl Parsable for If {
         }
 
         let (condition, parts) = Condition::parse(parts)?;
-        let (body, parts) = Node::build_from_parts(parts)?;
+        let (body, mut parts) = Node::build_from_parts(parts)?;
+
+        let mut else_ifs = Vec::new();
+        while parts.first().is_some_and(|next| ElseIf::matches(next)) {
+            let (else_if, p) = ElseIf::parse(parts)?;
+            else_ifs.push(*else_if);
+            parts = p;
+        }
+
+        let mut else_branch = None;
+        if parts.first().is_some_and(|next| Else::matches(next)) {
+            let (branch, p) = Else::parse(parts)?;
+            else_branch = Some(*branch);
+            parts = p;
+        }
+
+        let (end_line, parts) = End::close(parts, Self::KEYWORD, start)?;
 
         Ok((
             Box::new(Self {
                 condition: *condition,
                 body,
+                else_ifs,
+                else_branch,
+                line: start.line,
+                end_line,
             }),
             parts,
         ))
@@ -66,22 +100,20 @@ impl Parsable for If {
 }
 
 pub struct ElseIf {
-    condition: Condition,
-    body: Vec<Node>,
+    pub condition: Condition,
+    pub body: Vec<Node>,
 }
 
 impl IndentFormatter for ElseIf {
     fn fmt_indent(
         &self,
         f: &mut core::fmt::Formatter<'_>,
-        mut indent_count: usize,
+        indent_count: usize,
     ) -> color_eyre::Result<usize> {
-        indent_count -= 1;
-        indent_count = indent_writeln!(f, indent_count, "else if {}", self.condition)?;
+        indent_writeln!(f, indent_count, "else if {}", self.condition)?;
 
-        indent_count += 1;
         for node in &self.body {
-            indent_count = node.fmt_indent(f, indent_count)?;
+            node.fmt_indent(f, indent_count + 1)?;
         }
 
         Ok(indent_count)
@@ -92,7 +124,7 @@ impl Parsable for ElseIf {
     fn matches(value: &str) -> bool {
         value.to_lowercase() == "elseif"
     }
-    fn parse<'a>(parts: &'a [&'a str]) -> Result<(Box<Self>, &'a [&'a str])> {
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])> {
         if parts.is_empty() {
             return Err(eyre!("No parts to parse for elseif statement"));
         }
@@ -119,21 +151,19 @@ impl Parsable for ElseIf {
 }
 
 pub struct Else {
-    body: Vec<Node>,
+    pub body: Vec<Node>,
 }
 
 impl IndentFormatter for Else {
     fn fmt_indent(
         &self,
         f: &mut core::fmt::Formatter<'_>,
-        mut indent_count: usize,
+        indent_count: usize,
     ) -> color_eyre::Result<usize> {
-        indent_count -= 1;
         indent_writeln!(f, indent_count, "else")?;
 
-        indent_count += 1;
         for node in &self.body {
-            indent_count = node.fmt_indent(f, indent_count)?;
+            node.fmt_indent(f, indent_count + 1)?;
         }
 
         Ok(indent_count)
@@ -144,7 +174,7 @@ impl Parsable for Else {
     fn matches(value: &str) -> bool {
         value.to_lowercase() == "else"
     }
-    fn parse<'a>(parts: &'a [&'a str]) -> Result<(Box<Self>, &'a [&'a str])> {
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])> {
         if parts.is_empty() {
             return Err(eyre!("No parts to parse for else statement"));
         }
@@ -173,8 +203,59 @@ pub enum Condition {
         operator: Box<LogicalOperator>,
         right: Box<Condition>,
     },
-    Value(String),
-    NotValue(String),
+    Value(Expression),
+}
+
+impl Condition {
+    /// Calls `visit` for every expression in the condition and the
+    /// expressions nested in them.
+    pub fn visit<'a>(&'a self, visit: &mut impl FnMut(&'a Expression)) {
+        match self {
+            Condition::ComparisonOperator { left, right, .. } => {
+                left.visit(visit);
+                right.visit(visit);
+            }
+            Condition::LogicalOperator { left, right, .. } => {
+                left.visit(visit);
+                right.visit(visit);
+            }
+            Condition::Value(value) => value.visit(visit),
+        }
+    }
+
+    /// The distinct variables the condition reads.
+    pub fn variables(&self) -> Vec<&str> {
+        let mut variables = Vec::new();
+        self.visit(&mut |expression| {
+            if let Expression::Variable(name) = expression
+                && !variables.contains(&name.as_str())
+            {
+                variables.push(name.as_str());
+            }
+        });
+        variables
+    }
+
+    /// Parses `a`, `!a` or `a < b`, stopping before any logical operator.
+    fn parse_comparison<'a>(parts: &'a [Token<'a>]) -> Result<(Condition, &'a [Token<'a>])> {
+        let (left, parts) = Expression::parse(parts)?;
+
+        if parts.len() >= 2
+            && let Ok((operator, parts)) = ComparisonOperator::parse(parts)
+        {
+            let (right, parts) = Expression::parse(parts)?;
+            return Ok((
+                Condition::ComparisonOperator {
+                    left,
+                    operator,
+                    right,
+                },
+                parts,
+            ));
+        }
+
+        Ok((Condition::Value(*left), parts))
+    }
 }
 
 impl fmt::Display for Condition {
@@ -195,7 +276,6 @@ impl fmt::Display for Condition {
                 write!(f, "({left} {operator} {right})")
             }
             Condition::Value(value) => write!(f, "{value}"),
-            Condition::NotValue(value) => write!(f, "!{value}"),
         }
     }
 }
@@ -205,69 +285,42 @@ impl Parsable for Condition {
         Expression::matches(value) || value.starts_with('!')
     }
 
-    fn parse<'a>(parts: &'a [&'a str]) -> Result<(Box<Self>, &'a [&'a str])> {
+    /// Parses comparisons joined by `&&` and `||`, binding `&&` tighter and
+    /// grouping from the left.
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])> {
         if parts.is_empty() {
             return Err(eyre!("No parts to parse for condition"));
         }
 
-        if parts[0].starts_with('!') {
-            let value = parts[0].trim_start_matches('!');
-            return Ok((
-                Box::new(Condition::NotValue(value.to_string())),
-                &parts[1..],
-            ));
-        }
-
-        let (left_expr, parts) = Expression::parse(parts)?;
-
-        if parts.len() >= 2 {
-            if let Ok((comp_operator, parts2)) = ComparisonOperator::parse(parts) {
-                let (right_expr, after_comparison) = Expression::parse(parts2)?;
-
-                let comparison_condition = Condition::ComparisonOperator {
-                    left: left_expr,
-                    operator: comp_operator,
-                    right: right_expr,
-                };
-
-                if after_comparison.len() >= 2 {
-                    if let Ok((logical_op, parts3)) = LogicalOperator::parse(after_comparison) {
-                        let (right_condition, parts) = Condition::parse(parts3)?;
-                        return Ok((
-                            Box::new(Condition::LogicalOperator {
-                                left: Box::new(comparison_condition),
-                                operator: logical_op,
-                                right: right_condition,
-                            }),
-                            parts,
-                        ));
-                    }
-                }
-
-                return Ok((Box::new(comparison_condition), after_comparison));
-            }
+        let (condition, mut rest) = Self::parse_comparison(parts)?;
+        let mut conditions = vec![condition];
+        let mut operators = Vec::new();
+
+        while rest.len() >= 2
+            && let Ok((operator, remaining)) = LogicalOperator::parse(rest)
+        {
+            let (condition, remaining) = Self::parse_comparison(remaining)?;
+            operators.push(operator);
+            conditions.push(condition);
+            rest = remaining;
         }
 
-        if let Expression::Variable(var_name) = left_expr.as_ref() {
-            let simple_condition = Condition::Value(var_name.clone());
-
-            if parts.len() >= 2 {
-                if let Ok((logical_op, parts2)) = LogicalOperator::parse(parts) {
-                    let (right_condition, parts) = Condition::parse(parts2)?;
-                    return Ok((
-                        Box::new(Condition::LogicalOperator {
-                            left: Box::new(simple_condition),
-                            operator: logical_op,
-                            right: right_condition,
-                        }),
-                        parts,
-                    ));
-                }
-            }
-
-            return Ok((Box::new(simple_condition), parts));
+        while let Some(position) =
+            (0..operators.len()).max_by_key(|&i| (operators[i].precedence(), core::cmp::Reverse(i)))
+        {
+            let operator = operators.remove(position);
+            let right = conditions.remove(position + 1);
+            let left = conditions.remove(position);
+            conditions.insert(
+                position,
+                Condition::LogicalOperator {
+                    left: Box::new(left),
+                    operator,
+                    right: Box::new(right),
+                },
+            );
         }
 
-        Err(eyre!("Unable to parse condition from remaining parts"))
+        Ok((Box::new(conditions.remove(0)), rest))
     }
 }
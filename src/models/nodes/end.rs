@@ -2,12 +2,81 @@ use color_eyre::eyre::{Result, eyre};
 
 use crate::{
     indent_writeln,
-    models::nodes::{formatter::IndentFormatter, parsing::Parsable},
+    models::{
+        diagnostics::{Fix, Position, TextEdit},
+        nodes::{
+            formatter::IndentFormatter,
+            function::Function,
+            parsing::{Parsable, ParseError, Token},
+            start::Start,
+            test_case::TestCase,
+        },
+    },
 };
 
 pub struct End;
 impl End {
     const END: &'static str = "end";
+
+    /// Consumes the `end` that closes `block` and returns its line. When it
+    /// is missing the error offers to insert it, aligned with the opener:
+    /// before the `function`, `test` or `start` the block runs into, or at
+    /// the end of the last line.
+    pub fn close<'a>(
+        parts: &'a [Token<'a>],
+        block: &str,
+        opener: &Token,
+    ) -> Result<(usize, &'a [Token<'a>])> {
+        let indent = " ".repeat(opener.column);
+        match parts.first() {
+            Some(next) if Self::matches(next) => Ok((next.line, &parts[1..])),
+            Some(next)
+                if Function::matches(next) || TestCase::matches(next) || Start::matches(next) =>
+            {
+                let at = Position::new(next.line, 0);
+                let fix = Fix::new(
+                    format!("insert '{}'", Self::END),
+                    vec![TextEdit::insert(at, format!("{indent}{}\n", Self::END))],
+                );
+                Err(ParseError::new(
+                    opener.line,
+                    format!("Missing '{}' to close {block}", Self::END),
+                )
+                .with_fix(fix)
+                .into())
+            }
+            Some(next) => {
+                let at = Position::new(next.line, 0);
+                let fix = Fix::new(
+                    format!("insert '{}'", Self::END),
+                    vec![TextEdit::insert(at, format!("{indent}{}\n", Self::END))],
+                );
+                Err(ParseError::new(
+                    next.line,
+                    format!(
+                        "Expected '{}' to close {block} on line {} but found '{next}'",
+                        Self::END,
+                        opener.line + 1
+                    ),
+                )
+                .with_fix(fix)
+                .into())
+            }
+            None => {
+                let at = Position::end_of_document();
+                let fix = Fix::new(
+                    format!("insert '{}'", Self::END),
+                    vec![TextEdit::insert(at, format!("\n{indent}{}", Self::END))],
+                );
+                Err(ParseError::new(
+                    opener.line,
+                    format!("Missing '{}' to close {block}", Self::END),
+                )
+                .with_fix(fix)
+                .into())
+            }
+        }
+    }
 }
 
 impl IndentFormatter for End {
@@ -16,7 +85,7 @@ impl IndentFormatter for End {
         f: &mut core::fmt::Formatter<'_>,
         indent_count: usize,
     ) -> color_eyre::Result<usize> {
-        indent_writeln!(f, indent_count - 1, "{}", Self::END)
+        indent_writeln!(f, indent_count, "{}", Self::END)
     }
 }
 
@@ -25,7 +94,7 @@ impl Parsable for End {
         value.to_lowercase() == Self::END
     }
 
-    fn parse<'a>(parts: &'a [&'a str]) -> Result<(Box<Self>, &'a [&'a str])> {
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])> {
         let (start, parts) = parts
             .split_first()
             .ok_or(eyre!("can't get first element"))?;
@@ -1,7 +1,7 @@
 use core::fmt;
 
 use crate::models::nodes::operator::ArithmeticOperator;
-use crate::models::nodes::parsing::Parsable;
+use crate::models::nodes::parsing::{Parsable, Token};
 use color_eyre::eyre::{Result, eyre};
 
 pub enum Expression {
@@ -17,87 +17,210 @@ pub enum Expression {
         args: Vec<Expression>,
     },
     Not(Box<Expression>),
+    Array(Vec<Expression>),
+    Record(Vec<(String, Expression)>),
+    Index {
+        target: Box<Expression>,
+        index: Box<Expression>,
+    },
+    Field {
+        target: Box<Expression>,
+        name: String,
+    },
 }
 
 impl Expression {
-    fn parse_not_expression<'a>(parts: &'a [&'a str]) -> Result<(Box<Self>, &'a [&'a str])> {
-        let first = parts[0];
-        let inner_part = first.trim_start_matches('!');
-
-        if inner_part.is_empty() && parts.len() > 1 {
-            let (inner_expr, remaining) = Self::parse(&parts[1..])?;
-            Ok((Box::new(Expression::Not(inner_expr)), remaining))
-        } else {
-            let inner_parts = vec![inner_part];
-            let (inner_expr, _) = Self::parse(&inner_parts)?;
-            Ok((Box::new(Expression::Not(inner_expr)), &parts[1..]))
+    /// Calls `visit` for this expression and every expression nested in it,
+    /// parents before children.
+    pub fn visit<'a>(&'a self, visit: &mut impl FnMut(&'a Expression)) {
+        visit(self);
+        match self {
+            Expression::Variable(_) | Expression::Literal(_) => {}
+            Expression::BinaryOp { left, right, .. } => {
+                left.visit(visit);
+                right.visit(visit);
+            }
+            Expression::FunctionCall { args, .. } | Expression::Array(args) => {
+                for arg in args {
+                    arg.visit(visit);
+                }
+            }
+            Expression::Not(inner) => inner.visit(visit),
+            Expression::Record(fields) => {
+                for (_, value) in fields {
+                    value.visit(visit);
+                }
+            }
+            Expression::Index { target, index } => {
+                target.visit(visit);
+                index.visit(visit);
+            }
+            Expression::Field { target, .. } => target.visit(visit),
         }
     }
 
-    fn parse_function_call<'a>(
-        parts: &'a [&'a str],
-    ) -> color_eyre::Result<(Box<Self>, &'a [&'a str])> {
-        let first = parts[0];
-        let paren_pos = first
-            .find('(')
-            .ok_or_else(|| color_eyre::eyre::eyre!("No opening parenthesis found"))?;
-        let name = &first[..paren_pos];
-
-        let mut full_call = String::new();
-        let mut paren_count = 0;
-        let mut consumed_parts = 0;
+    /// Joins tokens until every bracket and string opened in the first token
+    /// is closed again, so `[1,` `2]` is read as one operand.
+    fn take_group<'a>(parts: &'a [Token<'a>]) -> (String, &'a [Token<'a>]) {
+        let mut text = String::new();
+        let mut depth = 0;
+        let mut in_string = false;
 
         for (i, part) in parts.iter().enumerate() {
             if i > 0 {
-                full_call.push(' ');
+                text.push(' ');
             }
-            full_call.push_str(part);
-            consumed_parts = i + 1;
-
+            text.push_str(part);
             for ch in part.chars() {
                 match ch {
-                    '(' => paren_count += 1,
-                    ')' => paren_count -= 1,
+                    '"' => in_string = !in_string,
+                    '(' | '[' | '{' if !in_string => depth += 1,
+                    ')' | ']' | '}' if !in_string => depth -= 1,
                     _ => {}
                 }
             }
-
-            if paren_count == 0 {
-                break;
+            if depth <= 0 && !in_string {
+                return (text, &parts[i + 1..]);
             }
         }
 
-        let start_pos = full_call.find('(').unwrap();
-        let end_pos = full_call.rfind(')').unwrap();
-        let args_str = &full_call[start_pos + 1..end_pos];
-        let args = Self::parse_function_arguments(args_str)?;
+        (text, &parts[parts.len()..])
+    }
 
-        Ok((
-            Box::new(Expression::FunctionCall {
-                name: name.to_string(),
-                args,
-            }),
-            &parts[consumed_parts..],
-        ))
-    }
-
-    fn parse_function_arguments(args_str: &str) -> color_eyre::Result<Vec<Expression>> {
-        let mut args = Vec::new();
-        if !args_str.trim().is_empty() {
-            for arg_str in args_str.split(',') {
-                let trimmed = arg_str.trim();
-                if !trimmed.is_empty() {
-                    let arg_parts: Vec<&str> = trimmed.split_whitespace().collect();
-                    let (arg_expr, _) = Self::parse(&arg_parts)?;
-                    args.push(*arg_expr);
+    /// Splits `text` on `separator` wherever it is not nested inside brackets
+    /// or a string literal.
+    pub fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+        let mut pieces = Vec::new();
+        let mut depth = 0;
+        let mut in_string = false;
+        let mut start = 0;
+
+        for (index, ch) in text.char_indices() {
+            match ch {
+                '"' => in_string = !in_string,
+                '(' | '[' | '{' if !in_string => depth += 1,
+                ')' | ']' | '}' if !in_string => depth -= 1,
+                _ if ch == separator && depth == 0 && !in_string => {
+                    pieces.push(&text[start..index]);
+                    start = index + ch.len_utf8();
+                }
+                _ => {}
+            }
+        }
+        pieces.push(&text[start..]);
+        pieces
+    }
+
+    /// Finds the bracket that opens the one closing `text`.
+    fn matching_open(text: &str, open: char, close: char) -> Option<usize> {
+        let mut depth = 0;
+        for (index, ch) in text.char_indices().rev() {
+            if ch == close {
+                depth += 1;
+            } else if ch == open {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
                 }
             }
         }
-        Ok(args)
+        None
+    }
+
+    fn parse_text(text: &str, at: &Token) -> Result<Expression> {
+        let parts: Vec<Token> = text
+            .split_whitespace()
+            .map(|text| Token {
+                text,
+                line: at.line,
+                column: at.column,
+            })
+            .collect();
+        let (expression, remaining) = Self::parse(&parts)?;
+        if let Some(extra) = remaining.first() {
+            return Err(eyre!("Unexpected '{extra}' in '{text}'"));
+        }
+        Ok(*expression)
+    }
+
+    fn parse_list(text: &str, at: &Token) -> Result<Vec<Expression>> {
+        Self::split_top_level(text, ',')
+            .into_iter()
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| Self::parse_text(item, at))
+            .collect()
+    }
+
+    fn parse_record(text: &str, at: &Token) -> Result<Expression> {
+        let mut fields = Vec::new();
+        for field in Self::split_top_level(text, ',') {
+            let field = field.trim();
+            if field.is_empty() {
+                continue;
+            }
+            let (name, value) = field
+                .split_once(':')
+                .ok_or_else(|| eyre!("Record field '{field}' needs the form 'name: value'"))?;
+            fields.push((name.trim().to_string(), Self::parse_text(value, at)?));
+        }
+        Ok(Expression::Record(fields))
+    }
+
+    /// Parses a single operand such as `x`, `"a b"`, `[1, 2]`, `{x: 1}`,
+    /// `f(a)`, `list[i]`, `point.x` or `!done`.
+    fn parse_primary(text: &str, at: &Token) -> Result<Expression> {
+        if let Some(inner) = text.strip_prefix('!') {
+            return Ok(Expression::Not(Box::new(Self::parse_primary(inner, at)?)));
+        }
+
+        if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+            return Ok(Expression::Literal(text.to_string()));
+        }
+
+        for (open, close) in [('(', ')'), ('[', ']'), ('{', '}')] {
+            if !text.ends_with(close) {
+                continue;
+            }
+            let Some(start) = Self::matching_open(text, open, close) else {
+                return Err(eyre!("Unbalanced '{open}' in '{text}'"));
+            };
+            let inner = &text[start + 1..text.len() - 1];
+            let before = &text[..start];
+
+            return match (open, before.is_empty()) {
+                ('(', true) => Self::parse_text(inner, at),
+                ('(', false) => Ok(Expression::FunctionCall {
+                    name: before.to_string(),
+                    args: Self::parse_list(inner, at)?,
+                }),
+                ('[', true) => Ok(Expression::Array(Self::parse_list(inner, at)?)),
+                ('[', false) => Ok(Expression::Index {
+                    target: Box::new(Self::parse_primary(before, at)?),
+                    index: Box::new(Self::parse_text(inner, at)?),
+                }),
+                ('{', true) => Self::parse_record(inner, at),
+                _ => Err(eyre!("Unexpected '{{' in '{text}'")),
+            };
+        }
+
+        if text.parse::<f64>().is_err()
+            && let Some((target, name)) = text.rsplit_once('.')
+            && !target.is_empty()
+        {
+            return Ok(Expression::Field {
+                target: Box::new(Self::parse_primary(target, at)?),
+                name: name.to_string(),
+            });
+        }
+
+        Ok(Self::parse_simple_expression(text))
     }
 
     fn parse_simple_expression(token: &str) -> Expression {
         if (token.starts_with('"') && token.ends_with('"'))
+            || token == "true"
+            || token == "false"
             || token.parse::<i32>().is_ok()
             || token.parse::<f64>().is_ok()
         {
@@ -108,15 +231,50 @@ impl Expression {
     }
 
     fn try_parse_binary_operation<'a>(
-        parts: &'a [&'a str],
-    ) -> Result<Option<(Box<ArithmeticOperator>, &'a [&'a str])>> {
-        if parts.len() >= 3 {
-            if let Ok((operator, remaining)) = ArithmeticOperator::parse(&parts[1..]) {
-                return Ok(Some((operator, remaining)));
-            }
+        parts: &'a [Token<'a>],
+    ) -> Result<Option<(Box<ArithmeticOperator>, &'a [Token<'a>])>> {
+        if parts.len() >= 2
+            && let Ok((operator, remaining)) = ArithmeticOperator::parse(parts)
+        {
+            return Ok(Some((operator, remaining)));
         }
         Ok(None)
     }
+
+    fn parse_operand<'a>(parts: &'a [Token<'a>]) -> Result<(Expression, &'a [Token<'a>])> {
+        let Some(first) = parts.first() else {
+            return Err(eyre!("No parts to parse for expression"));
+        };
+
+        if first.text == "!" {
+            let (inner, rest) = Self::parse_operand(&parts[1..])?;
+            return Ok((Expression::Not(Box::new(inner)), rest));
+        }
+
+        let (text, rest) = Self::take_group(parts);
+        Ok((Self::parse_primary(&text, first)?, rest))
+    }
+
+    /// Folds `a op b op c ...` into a tree, binding `*`, `/` and `%` tighter
+    /// than `+` and `-` and grouping equal precedence from the left.
+    fn fold(mut operands: Vec<Expression>, mut operators: Vec<ArithmeticOperator>) -> Expression {
+        while let Some(position) =
+            (0..operators.len()).max_by_key(|&i| (operators[i].precedence(), core::cmp::Reverse(i)))
+        {
+            let operator = operators.remove(position);
+            let right = operands.remove(position + 1);
+            let left = operands.remove(position);
+            operands.insert(
+                position,
+                Expression::BinaryOp {
+                    left: Box::new(left),
+                    operator: Box::new(operator),
+                    right: Box::new(right),
+                },
+            );
+        }
+        operands.remove(0)
+    }
 }
 
 impl Parsable for Expression {
@@ -125,42 +283,37 @@ impl Parsable for Expression {
             && (value.chars().all(|c| c.is_alphanumeric() || c == '_')
                 || value.parse::<i32>().is_ok()
                 || value.parse::<f64>().is_ok()
-                || value.starts_with('"') && value.ends_with('"')
+                || value.starts_with('"')
                 || value.starts_with('!')
+                || value.starts_with('[')
+                || value.starts_with('{')
                 || value.contains('('))
     }
 
-    fn parse<'a>(parts: &'a [&'a str]) -> Result<(Box<Self>, &'a [&'a str])> {
-        if parts.is_empty() {
-            return Err(eyre!("No parts to parse for expression"));
-        }
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])> {
+        let (operand, mut rest) = Self::parse_operand(parts)?;
+        let mut operands = vec![operand];
+        let mut operators = Vec::new();
 
-        let first = parts[0];
-
-        if first.starts_with('!') {
-            return Self::parse_not_expression(parts);
-        }
-
-        if first.contains('(') {
-            return Self::parse_function_call(parts);
+        while let Some((operator, remaining)) = Self::try_parse_binary_operation(rest)? {
+            let (operand, remaining) = Self::parse_operand(remaining)?;
+            operators.push(*operator);
+            operands.push(operand);
+            rest = remaining;
         }
 
-        let simple_expr = Self::parse_simple_expression(first);
+        Ok((Box::new(Self::fold(operands, operators)), rest))
+    }
+}
 
-        if let Some((operator, remaining)) = Self::try_parse_binary_operation(parts)? {
-            let (right_expr, final_remaining) = Self::parse(remaining)?;
-            Ok((
-                Box::new(Expression::BinaryOp {
-                    left: Box::new(simple_expr),
-                    operator,
-                    right: right_expr,
-                }),
-                final_remaining,
-            ))
-        } else {
-            Ok((Box::new(simple_expr), &parts[1..]))
+fn write_list(f: &mut fmt::Formatter<'_>, items: &[Expression]) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
         }
+        write!(f, "{item}")?;
     }
+    Ok(())
 }
 
 impl fmt::Display for Expression {
@@ -177,15 +330,27 @@ impl fmt::Display for Expression {
             }
             Expression::FunctionCall { name, args } => {
                 write!(f, "{name}(")?;
-                for (i, arg) in args.iter().enumerate() {
+                write_list(f, args)?;
+                write!(f, ")")
+            }
+            Expression::Not(expr) => write!(f, "!{expr}"),
+            Expression::Array(items) => {
+                write!(f, "[")?;
+                write_list(f, items)?;
+                write!(f, "]")
+            }
+            Expression::Record(fields) => {
+                write!(f, "{{")?;
+                for (i, (name, value)) in fields.iter().enumerate() {
                     if i > 0 {
                         write!(f, ", ")?;
                     }
-                    write!(f, "{arg}")?;
+                    write!(f, "{name}: {value}")?;
                 }
-                write!(f, ")")
+                write!(f, "}}")
             }
-            Expression::Not(expr) => write!(f, "!{expr}"),
+            Expression::Index { target, index } => write!(f, "{target}[{index}]"),
+            Expression::Field { target, name } => write!(f, "{target}.{name}"),
         }
     }
 }
@@ -1,66 +1,150 @@
 use crate::{
-    indent_write, indent_writeln,
-    models::nodes::{expression::Expression, formatter::IndentFormatter, parsing::Parsable},
+    indent_writeln,
+    models::nodes::{
+        end::End,
+        expression::Expression,
+        formatter::IndentFormatter,
+        node::Node,
+        parsing::{Parsable, Token},
+    },
 };
 use color_eyre::eyre::{Result, eyre};
 
-pub struct FunctionCall {
-    name: String,
-    args: Vec<Expression>,
+pub struct Function {
+    pub name: String,
+    pub params: Vec<String>,
+    pub body: Vec<Node>,
+    pub line: usize,
 }
 
-impl IndentFormatter for FunctionCall {
+impl Function {
+    const KEYWORD: &'static str = "function";
+
+    fn parse_header<'a>(parts: &'a [Token<'a>]) -> Result<(String, Vec<String>, &'a [Token<'a>])> {
+        let mut header = String::new();
+        let mut paren_count = 0;
+        let mut consumed_parts = 0;
+
+        for part in parts {
+            header.push_str(part);
+            consumed_parts += 1;
+            paren_count += part.matches('(').count() as i32;
+            paren_count -= part.matches(')').count() as i32;
+            if paren_count == 0 && header.contains('(') {
+                break;
+            }
+        }
+
+        let (Some(open), Some(close)) = (header.find('('), header.rfind(')')) else {
+            return Err(eyre!(
+                "Expected '{}' header like 'name(a, b)' but found '{header}'",
+                Self::KEYWORD
+            ));
+        };
+
+        let name = header[..open].to_string();
+        if name.is_empty() {
+            return Err(eyre!("{} is missing a name", Self::KEYWORD));
+        }
+
+        let params = header[open + 1..close]
+            .split(',')
+            .map(str::trim)
+            .filter(|param| !param.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        Ok((name, params, &parts[consumed_parts..]))
+    }
+}
+
+impl IndentFormatter for Function {
     fn fmt_indent(
         &self,
         f: &mut core::fmt::Formatter<'_>,
         indent_count: usize,
     ) -> color_eyre::Result<usize> {
-        indent_writeln!(f, indent_count, "{} ", self.name)?;
-        for (i, arg) in self.args.iter().enumerate() {
-            if i > 0 {
-                indent_write!(f, indent_count, ", ")?;
-            }
-            indent_write!(f, indent_count, "{arg}")?;
+        indent_writeln!(
+            f,
+            indent_count,
+            "{} {}({})",
+            Self::KEYWORD,
+            self.name,
+            self.params.join(", ")
+        )?;
+        for node in &self.body {
+            node.fmt_indent(f, indent_count + 1)?;
         }
-        indent_writeln!(f, indent_count, ":")
+        End.fmt_indent(f, indent_count)
     }
 }
 
-impl Parsable for FunctionCall {
+impl Parsable for Function {
     fn matches(value: &str) -> bool {
-        value.contains("(")
+        value.to_lowercase() == Self::KEYWORD
     }
 
-    fn parse<'a>(parts: &'a [&'a str]) -> Result<(Box<Self>, &'a [&'a str])> {
-        if parts.len() < 3 {
-            return Err(eyre!("Function call requires at least 2 parts"));
-        }
-
-        let (next, parts) = parts.split_first().ok_or(eyre!("can't get next element"))?;
-
-        if !Self::matches(next) {
-            return Err(eyre!("next element is not function"));
-        }
-
-        let (name, mut parts) = parts
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])> {
+        let (start, parts) = parts
             .split_first()
-            .ok_or(eyre!("can't get function name"))?;
-
-        let mut args = Vec::new();
-
-        while !parts.is_empty() {
-            println!("{}", parts.len());
-            let (arg, p) = Expression::parse(parts)?;
-            args.push(*arg);
-            parts = p;
+            .ok_or(eyre!("can't get first element"))?;
+        if !Self::matches(start) {
+            return Err(eyre!("first element is not {}", Self::KEYWORD));
         }
 
+        let (name, params, parts) = Self::parse_header(parts)?;
+        let (body, parts) = Node::build_from_parts(parts)?;
+        let (_, parts) = End::close(parts, Self::KEYWORD, start)?;
+
         Ok((
             Box::new(Self {
-                name: name.to_string(),
-                args,
+                name,
+                params,
+                body,
+                line: start.line,
             }),
             parts,
         ))
     }
 }
+
+pub struct FunctionCall {
+    pub name: String,
+    pub args: Vec<Expression>,
+    pub line: usize,
+}
+
+impl IndentFormatter for FunctionCall {
+    fn fmt_indent(
+        &self,
+        f: &mut core::fmt::Formatter<'_>,
+        indent_count: usize,
+    ) -> color_eyre::Result<usize> {
+        let args: Vec<String> = self.args.iter().map(ToString::to_string).collect();
+        indent_writeln!(f, indent_count, "{}({})", self.name, args.join(", "))
+    }
+}
+
+impl Parsable for FunctionCall {
+    fn matches(value: &str) -> bool {
+        value.contains("(")
+    }
+
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])> {
+        let line = parts
+            .first()
+            .map(|part| part.line)
+            .ok_or(eyre!("can't get next element"))?;
+
+        let (expression, parts) = Expression::parse(parts)?;
+        match *expression {
+            Expression::FunctionCall { name, args } => {
+                Ok((Box::new(Self { name, args, line }), parts))
+            }
+            other => Err(eyre!(
+                "Expected a function call on line {} but found '{other}'",
+                line + 1
+            )),
+        }
+    }
+}
@@ -3,45 +3,52 @@ use color_eyre::eyre::{Result, eyre};
 use crate::{
     indent_writeln,
     models::nodes::{
-        condition::Condition, formatter::IndentFormatter, node::Node, parsing::Parsable,
+        condition::Condition,
+        end::End,
+        expression::Expression,
+        formatter::IndentFormatter,
+        node::Node,
+        parsing::{Parsable, Token},
     },
 };
 
 pub enum LoopType {
     While(Condition),
-    For { item: String, list: String },
+    For { item: String, list: Expression },
 }
 
 impl LoopType {
     const FOR: &'static str = "for";
     const WHILE: &'static str = "while";
+    const IN: &'static str = "in";
 }
 
 pub struct Loop {
-    loop_type: LoopType,
-    body: Vec<Node>,
+    pub loop_type: LoopType,
+    pub body: Vec<Node>,
+    pub line: usize,
+    pub end_line: usize,
 }
 
 impl IndentFormatter for Loop {
     fn fmt_indent(
         &self,
         f: &mut core::fmt::Formatter<'_>,
-        mut indent_count: usize,
+        indent_count: usize,
     ) -> color_eyre::Result<usize> {
         match &self.loop_type {
             LoopType::While(condition) => {
-                indent_count = indent_writeln!(f, indent_count, "while {condition}")?;
+                indent_writeln!(f, indent_count, "while {condition}")?;
             }
             LoopType::For { item, list } => {
-                indent_count = indent_writeln!(f, indent_count, "for {item} in {list}")?;
+                indent_writeln!(f, indent_count, "for {item} in {list}")?;
             }
         }
 
-        indent_count += 1;
         for n in &self.body {
-            indent_count = n.fmt_indent(f, indent_count)?;
+            n.fmt_indent(f, indent_count + 1)?;
         }
-        Ok(indent_count)
+        End.fmt_indent(f, indent_count)
     }
 }
 
@@ -51,21 +58,29 @@ impl Parsable for Loop {
         value == LoopType::FOR || value == LoopType::WHILE
     }
 
-    fn parse<'a>(parts: &'a [&'a str]) -> Result<(Box<Self>, &'a [&'a str])> {
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])> {
         let (val, parts) = parts.split_first().ok_or(eyre!("can't get next element"))?;
+        let line = val.line;
 
-        match *val {
+        match val.text {
             LoopType::FOR => {
                 let (item, parts) = parts.split_first().ok_or(eyre!("can't get next element"))?;
-                let (list, parts) = parts.split_first().ok_or(eyre!("can't get next element"))?;
+                let parts = match parts.split_first() {
+                    Some((keyword, rest)) if keyword.text == LoopType::IN => rest,
+                    _ => parts,
+                };
+                let (list, parts) = Expression::parse(parts)?;
                 let (body, parts) = Node::build_from_parts(parts)?;
+                let (end_line, parts) = End::close(parts, LoopType::FOR, val)?;
                 Ok((
                     Box::new(Self {
                         loop_type: LoopType::For {
                             item: item.to_string(),
-                            list: list.to_string(),
+                            list: *list,
                         },
                         body,
+                        line,
+                        end_line,
                     }),
                     parts,
                 ))
@@ -73,11 +88,14 @@ impl Parsable for Loop {
             LoopType::WHILE => {
                 let (condition, parts) = Condition::parse(parts)?;
                 let (body, parts) = Node::build_from_parts(parts)?;
+                let (end_line, parts) = End::close(parts, LoopType::WHILE, val)?;
 
                 Ok((
                     Box::new(Self {
                         loop_type: LoopType::While(*condition),
                         body,
+                        line,
+                        end_line,
                     }),
                     parts,
                 ))
@@ -1,13 +1,16 @@
-mod assignment;
-mod comment;
-mod condition;
-mod end;
-mod expression;
-mod formatter;
-mod function;
-mod loops;
+pub mod assignment;
+pub mod comment;
+pub mod condition;
+pub mod end;
+pub mod expression;
+pub mod formatter;
+pub mod function;
+pub mod loops;
 pub mod node;
-mod operator;
-mod parsing;
-mod return_node;
-mod start;
+pub mod operator;
+pub mod parsing;
+pub mod program;
+pub mod return_node;
+pub mod start;
+pub mod test_case;
+pub mod walk;
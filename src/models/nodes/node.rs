@@ -1,38 +1,45 @@
-use core::fmt;
-
 use color_eyre::eyre::{Result, eyre};
 
 use crate::{
     indent_writeln,
+    models::diagnostics::{Fix, Position, TextEdit},
     models::nodes::{
         assignment::Assignment,
         comment::Comment,
         condition::{Else, ElseIf, If},
         end::End,
         formatter::IndentFormatter,
-        function::FunctionCall,
+        function::{Function, FunctionCall},
         loops::Loop,
-        parsing::Parsable,
+        parsing::{Parsable, ParseError, Token},
         return_node::Return,
         start::Start,
+        test_case::TestCase,
     },
 };
 
-pub struct Break;
+pub struct Break {
+    pub line: usize,
+}
+
+impl Break {
+    const KEYWORD: &'static str = "break";
+}
+
 impl Parsable for Break {
     fn matches(value: &str) -> bool {
-        value.to_lowercase() == "loop"
+        value.to_lowercase() == Self::KEYWORD
     }
 
-    fn parse<'a>(parts: &'a [&'a str]) -> Result<(Box<Self>, &'a [&'a str])> {
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])> {
         let (start, parts) = parts
             .split_first()
             .ok_or(eyre!("can't get first element"))?;
-        if !Loop::matches(start) {
-            return Err(eyre!("first element is not loop"));
+        if !Self::matches(start) {
+            return Err(eyre!("first element is not {}", Self::KEYWORD));
         }
 
-        Ok((Box::new(Self {}), parts))
+        Ok((Box::new(Self { line: start.line }), parts))
     }
 }
 
@@ -42,25 +49,32 @@ impl IndentFormatter for Break {
         f: &mut core::fmt::Formatter<'_>,
         indent_count: usize,
     ) -> color_eyre::Result<usize> {
-        indent_writeln!(f, indent_count, "break")
+        indent_writeln!(f, indent_count, "{}", Self::KEYWORD)
     }
 }
 
-pub struct Continue;
+pub struct Continue {
+    pub line: usize,
+}
+
+impl Continue {
+    const KEYWORD: &'static str = "continue";
+}
+
 impl Parsable for Continue {
     fn matches(value: &str) -> bool {
-        value.to_lowercase() == "loop"
+        value.to_lowercase() == Self::KEYWORD
     }
 
-    fn parse<'a>(parts: &'a [&'a str]) -> Result<(Box<Self>, &'a [&'a str])> {
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])> {
         let (start, parts) = parts
             .split_first()
             .ok_or(eyre!("can't get first element"))?;
-        if !Loop::matches(start) {
-            return Err(eyre!("first element is not loop"));
+        if !Self::matches(start) {
+            return Err(eyre!("first element is not {}", Self::KEYWORD));
         }
 
-        Ok((Box::new(Self {}), parts))
+        Ok((Box::new(Self { line: start.line }), parts))
     }
 }
 
@@ -70,19 +84,15 @@ impl IndentFormatter for Continue {
         f: &mut core::fmt::Formatter<'_>,
         indent_count: usize,
     ) -> color_eyre::Result<usize> {
-        indent_writeln!(f, indent_count, "continue")
+        indent_writeln!(f, indent_count, "{}", Self::KEYWORD)
     }
 }
 
 pub enum Node {
-    Start(Start),
     Loop(Loop),
     Break(Break),
     Continue(Continue),
     If(If),
-    ElseIf(ElseIf),
-    Else(Else),
-    End(End),
     Assignment(Assignment),
     FunctionCall(FunctionCall),
     Return(Return),
@@ -90,22 +100,59 @@ pub enum Node {
 }
 
 impl Node {
-    pub fn new(input: &str) -> Result<Self> {
-        let parts: Vec<&str> = input.split_whitespace().collect();
-        let (start, _) = Start::parse(&parts)?;
-        let start = Self::Start(*start);
+    pub fn line(&self) -> usize {
+        match self {
+            Node::Loop(val) => val.line,
+            Node::Break(val) => val.line,
+            Node::Continue(val) => val.line,
+            Node::If(val) => val.line,
+            Node::Assignment(val) => val.line,
+            Node::FunctionCall(val) => val.line,
+            Node::Return(val) => val.line,
+            Node::Comment(val) => val.line,
+        }
+    }
 
-        Ok(start)
+    /// The last line the statement occupies, i.e. the line of its `end`.
+    pub fn end_line(&self) -> usize {
+        match self {
+            Node::Loop(val) => val.end_line,
+            Node::If(val) => val.end_line,
+            _ => self.line(),
+        }
     }
 
-    pub fn build_from_parts<'a>(mut parts: &'a [&'a str]) -> Result<(Vec<Self>, &'a [&'a str])> {
-        let mut nodes = Vec::new();
+    /// Returns the statement blocks nested directly inside this node.
+    pub fn blocks(&self) -> Vec<&[Node]> {
+        match self {
+            Node::If(val) => {
+                let mut blocks = vec![val.body.as_slice()];
+                blocks.extend(val.else_ifs.iter().map(|branch| branch.body.as_slice()));
+                blocks.extend(val.else_branch.iter().map(|branch| branch.body.as_slice()));
+                blocks
+            }
+            Node::Loop(val) => vec![val.body.as_slice()],
+            _ => Vec::new(),
+        }
+    }
 
-        while !parts.is_empty() {
-            let first = parts.first().ok_or(eyre!("unable to get first in parts"))?;
+    /// Parses statements until the input runs out or a token that closes the
+    /// current block (`end`, `elseif`, `else`, `function`, `test`, `start`)
+    /// is reached. The closing token is left for the caller.
+    pub fn build_from_parts<'a>(
+        mut parts: &'a [Token<'a>],
+    ) -> Result<(Vec<Self>, &'a [Token<'a>])> {
+        let mut nodes = Vec::new();
 
-            if Start::matches(first) {
-                return Err(eyre!("Start can only be used once"));
+        while let Some(first) = parts.first() {
+            if End::matches(first)
+                || ElseIf::matches(first)
+                || Else::matches(first)
+                || Function::matches(first)
+                || TestCase::matches(first)
+                || Start::matches(first)
+            {
+                break;
             }
 
             macro_rules! try_parse {
@@ -123,21 +170,79 @@ impl Node {
             try_parse!(Break, Break);
             try_parse!(Continue, Continue);
             try_parse!(If, If);
-            try_parse!(ElseIf, ElseIf);
-            try_parse!(Else, Else);
-            try_parse!(End, End);
             try_parse!(Assignment, Assignment);
             try_parse!(FunctionCall, FunctionCall);
             try_parse!(Return, Return);
             try_parse!(Comment, Comment);
 
-            return Err(eyre!("Unknown node type: {first}"));
+            return Err(unknown_statement(parts).into());
         }
 
         Ok((nodes, parts))
     }
 }
 
+const KEYWORDS: &[&str] = &[
+    "start", "function", "if", "elseif", "else", "end", "while", "for", "let", "return", "break",
+    "continue", "test",
+];
+
+/// Builds the error for a statement that starts with an unrecognised word,
+/// offering `let` for bare assignments and the closest keyword for typos.
+pub fn unknown_statement(parts: &[Token]) -> ParseError {
+    let first = &parts[0];
+    if KEYWORDS.contains(&first.to_lowercase().as_str()) {
+        return ParseError::new(first.line, format!("Unexpected '{first}'"));
+    }
+
+    if parts.get(1).is_some_and(|next| next.text == "=") {
+        let at = Position::new(first.line, first.column);
+        return ParseError::new(
+            first.line,
+            format!("Assignment to '{first}' is missing 'let'"),
+        )
+        .with_fix(Fix::new("add 'let'", vec![TextEdit::insert(at, "let ")]));
+    }
+
+    let error = ParseError::new(first.line, format!("Unknown node type: {first}"));
+    match closest_keyword(first) {
+        Some(keyword) => {
+            let start = Position::new(first.line, first.column);
+            let end = Position::new(first.line, first.column + first.chars().count());
+            error.with_fix(Fix::new(
+                format!("replace with '{keyword}'"),
+                vec![TextEdit::replace(start, end, keyword)],
+            ))
+        }
+        None => error,
+    }
+}
+
+fn closest_keyword(word: &str) -> Option<&'static str> {
+    let word = word.to_lowercase();
+    let max_distance = if word.chars().count() <= 3 { 1 } else { 2 };
+    KEYWORDS
+        .iter()
+        .map(|keyword| (edit_distance(&word, keyword), *keyword))
+        .filter(|(distance, _)| *distance > 0 && *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, keyword)| keyword)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
 impl IndentFormatter for Node {
     fn fmt_indent(
         &self,
@@ -145,25 +250,14 @@ impl IndentFormatter for Node {
         indent_count: usize,
     ) -> color_eyre::Result<usize> {
         match self {
-            Node::Start(val) => val.fmt_indent(f, indent_count),
             Node::Loop(val) => val.fmt_indent(f, indent_count),
             Node::Break(val) => val.fmt_indent(f, indent_count),
             Node::Continue(val) => val.fmt_indent(f, indent_count),
             Node::If(val) => val.fmt_indent(f, indent_count),
-            Node::ElseIf(val) => val.fmt_indent(f, indent_count),
-            Node::Else(val) => val.fmt_indent(f, indent_count),
             Node::Assignment(val) => val.fmt_indent(f, indent_count),
             Node::FunctionCall(val) => val.fmt_indent(f, indent_count),
             Node::Return(val) => val.fmt_indent(f, indent_count),
             Node::Comment(val) => val.fmt_indent(f, indent_count),
-            Node::End(val) => val.fmt_indent(f, indent_count),
         }
     }
 }
-
-impl fmt::Display for Node {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.fmt_indent(f, 0).map_err(|_| fmt::Error)?;
-        Ok(())
-    }
-}
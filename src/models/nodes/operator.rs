@@ -1,7 +1,8 @@
-use crate::models::nodes::parsing::Parsable;
+use crate::models::nodes::parsing::{Parsable, Token};
 use color_eyre::eyre::{Result, eyre};
 use core::fmt;
 
+#[derive(Clone, Copy)]
 pub enum ComparisonOperator {
     Eq,
     Neq,
@@ -11,6 +12,7 @@ pub enum ComparisonOperator {
     Lte,
 }
 
+#[derive(Clone, Copy)]
 pub enum ArithmeticOperator {
     Addition,
     Subtraction,
@@ -24,17 +26,39 @@ pub enum LogicalOperator {
     Or,
 }
 
+impl ArithmeticOperator {
+    /// Higher binds tighter: `*`, `/` and `%` before `+` and `-`.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            ArithmeticOperator::Addition | ArithmeticOperator::Subtraction => 1,
+            ArithmeticOperator::Multiplication
+            | ArithmeticOperator::Division
+            | ArithmeticOperator::Modulo => 2,
+        }
+    }
+}
+
+impl LogicalOperator {
+    /// Higher binds tighter: `&&` before `||`.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            LogicalOperator::Or => 1,
+            LogicalOperator::And => 2,
+        }
+    }
+}
+
 impl Parsable for ComparisonOperator {
     fn matches(value: &str) -> bool {
         matches!(value, "==" | "!=" | ">" | "<" | ">=" | "<=")
     }
 
-    fn parse<'a>(parts: &'a [&'a str]) -> Result<(Box<Self>, &'a [&'a str])> {
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])> {
         let (first, parts) = parts
             .split_first()
             .ok_or(eyre!("No parts to parse for comparison operator"))?;
 
-        let operator = match *first {
+        let operator = match first.text {
             "==" => ComparisonOperator::Eq,
             "!=" => ComparisonOperator::Neq,
             ">" => ComparisonOperator::Gt,
@@ -53,12 +77,12 @@ impl Parsable for ArithmeticOperator {
         matches!(value, "+" | "-" | "*" | "/" | "%")
     }
 
-    fn parse<'a>(parts: &'a [&'a str]) -> Result<(Box<Self>, &'a [&'a str])> {
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])> {
         let (first, parts) = parts
             .split_first()
             .ok_or(eyre!("No parts to parse for arithmetic operator"))?;
 
-        let operator = match *first {
+        let operator = match first.text {
             "+" => ArithmeticOperator::Addition,
             "-" => ArithmeticOperator::Subtraction,
             "*" => ArithmeticOperator::Multiplication,
@@ -76,12 +100,12 @@ impl Parsable for LogicalOperator {
         matches!(value, "&&" | "||")
     }
 
-    fn parse<'a>(parts: &'a [&'a str]) -> Result<(Box<Self>, &'a [&'a str])> {
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])> {
         let (first, parts) = parts
             .split_first()
             .ok_or(eyre!("No parts to parse for logical operator"))?;
 
-        let operator = match *first {
+        let operator = match first.text {
             "&&" => LogicalOperator::And,
             "||" => LogicalOperator::Or,
             _ => return Err(eyre!("Invalid logical operator: {}", first)),
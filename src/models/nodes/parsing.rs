@@ -1,6 +1,94 @@
+use core::{fmt, ops::Deref};
+
 use color_eyre::eyre::Result;
 
+use crate::models::diagnostics::{Diagnostic, Fix};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Deref for Token<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.text
+    }
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+pub fn tokenize(input: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    for (line, text) in input.lines().enumerate() {
+        let mut word_start = None;
+        let chars = text.char_indices().chain([(text.len(), ' ')]);
+        for (column, (offset, ch)) in chars.enumerate() {
+            match (ch.is_whitespace(), word_start) {
+                (false, None) => word_start = Some((offset, column)),
+                (true, Some((start, start_column))) => {
+                    tokens.push(Token {
+                        text: &text[start..offset],
+                        line,
+                        column: start_column,
+                    });
+                    word_start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+    tokens
+}
+
+/// A parse failure that knows where it happened and, when the mistake is
+/// obvious, how to repair the source.
+#[derive(Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+    pub fix: Option<Fix>,
+}
+
+impl ParseError {
+    pub fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.line, self.message.clone());
+        match &self.fix {
+            Some(fix) => diagnostic.with_fix(fix.clone()),
+            None => diagnostic,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (line {})", self.message, self.line + 1)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 pub trait Parsable {
     fn matches(value: &str) -> bool;
-    fn parse<'a>(parts: &'a [&'a str]) -> Result<(Box<Self>, &'a [&'a str])>;
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])>;
 }
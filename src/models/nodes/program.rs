@@ -0,0 +1,97 @@
+use core::fmt;
+
+use color_eyre::eyre::{Result, eyre};
+
+use crate::models::nodes::{
+    formatter::IndentFormatter,
+    function::Function,
+    node::{Node, unknown_statement},
+    parsing::{Parsable, ParseError, Token, tokenize},
+    start::Start,
+    test_case::TestCase,
+};
+
+pub struct Program {
+    pub functions: Vec<Function>,
+    pub start: Start,
+    pub tests: Vec<TestCase>,
+}
+
+/// A named body of statements: either a function or the `start` block.
+pub struct Routine<'a> {
+    pub name: &'a str,
+    pub line: usize,
+    pub body: &'a [Node],
+}
+
+impl Program {
+    pub fn new(input: &str) -> Result<Self> {
+        let tokens = tokenize(input);
+        let mut parts: &[Token] = &tokens;
+        let mut functions = Vec::new();
+        let mut start = None;
+        let mut tests = Vec::new();
+
+        while let Some(first) = parts.first() {
+            if Function::matches(first) {
+                let (function, p) = Function::parse(parts)?;
+                functions.push(*function);
+                parts = p;
+            } else if TestCase::matches(first) {
+                let (test, p) = TestCase::parse(parts)?;
+                tests.push(*test);
+                parts = p;
+            } else if Start::matches(first) && start.is_none() {
+                let (node, p) = Start::parse(parts)?;
+                start = Some(*node);
+                parts = p;
+            } else if Start::matches(first) {
+                return Err(ParseError::new(first.line, "Start can only be used once").into());
+            } else {
+                return Err(unknown_statement(parts).into());
+            }
+        }
+
+        let start = start.ok_or_else(|| eyre!("Expected 'start' but found no start block"))?;
+        Ok(Self {
+            functions,
+            start,
+            tests,
+        })
+    }
+
+    pub fn routines(&self) -> Vec<Routine<'_>> {
+        let mut routines: Vec<Routine> = self
+            .functions
+            .iter()
+            .map(|function| Routine {
+                name: &function.name,
+                line: function.line,
+                body: &function.body,
+            })
+            .collect();
+        routines.push(Routine {
+            name: "start",
+            line: self.start.line,
+            body: &self.start.body,
+        });
+        routines
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for function in &self.functions {
+            function.fmt_indent(f, 0).map_err(|_| fmt::Error)?;
+            writeln!(f)?;
+        }
+        self.start.fmt_indent(f, 0).map_err(|_| fmt::Error)?;
+        if !self.tests.is_empty() {
+            writeln!(f)?;
+        }
+        for test in &self.tests {
+            test.fmt_indent(f, 0).map_err(|_| fmt::Error)?;
+        }
+        Ok(())
+    }
+}
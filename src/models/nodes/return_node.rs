@@ -1,11 +1,16 @@
 use crate::{
     indent_writeln,
-    models::nodes::{expression::Expression, formatter::IndentFormatter, parsing::Parsable},
+    models::nodes::{
+        expression::Expression,
+        formatter::IndentFormatter,
+        parsing::{Parsable, Token},
+    },
 };
 use color_eyre::eyre::{Ok, Result, eyre};
 
 pub struct Return {
-    value: Expression,
+    pub value: Expression,
+    pub line: usize,
 }
 
 impl Return {
@@ -27,7 +32,7 @@ impl Parsable for Return {
         value.to_lowercase() == Self::KEYWORD
     }
 
-    fn parse<'a>(parts: &'a [&'a str]) -> Result<(Box<Self>, &'a [&'a str])> {
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])> {
         let (first, parts) = parts
             .split_first()
             .ok_or(eyre!("can't get first element"))?;
@@ -35,6 +40,12 @@ impl Parsable for Return {
             return Err(eyre!("first element is not {}", Self::KEYWORD));
         }
         let (expression, parts) = Expression::parse(parts)?;
-        Ok((Box::new(Self { value: *expression }), parts))
+        Ok((
+            Box::new(Self {
+                value: *expression,
+                line: first.line,
+            }),
+            parts,
+        ))
     }
 }
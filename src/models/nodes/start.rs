@@ -1,11 +1,16 @@
 use crate::{
     indent_writeln,
-    models::nodes::{formatter::IndentFormatter, node::Node, parsing::Parsable},
+    models::nodes::{
+        formatter::IndentFormatter,
+        node::Node,
+        parsing::{Parsable, Token},
+    },
 };
 use color_eyre::eyre::{Result, eyre};
 
 pub struct Start {
-    body: Vec<Node>,
+    pub body: Vec<Node>,
+    pub line: usize,
 }
 
 impl Start {
@@ -32,7 +37,7 @@ impl Parsable for Start {
         value.to_lowercase() == Self::KEYWORD
     }
 
-    fn parse<'a>(parts: &'a [&'a str]) -> Result<(Box<Self>, &'a [&'a str])> {
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])> {
         let (start, parts) = parts
             .split_first()
             .ok_or_else(|| eyre!("Cannot parse {}: input is empty", Self::KEYWORD))?;
@@ -43,6 +48,12 @@ impl Parsable for Start {
 
         let (body, parts) = Node::build_from_parts(parts)?;
 
-        Ok((Box::new(Self { body }), parts))
+        Ok((
+            Box::new(Self {
+                body,
+                line: start.line,
+            }),
+            parts,
+        ))
     }
 }
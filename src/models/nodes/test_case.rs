@@ -0,0 +1,102 @@
+use crate::{
+    indent_writeln,
+    models::nodes::{
+        comment::Comment,
+        expression::Expression,
+        formatter::IndentFormatter,
+        parsing::{Parsable, ParseError, Token, tokenize},
+    },
+};
+use color_eyre::eyre::{Result, eyre};
+
+/// A check declared next to the algorithm, such as
+/// `test binary_search([1, 3, 5], 5) == 2`.
+pub struct TestCase {
+    pub call: Expression,
+    pub expected: Expression,
+    pub line: usize,
+}
+
+impl TestCase {
+    const KEYWORD: &'static str = "test";
+    const SEPARATOR: &'static str = "==";
+
+    /// Parses a sidecar file holding nothing but `test` lines and comments.
+    pub fn parse_all(input: &str) -> Result<Vec<Self>> {
+        let tokens = tokenize(input);
+        let mut parts: &[Token] = &tokens;
+        let mut tests = Vec::new();
+        while let Some(first) = parts.first() {
+            if Comment::matches(first) {
+                let (_, rest) = Comment::parse(parts)?;
+                parts = rest;
+                continue;
+            }
+            let (test, rest) = Self::parse(parts)?;
+            tests.push(*test);
+            parts = rest;
+        }
+        Ok(tests)
+    }
+}
+
+impl IndentFormatter for TestCase {
+    fn fmt_indent(
+        &self,
+        f: &mut core::fmt::Formatter<'_>,
+        indent_count: usize,
+    ) -> color_eyre::Result<usize> {
+        indent_writeln!(
+            f,
+            indent_count,
+            "{} {} {} {}",
+            Self::KEYWORD,
+            self.call,
+            Self::SEPARATOR,
+            self.expected
+        )
+    }
+}
+
+impl Parsable for TestCase {
+    fn matches(value: &str) -> bool {
+        value.to_lowercase() == Self::KEYWORD
+    }
+
+    fn parse<'a>(parts: &'a [Token<'a>]) -> Result<(Box<Self>, &'a [Token<'a>])> {
+        let (first, parts) = parts
+            .split_first()
+            .ok_or(eyre!("can't get first element"))?;
+        if !Self::matches(first) {
+            return Err(ParseError::new(
+                first.line,
+                format!("Expected '{}' but found '{first}'", Self::KEYWORD),
+            )
+            .into());
+        }
+        let (call, parts) = Expression::parse(parts)?;
+        let parts = match parts.split_first() {
+            Some((separator, parts)) if separator.text == Self::SEPARATOR => parts,
+            _ => {
+                return Err(ParseError::new(
+                    first.line,
+                    format!(
+                        "Expected '{} <call> {} <expected>'",
+                        Self::KEYWORD,
+                        Self::SEPARATOR
+                    ),
+                )
+                .into());
+            }
+        };
+        let (expected, parts) = Expression::parse(parts)?;
+        Ok((
+            Box::new(Self {
+                call: *call,
+                expected: *expected,
+                line: first.line,
+            }),
+            parts,
+        ))
+    }
+}
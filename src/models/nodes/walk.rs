@@ -0,0 +1,23 @@
+use crate::models::nodes::node::Node;
+
+/// Where a visited statement sits: how many blocks enclose it and whether
+/// one of them is a loop body.
+#[derive(Clone, Copy, Default)]
+pub struct Scope {
+    pub depth: usize,
+    pub in_loop: bool,
+}
+
+/// Calls `visit` for every statement in `body`, parents before children.
+pub fn walk<'a>(body: &'a [Node], scope: Scope, visit: &mut impl FnMut(&'a Node, Scope)) {
+    for node in body {
+        visit(node, scope);
+        let inner = Scope {
+            depth: scope.depth + 1,
+            in_loop: scope.in_loop || matches!(node, Node::Loop(_)),
+        };
+        for block in node.blocks() {
+            walk(block, inner, visit);
+        }
+    }
+}
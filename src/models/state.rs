@@ -1,23 +1,111 @@
-use edtui::EditorState;
-use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 
-use crate::models::nodes::node::Node;
+use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct ScrollState;
+use crate::models::{
+    analysis,
+    complexity::Measurement,
+    coverage::Coverage,
+    debugger::{Playback, Session},
+    diagnostics::Diagnostic,
+    interpreter::limits::Limits,
+    lint::config::LintConfig,
+    nodes::{parsing::ParseError, program::Program},
+    testing::TestResult,
+    trace::Trace,
+    transpile::Language,
+};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub enum AppState {
     LeftSelected,
     RightSelected,
+    Debugging,
+}
+
+/// What the right-hand pane shows.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub enum Panel {
+    #[default]
+    Preview,
+    Trace,
+    Complexity,
+    /// The program translated into another language.
+    Code,
+    /// The program drawn as flowcharts.
+    Flowchart,
+    /// The program drawn as Nassi–Shneiderman diagrams.
+    Structogram,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct State {
+    #[serde(skip)]
     pub should_quit: bool,
     pub app_state: AppState,
+    #[serde(default)]
+    pub show_types: bool,
+    #[serde(default)]
+    pub show_metrics: bool,
+    /// Whether the editor gutter shows how often each line ran.
+    #[serde(default)]
+    pub show_coverage: bool,
+    #[serde(default)]
+    pub panel: Panel,
+    #[serde(skip)]
+    pub lint_config: LintConfig,
+    /// Zero-based editor lines the debugger stops at.
+    #[serde(skip)]
+    pub breakpoints: BTreeSet<usize>,
+    #[serde(skip)]
+    pub debugger: Option<Session>,
+    /// First editor line on screen, tracked alongside the editor's own
+    /// scrolling so the gutter lines up with the text.
+    #[serde(skip)]
+    pub editor_scroll: usize,
+    #[serde(skip)]
+    pub trace: Option<Trace>,
+    #[serde(skip)]
+    pub trace_scroll: usize,
     #[serde(skip)]
-    pub editor_state: EditorState,
+    pub complexity: Option<Measurement>,
+    /// The language the code panel translates the program into.
+    #[serde(default)]
+    pub language: Language,
+    #[serde(skip)]
+    pub code_scroll: usize,
+    /// First row and column of the flowchart or structogram panel on screen.
+    #[serde(skip)]
+    pub diagram_scroll: usize,
+    #[serde(skip)]
+    pub diagram_pan: usize,
+    /// The editor line the diagram was last scrolled to, so it only follows
+    /// the cursor when that moves.
+    #[serde(skip)]
+    pub diagram_cursor: Option<usize>,
+    #[serde(default)]
+    pub playback: Playback,
+    /// The array variable drawn as bars while debugging; the first array in
+    /// scope when unset.
+    #[serde(skip)]
+    pub array_view: Option<String>,
+    /// A short message about the last action, such as where a file was
+    /// exported to.
+    #[serde(skip)]
+    pub status: Option<String>,
+    /// Settings files that couldn't be read at startup, and why.
+    #[serde(skip)]
+    pub settings_errors: Vec<String>,
+    /// Step and recursion limits for every run started from the editor.
+    #[serde(default)]
+    pub limits: Limits,
+    /// Results of the last test run, shown as gutter markers and failures
+    /// in the diagnostics.
+    #[serde(skip)]
+    pub test_results: Vec<TestResult>,
+    /// Lines run by the last test run or debug session.
+    #[serde(skip)]
+    pub coverage: Option<Coverage>,
 }
 
 impl State {
@@ -25,11 +113,47 @@ impl State {
         Ok(Self {
             should_quit: false,
             app_state: AppState::LeftSelected,
-            editor_state: EditorState::default(),
+            show_types: false,
+            show_metrics: false,
+            show_coverage: false,
+            panel: Panel::Preview,
+            lint_config: LintConfig::default(),
+            breakpoints: BTreeSet::new(),
+            debugger: None,
+            editor_scroll: 0,
+            trace: None,
+            trace_scroll: 0,
+            complexity: None,
+            language: Language::default(),
+            code_scroll: 0,
+            diagram_scroll: 0,
+            diagram_pan: 0,
+            diagram_cursor: None,
+            playback: Playback::default(),
+            array_view: None,
+            status: None,
+            settings_errors: Vec::new(),
+            limits: Limits::default(),
+            test_results: Vec::new(),
+            coverage: None,
         })
     }
 
-    pub fn get_output(text: &str) -> color_eyre::Result<Node> {
-        Node::new(text)
+    pub fn get_output(text: &str) -> color_eyre::Result<Program> {
+        Program::new(text)
+    }
+
+    pub fn get_diagnostics(
+        program: &color_eyre::Result<Program>,
+        lint_config: &LintConfig,
+    ) -> Vec<Diagnostic> {
+        match program {
+            Ok(program) => analysis::analyze(program, lint_config),
+            Err(e) => e
+                .downcast_ref::<ParseError>()
+                .map(ParseError::to_diagnostic)
+                .into_iter()
+                .collect(),
+        }
     }
 }
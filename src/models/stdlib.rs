@@ -0,0 +1,250 @@
+use core::fmt;
+
+/// The kind of value a built-in takes or gives back. The kinds that refer
+/// to other arguments let one signature cover several concrete types, e.g.
+/// `abs` returns an int for an int and a real for a real.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Int,
+    Real,
+    /// An int or a real.
+    Number,
+    String,
+    Bool,
+    Array,
+    /// An array of ints.
+    Ints,
+    /// An array or a string.
+    Sequence,
+    Any,
+    /// The type of the first argument, widened to real when numeric
+    /// arguments are mixed.
+    SameAsFirst,
+    /// No value; the built-in can only be used as a statement.
+    Nothing,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Kind::Int => "int",
+            Kind::Real => "real",
+            Kind::Number => "number",
+            Kind::String => "string",
+            Kind::Bool => "bool",
+            Kind::Array => "array",
+            Kind::Ints => "array<int>",
+            Kind::Sequence => "array|string",
+            Kind::Any => "any",
+            Kind::SameAsFirst => "same",
+            Kind::Nothing => "nothing",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// A function every program can call without defining it.
+pub struct Builtin {
+    pub name: &'static str,
+    pub params: &'static [Kind],
+    /// Whether the last parameter may be repeated any number of times,
+    /// including zero.
+    pub variadic: bool,
+    pub returns: Kind,
+    pub description: &'static str,
+}
+
+impl Builtin {
+    /// Whether `count` arguments fit the signature.
+    pub fn accepts_count(&self, count: usize) -> bool {
+        if self.variadic {
+            count + 1 >= self.params.len()
+        } else {
+            count == self.params.len()
+        }
+    }
+
+    /// The kind expected for the argument at `index`.
+    pub fn param(&self, index: usize) -> Option<Kind> {
+        match self.params.get(index) {
+            Some(kind) => Some(*kind),
+            None if self.variadic => self.params.last().copied(),
+            None => None,
+        }
+    }
+
+    /// The parameter list as written in the signature, e.g. `number, number`.
+    pub fn parameters(&self) -> String {
+        let params: Vec<String> = self.params.iter().map(Kind::to_string).collect();
+        let mut text = params.join(", ");
+        if self.variadic {
+            text.push_str("...");
+        }
+        text
+    }
+
+    pub fn arity(&self) -> String {
+        if self.variadic {
+            format!("at least {}", self.params.len().saturating_sub(1))
+        } else {
+            self.params.len().to_string()
+        }
+    }
+}
+
+impl fmt::Display for Builtin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let returns = match (self.returns, self.params.first()) {
+            (Kind::SameAsFirst, Some(first)) => *first,
+            (returns, _) => returns,
+        };
+        write!(f, "{}({}) -> {returns}", self.name, self.parameters())
+    }
+}
+
+pub const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "print",
+        params: &[Kind::Any],
+        variadic: true,
+        returns: Kind::Nothing,
+        description: "writes its arguments separated by spaces on one line",
+    },
+    Builtin {
+        name: "length",
+        params: &[Kind::Sequence],
+        variadic: false,
+        returns: Kind::Int,
+        description: "number of elements of an array or characters of a string",
+    },
+    Builtin {
+        name: "abs",
+        params: &[Kind::Number],
+        variadic: false,
+        returns: Kind::SameAsFirst,
+        description: "absolute value",
+    },
+    Builtin {
+        name: "min",
+        params: &[Kind::Number, Kind::Number],
+        variadic: false,
+        returns: Kind::SameAsFirst,
+        description: "the smaller of two numbers",
+    },
+    Builtin {
+        name: "max",
+        params: &[Kind::Number, Kind::Number],
+        variadic: false,
+        returns: Kind::SameAsFirst,
+        description: "the larger of two numbers",
+    },
+    Builtin {
+        name: "sqrt",
+        params: &[Kind::Number],
+        variadic: false,
+        returns: Kind::Real,
+        description: "square root",
+    },
+    Builtin {
+        name: "pow",
+        params: &[Kind::Number, Kind::Number],
+        variadic: false,
+        returns: Kind::Real,
+        description: "the first number raised to the power of the second",
+    },
+    Builtin {
+        name: "floor",
+        params: &[Kind::Number],
+        variadic: false,
+        returns: Kind::Int,
+        description: "largest int not greater than the number",
+    },
+    Builtin {
+        name: "ceil",
+        params: &[Kind::Number],
+        variadic: false,
+        returns: Kind::Int,
+        description: "smallest int not less than the number",
+    },
+    Builtin {
+        name: "round",
+        params: &[Kind::Number],
+        variadic: false,
+        returns: Kind::Int,
+        description: "nearest int, halves rounded away from zero",
+    },
+    Builtin {
+        name: "random",
+        params: &[Kind::Int],
+        variadic: false,
+        returns: Kind::Int,
+        description: "random int from 0 up to but not including the argument",
+    },
+    Builtin {
+        name: "range",
+        params: &[Kind::Int, Kind::Int],
+        variadic: false,
+        returns: Kind::Ints,
+        description: "array of the ints from the first up to but not including the second",
+    },
+    Builtin {
+        name: "append",
+        params: &[Kind::Array, Kind::Any],
+        variadic: false,
+        returns: Kind::SameAsFirst,
+        description: "copy of the array with the value added at the end",
+    },
+    Builtin {
+        name: "contains",
+        params: &[Kind::Sequence, Kind::Any],
+        variadic: false,
+        returns: Kind::Bool,
+        description: "whether an array holds the value or a string holds the substring",
+    },
+    Builtin {
+        name: "substring",
+        params: &[Kind::String, Kind::Int, Kind::Int],
+        variadic: false,
+        returns: Kind::String,
+        description: "characters from the start index up to but not including the end index",
+    },
+    Builtin {
+        name: "to_upper",
+        params: &[Kind::String],
+        variadic: false,
+        returns: Kind::String,
+        description: "string in upper case",
+    },
+    Builtin {
+        name: "to_lower",
+        params: &[Kind::String],
+        variadic: false,
+        returns: Kind::String,
+        description: "string in lower case",
+    },
+    Builtin {
+        name: "to_string",
+        params: &[Kind::Any],
+        variadic: false,
+        returns: Kind::String,
+        description: "the value as text, as `print` would write it",
+    },
+    Builtin {
+        name: "to_int",
+        params: &[Kind::Any],
+        variadic: false,
+        returns: Kind::Int,
+        description: "a number truncated towards zero, or a string parsed as an int",
+    },
+    Builtin {
+        name: "to_real",
+        params: &[Kind::Any],
+        variadic: false,
+        returns: Kind::Real,
+        description: "a number as a real, or a string parsed as a real",
+    },
+];
+
+pub fn builtin(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
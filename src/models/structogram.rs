@@ -0,0 +1,382 @@
+use crate::models::{
+    canvas::{Canvas, Region},
+    flowchart::{condition, expression, statement},
+    nodes::{
+        condition::{Condition, Else, ElseIf},
+        loops::{Loop, LoopType},
+        node::Node,
+        program::Program,
+    },
+};
+
+/// Blank rows between the structograms of two routines.
+const ROUTINE_GAP: usize = 2;
+/// Rows an `if` takes above its branches: the border, the condition, the
+/// `yes` and `no` labels and the line the branches start on.
+const HEADER: usize = 4;
+/// Columns a loop's body is moved right by, leaving the bar on its left.
+const BAR: usize = 2;
+/// The narrowest the `yes` and `no` sides of an `if` can be with room for
+/// their label between the border and the diagonal.
+const YES_WIDTH: usize = 8;
+const NO_WIDTH: usize = 7;
+
+/// Something drawn, in character cells. Lines run through the middle of the
+/// cells they cover, so neighbouring boxes share their borders.
+pub enum Mark {
+    Box {
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    },
+    Diagonal {
+        from: (usize, usize),
+        to: (usize, usize),
+    },
+    /// Text starting at `x`, `y`.
+    Label {
+        x: usize,
+        y: usize,
+        text: String,
+        bold: bool,
+    },
+}
+
+/// Nassi–Shneiderman diagrams of a program: a sequence is a stack of boxes,
+/// an `if` a box split by two diagonals into its `yes` and `no` sides, and a
+/// loop a box wrapped around its body on the left.
+pub struct Structogram {
+    pub width: usize,
+    pub height: usize,
+    pub marks: Vec<Mark>,
+    pub regions: Vec<Region>,
+}
+
+/// A box of the diagram before it is placed.
+enum Element {
+    Statement {
+        text: String,
+        line: Option<usize>,
+        bold: bool,
+    },
+    Branch {
+        text: String,
+        line: Option<usize>,
+        yes: Vec<Element>,
+        no: Vec<Element>,
+    },
+    Loop {
+        text: String,
+        line: usize,
+        body: Vec<Element>,
+    },
+}
+
+/// The structograms of every function and `start`, one below the other.
+pub fn structogram(program: &Program) -> Structogram {
+    let mut routines: Vec<Vec<Element>> = program
+        .functions
+        .iter()
+        .map(|function| {
+            let title = format!("{}({})", function.name, function.params.join(", "));
+            routine(title, function.line, &function.body)
+        })
+        .collect();
+    routines.push(routine(
+        "start".to_string(),
+        program.start.line,
+        &program.start.body,
+    ));
+    let width = routines
+        .iter()
+        .map(|routine| sequence_width(routine))
+        .max()
+        .unwrap_or(0);
+    let mut structogram = Structogram {
+        width,
+        height: 0,
+        marks: Vec::new(),
+        regions: Vec::new(),
+    };
+    let mut y = 0;
+    for routine in &routines {
+        let height = sequence_height(routine);
+        structogram.sequence(routine, 0, y, width, height);
+        y += height + ROUTINE_GAP;
+    }
+    structogram.height = y.saturating_sub(ROUTINE_GAP);
+    structogram
+}
+
+impl Structogram {
+    /// The diagrams drawn with box-drawing characters, each diagonal as one
+    /// slash on each row it crosses.
+    pub fn canvas(&self) -> Canvas {
+        let mut canvas = Canvas::new(self.width, self.height);
+        for mark in &self.marks {
+            match mark {
+                Mark::Box {
+                    x,
+                    y,
+                    width,
+                    height,
+                } => canvas.rectangle(*x, *y, *width, *height),
+                Mark::Diagonal { from, to } => {
+                    let slash = if to.0 < from.0 { "╱" } else { "╲" };
+                    for row in from.1 + 1..to.1 {
+                        canvas.text(diagonal(*from, *to, row), row, slash);
+                    }
+                }
+                Mark::Label { x, y, text, .. } => canvas.text(*x, *y, text),
+            }
+        }
+        canvas.regions = self.regions.clone();
+        canvas
+    }
+
+    /// Places `elements` one below the other, the last one stretched down to
+    /// fill `height`.
+    fn sequence(&mut self, elements: &[Element], x: usize, y: usize, width: usize, height: usize) {
+        let mut top = y;
+        for (index, element) in elements.iter().enumerate() {
+            let rows = if index + 1 == elements.len() {
+                y + height - top
+            } else {
+                element.height()
+            };
+            self.element(element, x, top, width, rows);
+            top += rows - 1;
+        }
+    }
+
+    fn element(&mut self, element: &Element, x: usize, y: usize, width: usize, height: usize) {
+        self.marks.push(Mark::Box {
+            x,
+            y,
+            width,
+            height,
+        });
+        match element {
+            Element::Statement { text, line, bold } => {
+                self.label(x + 2, y + 1, text, *bold);
+                self.region(*line, x, y, width, height);
+            }
+            Element::Branch {
+                text,
+                line,
+                yes,
+                no,
+            } => {
+                let (yes_width, no_width) = split(
+                    text.chars().count(),
+                    sequence_width(yes),
+                    sequence_width(no),
+                );
+                // Whatever the parent adds is shared out between the sides.
+                let extra = width - (yes_width + no_width - 1);
+                let yes_width = yes_width + extra / 2;
+                let split = x + yes_width - 1;
+                let right = x + width - 1;
+                let bottom = y + HEADER - 1;
+                self.marks.push(Mark::Diagonal {
+                    from: (x, y),
+                    to: (split, bottom),
+                });
+                self.marks.push(Mark::Diagonal {
+                    from: (right, y),
+                    to: (split, bottom),
+                });
+                let left_gap = diagonal((x, y), (split, bottom), y + 1);
+                let right_gap = diagonal((right, y), (split, bottom), y + 1);
+                let length = text.chars().count();
+                let room = right_gap - left_gap - 1;
+                self.label(left_gap + 1 + (room - length) / 2, y + 1, text, false);
+                self.label(x + 1, y + 2, "yes", false);
+                self.label(right - 2, y + 2, "no", false);
+                self.region(*line, x, y, width, HEADER);
+                self.sequence(yes, x, bottom, yes_width, height - HEADER + 1);
+                self.sequence(no, split, bottom, right - split + 1, height - HEADER + 1);
+            }
+            Element::Loop { text, line, body } => {
+                self.label(x + 2, y + 1, text, false);
+                self.region(Some(*line), x, y, width, 3);
+                self.sequence(body, x + BAR, y + 2, width - BAR, height - 2);
+            }
+        }
+    }
+
+    fn label(&mut self, x: usize, y: usize, text: &str, bold: bool) {
+        if !text.is_empty() {
+            self.marks.push(Mark::Label {
+                x,
+                y,
+                text: text.to_string(),
+                bold,
+            });
+        }
+    }
+
+    fn region(&mut self, line: Option<usize>, x: usize, y: usize, width: usize, height: usize) {
+        if let Some(line) = line {
+            self.regions.push(Region {
+                line,
+                x,
+                y,
+                width,
+                height,
+            });
+        }
+    }
+}
+
+impl Element {
+    /// The narrowest the element can be drawn.
+    fn width(&self) -> usize {
+        match self {
+            Element::Statement { text, .. } => text.chars().count() + 4,
+            Element::Branch { text, yes, no, .. } => {
+                let (yes, no) = split(
+                    text.chars().count(),
+                    sequence_width(yes),
+                    sequence_width(no),
+                );
+                yes + no - 1
+            }
+            Element::Loop { text, body, .. } => {
+                (text.chars().count() + 4).max(sequence_width(body) + BAR)
+            }
+        }
+    }
+
+    fn height(&self) -> usize {
+        match self {
+            Element::Statement { .. } => 3,
+            Element::Branch { yes, no, .. } => {
+                HEADER - 1 + sequence_height(yes).max(sequence_height(no))
+            }
+            Element::Loop { body, .. } => 2 + sequence_height(body),
+        }
+    }
+}
+
+fn sequence_width(elements: &[Element]) -> usize {
+    elements.iter().map(Element::width).max().unwrap_or(0)
+}
+
+/// Boxes one below the other share the border between them.
+fn sequence_height(elements: &[Element]) -> usize {
+    elements.iter().map(Element::height).sum::<usize>() + 1 - elements.len()
+}
+
+/// The widths of the `yes` and `no` sides of an `if`, widened until the
+/// condition fits between the diagonals.
+fn split(length: usize, yes: usize, no: usize) -> (usize, usize) {
+    let (mut yes, mut no) = (yes.max(YES_WIDTH), no.max(NO_WIDTH));
+    loop {
+        let (split, right) = (yes - 1, yes + no - 2);
+        let left_gap = diagonal((0, 0), (split, HEADER - 1), 1);
+        let right_gap = diagonal((right, 0), (split, HEADER - 1), 1);
+        if right_gap - left_gap > length + 2 {
+            return (yes, no);
+        }
+        if yes <= no {
+            yes += 1;
+        } else {
+            no += 1;
+        }
+    }
+}
+
+/// The column a diagonal line crosses `row` at.
+fn diagonal(from: (usize, usize), to: (usize, usize), row: usize) -> usize {
+    let steps = to.1 - from.1;
+    let step = row - from.1;
+    if to.0 >= from.0 {
+        from.0 + (to.0 - from.0) * step / steps
+    } else {
+        from.0 - (from.0 - to.0) * step / steps
+    }
+}
+
+fn routine(title: String, line: usize, body: &[Node]) -> Vec<Element> {
+    let mut elements = vec![Element::Statement {
+        text: title,
+        line: Some(line),
+        bold: true,
+    }];
+    elements.extend(sequence(body));
+    elements
+}
+
+/// The elements of a body, or an empty box when it has no statements.
+fn sequence(body: &[Node]) -> Vec<Element> {
+    let elements: Vec<Element> = body.iter().filter_map(node).collect();
+    if elements.is_empty() {
+        return vec![Element::Statement {
+            text: String::new(),
+            line: None,
+            bold: false,
+        }];
+    }
+    elements
+}
+
+fn node(node: &Node) -> Option<Element> {
+    let line = match node {
+        Node::Assignment(value) => value.line,
+        Node::FunctionCall(value) => value.line,
+        Node::Return(value) => value.line,
+        Node::Break(value) => value.line,
+        Node::Continue(value) => value.line,
+        Node::If(statement) => {
+            return Some(branch(
+                &statement.condition,
+                Some(statement.line),
+                &statement.body,
+                &statement.else_ifs,
+                statement.else_branch.as_ref(),
+            ));
+        }
+        Node::Loop(statement) => return Some(loop_statement(statement)),
+        Node::Comment(_) => return None,
+    };
+    Some(Element::Statement {
+        text: statement(node),
+        line: Some(line),
+        bold: false,
+    })
+}
+
+/// An `if`, each `elseif` drawn as an `if` on the `no` side of the one
+/// before.
+fn branch(
+    test: &Condition,
+    line: Option<usize>,
+    body: &[Node],
+    else_ifs: &[ElseIf],
+    else_branch: Option<&Else>,
+) -> Element {
+    let no = match else_ifs.split_first() {
+        Some((next, rest)) => vec![branch(&next.condition, None, &next.body, rest, else_branch)],
+        None => sequence(else_branch.map_or(&[], |branch| &branch.body)),
+    };
+    Element::Branch {
+        text: condition(test),
+        line,
+        yes: sequence(body),
+        no,
+    }
+}
+
+fn loop_statement(statement: &Loop) -> Element {
+    let text = match &statement.loop_type {
+        LoopType::While(test) => format!("while {}", condition(test)),
+        LoopType::For { item, list } => format!("for each {item} in {}", expression(list)),
+    };
+    Element::Loop {
+        text,
+        line: statement.line,
+        body: sequence(&statement.body),
+    }
+}
@@ -0,0 +1,151 @@
+use crate::models::{
+    coverage::Coverage,
+    interpreter::{limits::Limits, value::Value, vm::Vm},
+    nodes::{operator::ComparisonOperator, program::Program, test_case::TestCase},
+};
+
+pub enum Outcome {
+    Passed,
+    Failed {
+        actual: Value,
+        expected: Value,
+        /// Where the two values first differ, such as `[2]` or `.name`,
+        /// when they are arrays or records.
+        difference: Option<String>,
+    },
+    /// The call or the expected value could not be evaluated.
+    Error(String),
+}
+
+pub struct TestResult {
+    pub line: usize,
+    /// The test as written, e.g. `binary_search([1, 3, 5], 5) == 2`.
+    pub name: String,
+    pub outcome: Outcome,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        matches!(self.outcome, Outcome::Passed)
+    }
+
+    /// What went wrong, one line per entry; empty for a passing test.
+    pub fn details(&self) -> Vec<String> {
+        match &self.outcome {
+            Outcome::Passed => Vec::new(),
+            Outcome::Failed {
+                actual,
+                expected,
+                difference,
+            } => {
+                let mut details =
+                    vec![format!("expected {expected}"), format!("actual   {actual}")];
+                details.extend(difference.iter().map(|at| format!("differs at {at}")));
+                details
+            }
+            Outcome::Error(message) => vec![message.clone()],
+        }
+    }
+}
+
+/// Runs every test against a fresh interpreter, so tests can't affect each
+/// other, and adds the statements they ran to `coverage`.
+pub fn run(
+    program: &Program,
+    tests: &[TestCase],
+    limits: &Limits,
+    coverage: &mut Coverage,
+) -> Vec<TestResult> {
+    tests
+        .iter()
+        .map(|test| {
+            let mut vm = Vm::with_observer(program, Coverage::default());
+            vm.limits = limits.clone();
+            let outcome = run_test(&mut vm, test);
+            coverage.add(&vm.observer);
+            TestResult {
+                line: test.line,
+                name: format!("{} == {}", test.call, test.expected),
+                outcome,
+            }
+        })
+        .collect()
+}
+
+fn run_test(vm: &mut Vm<Coverage>, test: &TestCase) -> Outcome {
+    let actual = match vm.evaluate_expression(&test.call, test.line) {
+        Ok(value) => value,
+        Err(error) => return Outcome::Error(error.to_string()),
+    };
+    let expected = match vm.evaluate_expression(&test.expected, test.line) {
+        Ok(value) => value,
+        Err(error) => return Outcome::Error(error.to_string()),
+    };
+    if equal(&actual, &expected) {
+        return Outcome::Passed;
+    }
+    Outcome::Failed {
+        difference: difference(&actual, &expected, String::new()),
+        actual,
+        expected,
+    }
+}
+
+/// Equality as `==` sees it, so `2` matches `2.0`, applied element by
+/// element inside arrays and records.
+fn equal(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Array(actual), Value::Array(expected)) => {
+            actual.len() == expected.len() && actual.iter().zip(expected).all(|(a, e)| equal(a, e))
+        }
+        (Value::Record(actual), Value::Record(expected)) => {
+            actual.len() == expected.len()
+                && expected.iter().all(|(name, expected)| {
+                    actual
+                        .iter()
+                        .any(|(field, actual)| field == name && equal(actual, expected))
+                })
+        }
+        _ => actual
+            .compare(&ComparisonOperator::Eq, expected)
+            .unwrap_or(false),
+    }
+}
+
+/// The path to the first element or field where two unequal arrays or
+/// records differ. `None` when the values are not both arrays or records, as
+/// the whole value is then the difference.
+fn difference(actual: &Value, expected: &Value, path: String) -> Option<String> {
+    match (actual, expected) {
+        (Value::Array(actual), Value::Array(expected)) => {
+            let mismatch = actual
+                .iter()
+                .zip(expected)
+                .position(|(actual, expected)| !equal(actual, expected));
+            match mismatch {
+                Some(i) => {
+                    let path = format!("{path}[{i}]");
+                    difference(&actual[i], &expected[i], path.clone()).or(Some(path))
+                }
+                None => Some(format!("{path}[{}]", actual.len().min(expected.len()))),
+            }
+        }
+        (Value::Record(actual), Value::Record(expected)) => {
+            for (name, expected) in expected {
+                let path = format!("{path}.{name}");
+                match actual.iter().find(|(field, _)| field == name) {
+                    Some((_, actual)) if equal(actual, expected) => {}
+                    Some((_, actual)) => {
+                        return difference(actual, expected, path.clone()).or(Some(path));
+                    }
+                    None => return Some(format!("{path} (missing)")),
+                }
+            }
+            actual
+                .iter()
+                .find(|(field, _)| !expected.iter().any(|(name, _)| name == field))
+                .map(|(field, _)| format!("{path}.{field} (unexpected)"))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,198 @@
+use color_eyre::eyre::Result;
+
+use crate::models::{
+    interpreter::{Frame, Interpreter, Observer, limits::Limits, value::Value},
+    nodes::program::Program,
+};
+
+/// One executed statement of the traced routine and the variables right
+/// after it ran.
+pub struct TraceRow {
+    pub line: usize,
+    /// Values in the order of [`Trace::columns`]; `None` while a variable is
+    /// not assigned yet.
+    pub values: Vec<Option<Value>>,
+    /// Lines printed while the statement ran, including by calls it made.
+    pub output: Vec<String>,
+}
+
+/// Rows a trace table keeps; the run goes on without recording the rest.
+const ROW_LIMIT: usize = 10_000;
+/// Values the rows of a trace table keep copies of, so that a few large
+/// arrays can't use up the memory before [`ROW_LIMIT`] is reached.
+const VALUE_LIMIT: usize = 4_000_000;
+
+/// A trace table of a single routine: one column per variable and one row
+/// per executed statement or loop iteration. Statements inside called
+/// functions are folded into the call.
+pub struct Trace {
+    pub columns: Vec<String>,
+    pub rows: Vec<TraceRow>,
+    /// The runtime error that ended the run, if any.
+    pub error: Option<String>,
+    /// Whether the run went on past the last row recorded.
+    pub truncated: bool,
+}
+
+#[derive(Default)]
+struct Tracer {
+    columns: Vec<String>,
+    rows: Vec<TraceRow>,
+    /// Line and output length of the statement that is running.
+    pending: Option<(usize, usize)>,
+    /// Values copied into the rows so far.
+    recorded: usize,
+    truncated: bool,
+}
+
+impl Tracer {
+    fn finish_row(&mut self, frame: &Frame, output: &[String]) {
+        let Some((line, printed)) = self.pending.take() else {
+            return;
+        };
+        if self.truncated {
+            return;
+        }
+        let size: usize = frame.variables.iter().map(|(_, value)| value.size()).sum();
+        if self.rows.len() == ROW_LIMIT || self.recorded + size > VALUE_LIMIT {
+            self.truncated = true;
+            return;
+        }
+        self.recorded += size;
+        for (name, _) in &frame.variables {
+            if !self.columns.contains(name) {
+                self.columns.push(name.clone());
+            }
+        }
+        self.rows.push(TraceRow {
+            line,
+            values: self
+                .columns
+                .iter()
+                .map(|column| frame.get(column).cloned())
+                .collect(),
+            output: output[printed..].to_vec(),
+        });
+    }
+}
+
+impl Observer for Tracer {
+    fn statement(&mut self, line: usize, frames: &[Frame], output: &[String]) -> Result<()> {
+        if let [frame] = frames {
+            self.finish_row(frame, output);
+            self.pending = Some((line, output.len()));
+        }
+        Ok(())
+    }
+
+    fn exit(&mut self, frames: &[Frame], output: &[String]) -> Result<()> {
+        if let [frame] = frames {
+            self.finish_row(frame, output);
+        }
+        Ok(())
+    }
+}
+
+impl Trace {
+    /// Runs the `start` block, or calls `function` with arguments given as
+    /// source text such as `[1, 3, 5]`, and records its trace table.
+    pub fn record(
+        program: &Program,
+        function: Option<(&str, &[String])>,
+        limits: &Limits,
+    ) -> Result<Self> {
+        let mut interpreter = Interpreter::with_observer(program, Tracer::default());
+        interpreter.limits = limits.clone();
+        let result = match function {
+            Some((name, inputs)) => {
+                let args = inputs
+                    .iter()
+                    .map(|input| interpreter.evaluate_input(input))
+                    .collect::<Result<Vec<_>>>()?;
+                interpreter.call(name, args, 0)
+            }
+            None => interpreter.run(),
+        };
+        Ok(Self {
+            columns: interpreter.observer.columns,
+            rows: interpreter.observer.rows,
+            error: result.err().map(|error| error.to_string()),
+            truncated: interpreter.observer.truncated,
+        })
+    }
+
+    /// What the table leaves out, for a line below it.
+    pub fn truncation(&self) -> Option<String> {
+        self.truncated
+            .then(|| format!("trace truncated after {} rows", self.rows.len()))
+    }
+
+    pub fn header(&self) -> Vec<String> {
+        let mut header = vec!["step".to_string(), "line".to_string()];
+        header.extend(self.columns.iter().cloned());
+        header.push("output".to_string());
+        header
+    }
+
+    /// The cells of each row, lined up with [`Trace::header`].
+    pub fn cells(&self) -> Vec<Vec<String>> {
+        self.rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let mut cells = vec![(i + 1).to_string(), (row.line + 1).to_string()];
+                cells.extend((0..self.columns.len()).map(|column| {
+                    row.values
+                        .get(column)
+                        .and_then(Option::as_ref)
+                        .map(Value::to_string)
+                        .unwrap_or_default()
+                }));
+                cells.push(row.output.join("\n"));
+                cells
+            })
+            .collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        for cells in [self.header()].into_iter().chain(self.cells()) {
+            let escaped: Vec<String> = cells.iter().map(|cell| csv_field(cell)).collect();
+            csv.push_str(&escaped.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let header = self.header();
+        let mut markdown = markdown_row(&header);
+        markdown.push_str(&markdown_row(&vec!["---".to_string(); header.len()]));
+        for cells in self.cells() {
+            markdown.push_str(&markdown_row(&cells));
+        }
+        if let Some(truncation) = self.truncation() {
+            markdown.push_str(&format!("\n{truncation}\n"));
+        }
+        if let Some(error) = &self.error {
+            markdown.push_str(&format!("\n{error}\n"));
+        }
+        markdown
+    }
+}
+
+fn csv_field(cell: &str) -> String {
+    if cell.contains([',', '"', '\n']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+fn markdown_row(cells: &[String]) -> String {
+    let cells: Vec<String> = cells
+        .iter()
+        .map(|cell| cell.replace('|', "\\|").replace('\n', "<br>"))
+        .collect();
+    format!("| {} |\n", cells.join(" | "))
+}
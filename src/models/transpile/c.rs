@@ -0,0 +1,1555 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use color_eyre::eyre::{Result, eyre};
+
+use crate::models::{
+    analysis::types::{Type, Typing},
+    nodes::{
+        assignment::Assignment,
+        comment::Comment,
+        condition::{Condition, If},
+        expression::Expression,
+        function::{Function, FunctionCall},
+        loops::{Loop, LoopType},
+        node::Node,
+        operator::{ArithmeticOperator, ComparisonOperator, LogicalOperator},
+        program::Program,
+        return_node::Return,
+        start::Start,
+        test_case::TestCase,
+        walk::{Scope, walk},
+    },
+    transpile::{Emitter, Scan, Signature, Writer, always_returns, modified, root, signatures},
+};
+
+const INDENT: &str = "    ";
+
+/// C keywords, and the library functions and helpers the generated code
+/// uses, which a pseudocode name gets a trailing `_` to stay clear of.
+const RESERVED: &[&str] = &[
+    "auto",
+    "bool",
+    "break",
+    "case",
+    "char",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "register",
+    "restrict",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "true",
+    "typedef",
+    "union",
+    "unsigned",
+    "void",
+    "volatile",
+    "while",
+    "NULL",
+    "abs",
+    "assert",
+    "atof",
+    "atoll",
+    "ceil",
+    "exit",
+    "fabs",
+    "floor",
+    "fmax",
+    "fmin",
+    "fmod",
+    "free",
+    "llabs",
+    "llround",
+    "main",
+    "malloc",
+    "memcpy",
+    "pow",
+    "printf",
+    "rand",
+    "round",
+    "sqrt",
+    "srand",
+    "strcat",
+    "strcmp",
+    "strcpy",
+    "strlen",
+    "strstr",
+    "time",
+    "toupper",
+    "tolower",
+    "int_max",
+    "int_min",
+    "int_to_string",
+    "format_real",
+    "print_real",
+    "range",
+    "real_to_string",
+    "string_at",
+    "string_concat",
+    "string_lower",
+    "string_upper",
+    "substring",
+];
+
+/// How tightly a C expression binds, loosest first. A conditional
+/// expression binds looser than all of them.
+const CONDITIONAL: u8 = 0;
+const OR: u8 = 1;
+const AND: u8 = 2;
+const EQUALITY: u8 = 3;
+const RELATIONAL: u8 = 4;
+const SUM: u8 = 5;
+const PRODUCT: u8 = 6;
+const UNARY: u8 = 7;
+const ATOM: u8 = 8;
+
+/// C text and how tightly it binds, or why the construct has no
+/// translation.
+type Translation = std::result::Result<(String, u8), String>;
+
+const STRING_CONCAT: &str = "\
+char *string_concat(const char *left, const char *right) {
+    char *text = malloc(strlen(left) + strlen(right) + 1);
+    strcpy(text, left);
+    strcat(text, right);
+    return text;
+}
+";
+const STRING_AT: &str = "\
+char *string_at(const char *text, long long index) {
+    char *character = malloc(2);
+    character[0] = text[index];
+    character[1] = '\\0';
+    return character;
+}
+";
+const SUBSTRING: &str = "\
+char *substring(const char *text, long long from, long long to) {
+    char *part = malloc(to - from + 1);
+    memcpy(part, text + from, to - from);
+    part[to - from] = '\\0';
+    return part;
+}
+";
+const STRING_UPPER: &str = "\
+char *string_upper(const char *text) {
+    size_t length = strlen(text);
+    char *result = malloc(length + 1);
+    for (size_t i = 0; i <= length; i++) {
+        result[i] = toupper((unsigned char)text[i]);
+    }
+    return result;
+}
+";
+const STRING_LOWER: &str = "\
+char *string_lower(const char *text) {
+    size_t length = strlen(text);
+    char *result = malloc(length + 1);
+    for (size_t i = 0; i <= length; i++) {
+        result[i] = tolower((unsigned char)text[i]);
+    }
+    return result;
+}
+";
+const INT_TO_STRING: &str = "\
+char *int_to_string(long long value) {
+    char *text = malloc(21);
+    snprintf(text, 21, \"%lld\", value);
+    return text;
+}
+";
+const FORMAT_REAL: &str = "\
+void format_real(char *text, double value) {
+    for (int digits = 1; digits <= 17; digits++) {
+        snprintf(text, 32, \"%.*g\", digits, value);
+        if (strtod(text, NULL) == value) {
+            break;
+        }
+    }
+    if (strspn(text, \"-0123456789\") == strlen(text)) {
+        strcat(text, \".0\");
+    }
+}
+";
+const REAL_TO_STRING: &str = "\
+char *real_to_string(double value) {
+    char *text = malloc(32);
+    format_real(text, value);
+    return text;
+}
+";
+const PRINT_REAL: &str = "\
+void print_real(double value) {
+    char text[32];
+    format_real(text, value);
+    fputs(text, stdout);
+}
+";
+const INT_MIN: &str = "\
+long long int_min(long long left, long long right) {
+    return left < right ? left : right;
+}
+";
+const INT_MAX: &str = "\
+long long int_max(long long left, long long right) {
+    return left > right ? left : right;
+}
+";
+const RANGE: &str = "\
+IntArray range(long long from, long long to) {
+    long long length = to > from ? to - from : 0;
+    IntArray array = {malloc(sizeof(long long) * (length > 0 ? length : 1)), length};
+    for (long long i = 0; i < length; i++) {
+        array.items[i] = from + i;
+    }
+    return array;
+}
+";
+
+/// The operations on an array type, with `$ARRAY` for its name, `$ITEM` for
+/// the element type, `$ITEM_PARAM` and `$ITEMS_PARAM` for a parameter
+/// holding one or a pointer to several elements, and `$EQUALS`, `$DIFFERS`,
+/// `$COPY` and `$PRINT` for code on the `i`th element. Copying the elements
+/// themselves only matters for arrays of arrays.
+const ARRAY_OF: &str = "\
+$ARRAY $ARRAY_of(long long length, $ITEMS_PARAM) {
+    $ARRAY array = {malloc(sizeof($ITEM) * (length > 0 ? length : 1)), length};
+    for (long long i = 0; i < length; i++) {
+        array.items[i] = items[i];
+    }
+    return array;
+}
+";
+const ARRAY_COPY: &str = "\
+$ARRAY $ARRAY_copy($ARRAY array) {
+    return $ARRAY_of(array.length, array.items);
+}
+";
+const ARRAY_COPY_NESTED: &str = "\
+$ARRAY $ARRAY_copy($ARRAY array) {
+    $ARRAY copy = $ARRAY_of(array.length, array.items);
+    for (long long i = 0; i < copy.length; i++) {
+        $COPY
+    }
+    return copy;
+}
+";
+const ARRAY_APPEND: &str = "\
+$ARRAY $ARRAY_append($ARRAY array, $ITEM_PARAM) {
+    $ARRAY result = {malloc(sizeof($ITEM) * (array.length + 1)), array.length + 1};
+    for (long long i = 0; i < array.length; i++) {
+        result.items[i] = array.items[i];
+    }
+    result.items[array.length] = item;
+    return result;
+}
+";
+const ARRAY_CONCAT: &str = "\
+$ARRAY $ARRAY_concat($ARRAY left, $ARRAY right) {
+    long long length = left.length + right.length;
+    $ARRAY result = {malloc(sizeof($ITEM) * (length > 0 ? length : 1)), length};
+    for (long long i = 0; i < left.length; i++) {
+        result.items[i] = left.items[i];
+    }
+    for (long long i = 0; i < right.length; i++) {
+        result.items[left.length + i] = right.items[i];
+    }
+    return result;
+}
+";
+const ARRAY_EQUALS: &str = "\
+bool $ARRAY_equals($ARRAY left, $ARRAY right) {
+    if (left.length != right.length) {
+        return false;
+    }
+    for (long long i = 0; i < left.length; i++) {
+        if ($DIFFERS) {
+            return false;
+        }
+    }
+    return true;
+}
+";
+const ARRAY_CONTAINS: &str = "\
+bool $ARRAY_contains($ARRAY array, $ITEM_PARAM) {
+    for (long long i = 0; i < array.length; i++) {
+        if ($EQUALS) {
+            return true;
+        }
+    }
+    return false;
+}
+";
+const ARRAY_PRINT: &str = "\
+void $ARRAY_print($ARRAY array) {
+    printf(\"[\");
+    for (long long i = 0; i < array.length; i++) {
+        if (i > 0) {
+            printf(\", \");
+        }
+        $PRINT
+    }
+    printf(\"]\");
+}
+";
+
+/// The program as a C99 source file: a struct per array and record type,
+/// a prototype and a definition per pseudocode function, and `start` as
+/// `main`. The `test` lines become a second `main` that runs them with
+/// `assert`, compiled in with `-DTESTS`. Arrays are allocated with `malloc`
+/// and never freed.
+///
+/// Types come from inference, so a function is only translated when
+/// something calls it with arguments of known types. When any construct
+/// has no translation the result is an error listing each one with its
+/// line, rather than code that doesn't compile.
+pub fn transpile(program: &Program) -> Result<String> {
+    let mut c = C::new(program);
+    let mut prototypes = Vec::new();
+    let mut definitions = Vec::new();
+    for function in &program.functions {
+        match c.function(function) {
+            Some((prototype, definition)) => {
+                prototypes.push(prototype);
+                definitions.push(definition);
+            }
+            None => definitions.push(format!(
+                "// '{}' is left out, since nothing calls it with arguments of known types.\n",
+                function.name
+            )),
+        }
+    }
+    let main = c.main(&program.start);
+    let tests = c.tests(&program.tests);
+    if !c.diagnostics.is_empty() {
+        return Err(eyre!(
+            "cannot translate to C:\n{}",
+            c.diagnostics.join("\n")
+        ));
+    }
+
+    let mut sections = Vec::new();
+    let includes: Vec<String> = c
+        .includes
+        .iter()
+        .map(|header| format!("#include <{header}>\n"))
+        .collect();
+    sections.push(includes.concat());
+    sections.extend(c.typedefs);
+    sections.extend(c.helpers);
+    if !prototypes.is_empty() {
+        sections.push(prototypes.concat());
+    }
+    sections.extend(definitions);
+    sections.push(match tests {
+        Some(tests) => format!("#ifdef TESTS\n{tests}#else\n{main}#endif\n"),
+        None => main,
+    });
+    Ok(sections.join("\n"))
+}
+
+struct C<'a> {
+    typing: Typing,
+    writer: Writer,
+    /// Names of the program's own functions, which win over builtins.
+    functions: HashSet<&'a str>,
+    /// Signatures of the functions whose types are all known.
+    signatures: HashMap<&'a str, Signature>,
+    /// Each construct without a translation, with its line.
+    diagnostics: Vec<String>,
+    includes: BTreeSet<&'static str>,
+    /// Struct definitions, each after the types it uses.
+    typedefs: Vec<String>,
+    /// Names of the structs defined so far.
+    types: HashSet<String>,
+    /// Fields of the record type behind each struct, in order of first use;
+    /// the struct is named after its position.
+    records: Vec<Vec<(String, Type)>>,
+    /// Helper function definitions, each after the helpers it calls.
+    helpers: Vec<String>,
+    /// Names of the helpers defined so far.
+    helper_names: HashSet<String>,
+    /// Whether `random` is used, so `main` seeds the generator.
+    random: bool,
+    /// Types of the variables of the current routine.
+    env: HashMap<String, Type>,
+    /// Return type of the current routine, `None` for one that returns
+    /// nothing.
+    returns: Option<Type>,
+    /// Whether the current routine is `main`.
+    in_main: bool,
+    /// Variables of the current routine with an element or field assigned.
+    modified: HashSet<String>,
+    /// Variables of the current routine assigned again after their
+    /// declaration.
+    reassigned: HashSet<String>,
+    /// Variables declared so far in the current routine.
+    declared: HashSet<String>,
+    /// How many loops the current statement is nested in.
+    loops: usize,
+}
+
+/// A piece of `printf` output: text as it is, or a value of a type.
+enum Part {
+    Text(String),
+    Value(String, Type),
+}
+
+impl<'a> C<'a> {
+    fn new(program: &'a Program) -> Self {
+        let typing = Typing::new(program);
+        let signatures = signatures(program, &typing);
+        Self {
+            typing,
+            writer: Writer::new(INDENT),
+            functions: program.functions.iter().map(|f| f.name.as_str()).collect(),
+            signatures,
+            diagnostics: Vec::new(),
+            includes: ["stdbool.h", "stdio.h", "stdlib.h", "string.h"]
+                .into_iter()
+                .collect(),
+            typedefs: Vec::new(),
+            types: HashSet::new(),
+            records: Vec::new(),
+            helpers: Vec::new(),
+            helper_names: HashSet::new(),
+            random: false,
+            env: HashMap::new(),
+            returns: None,
+            in_main: false,
+            modified: HashSet::new(),
+            reassigned: HashSet::new(),
+            declared: HashSet::new(),
+            loops: 0,
+        }
+    }
+
+    /// The prototype and definition of a function whose types are known.
+    fn function(&mut self, function: &Function) -> Option<(String, String)> {
+        let (params, returns) = self.signatures.get(function.name.as_str()).cloned()?;
+        self.begin(&function.name, &function.params, &function.body, returns);
+        let params: Result<Vec<String>, String> = function
+            .params
+            .iter()
+            .zip(&params)
+            .map(|(param, param_type)| Ok(declaration(&self.c_type(param_type)?, &ident(param))))
+            .collect();
+        let return_type = match self.returns.clone() {
+            Some(returns) => self.c_type(&returns),
+            None => Ok("void".to_string()),
+        };
+        let (params, return_type) = match (params, return_type) {
+            (Ok(params), Ok(return_type)) => (params, return_type),
+            (Err(reason), _) | (_, Err(reason)) => {
+                self.fail(function.line, &reason);
+                return Some((String::new(), String::new()));
+            }
+        };
+        let params = if params.is_empty() {
+            "void".to_string()
+        } else {
+            params.join(", ")
+        };
+        let header = declaration(
+            &return_type,
+            &format!("{}({params})", ident(&function.name)),
+        );
+        self.writer.line(format!("{header} {{"));
+        self.writer.indent();
+        // Arrays are values in pseudocode, so a function that changes an
+        // element must not change the caller's copy.
+        for param in &function.params {
+            if self.modified.contains(param)
+                && let Some(Type::Array(element)) = self.env.get(param).cloned()
+            {
+                match self.array_helper(&element, "copy") {
+                    Ok(copy) => {
+                        let name = ident(param);
+                        self.writer.line(format!("{name} = {copy}({name});"));
+                    }
+                    Err(reason) => self.fail(function.line, &reason),
+                }
+            }
+        }
+        self.hoist(&function.params, &function.body, function.line);
+        self.statements(&function.body);
+        self.writer.dedent();
+        self.writer.line("}");
+        Some((format!("{header};\n"), self.writer.take()))
+    }
+
+    fn main(&mut self, start: &Start) -> String {
+        let body = &start.body;
+        self.begin("start", &[], body, None);
+        self.in_main = true;
+        self.writer.indent();
+        self.hoist(&[], body, start.line);
+        self.statements(body);
+        if !always_returns(body) {
+            self.writer.line("return 0;");
+        }
+        self.writer.dedent();
+        self.in_main = false;
+        let body = self.writer.take();
+        let seed = if self.random {
+            self.includes.insert("time.h");
+            format!("{INDENT}srand((unsigned)time(NULL));\n")
+        } else {
+            String::new()
+        };
+        format!("int main(void) {{\n{seed}{body}}}\n")
+    }
+
+    /// A `main` asserting each test, or `None` without tests.
+    fn tests(&mut self, tests: &[TestCase]) -> Option<String> {
+        if tests.is_empty() {
+            return None;
+        }
+        self.includes.insert("assert.h");
+        self.env = HashMap::new();
+        self.writer.line("int main(void) {");
+        self.writer.indent();
+        for test in tests {
+            let actual = self.ty(&test.call);
+            let equal = self.operand(&test.call, OR).and_then(|call| {
+                let (expected, _) = self.value(&test.expected, &actual)?;
+                self.equals(&actual, &call, &expected)
+            });
+            match equal {
+                Ok(equal) => self.writer.line(format!("assert({equal});")),
+                Err(reason) => self.fail(test.line, &reason),
+            }
+        }
+        self.writer.line("printf(\"all tests passed\\n\");");
+        self.writer.line("return 0;");
+        self.writer.dedent();
+        self.writer.line("}");
+        Some(self.writer.take())
+    }
+
+    fn begin(&mut self, routine: &str, params: &[String], body: &[Node], returns: Option<Type>) {
+        self.env = self
+            .typing
+            .variables
+            .get(routine)
+            .cloned()
+            .unwrap_or_default();
+        self.returns = returns;
+        self.loops = 0;
+        self.modified = modified(body);
+        self.reassigned = Scan::new(body).reassigned(params);
+        self.declared = params.iter().cloned().collect();
+    }
+
+    /// Declares up front each variable used outside the block it is first
+    /// assigned in, since a declaration only reaches the end of its block.
+    fn hoist(&mut self, params: &[String], body: &[Node], line: usize) {
+        let mut hoisted: Vec<String> = Scan::new(body).hoisted(params).into_iter().collect();
+        hoisted.sort();
+        for var in hoisted {
+            let var_type = self.env.get(&var).cloned().unwrap_or(Type::Unknown);
+            match self.c_type(&var_type) {
+                Ok(c_type) => {
+                    let zero = match var_type {
+                        Type::Int => "0",
+                        Type::Real => "0.0",
+                        Type::Bool => "false",
+                        Type::String => "\"\"",
+                        _ => "{0}",
+                    };
+                    self.writer
+                        .line(format!("{} = {zero};", declaration(&c_type, &ident(&var))));
+                }
+                Err(_) => self.fail(line, &format!("the type of '{var}' is not known")),
+            }
+            self.declared.insert(var);
+        }
+    }
+
+    fn fail(&mut self, line: usize, reason: &str) {
+        self.diagnostics
+            .push(format!("line {}: {reason}", line + 1));
+    }
+
+    fn ty(&mut self, expression: &Expression) -> Type {
+        self.typing.expression(expression, &self.env)
+    }
+
+    fn c_type(&mut self, value_type: &Type) -> Result<String, String> {
+        match value_type {
+            Type::Int => Ok("long long".to_string()),
+            Type::Real => Ok("double".to_string()),
+            Type::String => Ok("char *".to_string()),
+            Type::Bool => Ok("bool".to_string()),
+            Type::Array(element) => self.array_type(element),
+            Type::Record(fields) => self.record_type(fields),
+            Type::Unknown => Err("a value of unknown type has no C type".to_string()),
+        }
+    }
+
+    /// The struct for arrays of `element`, defined on first use.
+    fn array_type(&mut self, element: &Type) -> Result<String, String> {
+        let item = self.c_type(element)?;
+        let name = format!("{}Array", type_name(element, &self.records));
+        if self.types.insert(name.clone()) {
+            self.typedefs.push(format!(
+                "typedef struct {{\n{INDENT}{};\n{INDENT}long long length;\n}} {name};\n",
+                declaration(&pointer(&item), "items")
+            ));
+        }
+        Ok(name)
+    }
+
+    /// The struct for a record type, defined on first use.
+    fn record_type(&mut self, fields: &[(String, Type)]) -> Result<String, String> {
+        if let Some(index) = self.records.iter().position(|known| known == fields) {
+            return Ok(format!("Record{}", index + 1));
+        }
+        self.records.push(fields.to_vec());
+        let name = format!("Record{}", self.records.len());
+        let mut text = "typedef struct {\n".to_string();
+        for (field, field_type) in fields {
+            let c_type = self.c_type(field_type)?;
+            text.push_str(&format!(
+                "{INDENT}{};\n",
+                declaration(&c_type, &ident(field))
+            ));
+        }
+        text.push_str(&format!("}} {name};\n"));
+        self.typedefs.push(text);
+        self.types.insert(name.clone());
+        Ok(name)
+    }
+
+    /// Adds a helper function the first time it is used.
+    fn helper(&mut self, name: &str, definition: &str) -> String {
+        if self.helper_names.insert(name.to_string()) {
+            self.helpers.push(definition.to_string());
+        }
+        name.to_string()
+    }
+
+    /// A helper that writes reals the way the interpreter does, defined
+    /// after the `format_real` it calls.
+    fn real_helper(&mut self, name: &str, definition: &str) -> String {
+        self.helper("format_real", FORMAT_REAL);
+        self.helper(name, definition)
+    }
+
+    /// The name of `operation` on arrays of `element`, defined on first use
+    /// after the helpers it needs.
+    fn array_helper(&mut self, element: &Type, operation: &str) -> Result<String, String> {
+        let array = self.array_type(element)?;
+        let name = format!("{array}_{operation}");
+        if self.helper_names.contains(&name) {
+            return Ok(name);
+        }
+        let item = self.c_type(element)?;
+        let (template, code) = match operation {
+            "of" => (ARRAY_OF, String::new()),
+            "copy" => {
+                self.array_helper(element, "of")?;
+                match element {
+                    Type::Array(inner) => (
+                        ARRAY_COPY_NESTED,
+                        format!(
+                            "copy.items[i] = {}(copy.items[i]);",
+                            self.array_helper(inner, "copy")?
+                        ),
+                    ),
+                    _ => (ARRAY_COPY, String::new()),
+                }
+            }
+            "append" => (ARRAY_APPEND, String::new()),
+            "concat" => (ARRAY_CONCAT, String::new()),
+            "equals" => (
+                ARRAY_EQUALS,
+                self.differs(element, "left.items[i]", "right.items[i]")?,
+            ),
+            "contains" => (
+                ARRAY_CONTAINS,
+                self.equals(element, "array.items[i]", "item")?,
+            ),
+            _ => {
+                let parts = vec![Part::Value("array.items[i]".to_string(), element.clone())];
+                (ARRAY_PRINT, self.printf(parts, true)?.join(" "))
+            }
+        };
+        let definition = template
+            .replace("$ITEM_PARAM", &declaration(&item, "item"))
+            .replace("$ITEMS_PARAM", &declaration(&pointer(&item), "items"))
+            .replace("$ITEM", &item)
+            .replace("$ARRAY", &array)
+            .replace("$EQUALS", &code)
+            .replace("$DIFFERS", &code)
+            .replace("$COPY", &code)
+            .replace("$PRINT", &code);
+        Ok(self.helper(&name, &definition))
+    }
+
+    /// The name of `operation`, `equals` or `print`, on a record type.
+    fn record_helper(
+        &mut self,
+        fields: &[(String, Type)],
+        operation: &str,
+    ) -> Result<String, String> {
+        let record = self.record_type(fields)?;
+        let name = format!("{record}_{operation}");
+        if self.helper_names.contains(&name) {
+            return Ok(name);
+        }
+        let definition = if operation == "equals" {
+            let mut checks = Vec::new();
+            for (field, field_type) in fields {
+                let field = ident(field);
+                checks.push(self.equals(
+                    field_type,
+                    &format!("left.{field}"),
+                    &format!("right.{field}"),
+                )?);
+            }
+            let checks = if checks.is_empty() {
+                "true".to_string()
+            } else {
+                checks.join(" && ")
+            };
+            format!("bool {name}({record} left, {record} right) {{\n{INDENT}return {checks};\n}}\n")
+        } else {
+            let mut parts = vec![Part::Text("{".to_string())];
+            for (index, (field, field_type)) in fields.iter().enumerate() {
+                let separator = if index > 0 { ", " } else { "" };
+                parts.push(Part::Text(format!("{separator}{field}: ")));
+                parts.push(Part::Value(
+                    format!("record.{}", ident(field)),
+                    field_type.clone(),
+                ));
+            }
+            parts.push(Part::Text("}".to_string()));
+            let mut text = format!("void {name}({record} record) {{\n");
+            for line in self.printf(parts, true)? {
+                text.push_str(&format!("{INDENT}{line}\n"));
+            }
+            text.push_str("}\n");
+            text
+        };
+        Ok(self.helper(&name, &definition))
+    }
+
+    /// Statements printing `parts`, as few `printf` calls as the types
+    /// allow. Strings are `quoted` inside arrays and records.
+    fn printf(&mut self, parts: Vec<Part>, quoted: bool) -> Result<Vec<String>, String> {
+        let mut lines = Vec::new();
+        let mut format = String::new();
+        let mut args = String::new();
+        let flush = |lines: &mut Vec<String>, format: &mut String, args: &mut String| {
+            if !format.is_empty() {
+                lines.push(format!("printf(\"{format}\"{args});"));
+            }
+            format.clear();
+            args.clear();
+        };
+        for part in parts {
+            let (value, value_type) = match part {
+                Part::Text(text) => {
+                    format.push_str(&escape(&text).replace('%', "%%"));
+                    continue;
+                }
+                Part::Value(value, value_type) => (value, value_type),
+            };
+            match value_type {
+                Type::Int => format.push_str("%lld"),
+                Type::Real => {
+                    flush(&mut lines, &mut format, &mut args);
+                    let print = self.real_helper("print_real", PRINT_REAL);
+                    lines.push(format!("{print}({value});"));
+                    continue;
+                }
+                Type::String if quoted => format.push_str("\\\"%s\\\""),
+                Type::String => format.push_str("%s"),
+                Type::Bool => {
+                    format.push_str("%s");
+                    args.push_str(&format!(", {value} ? \"true\" : \"false\""));
+                    continue;
+                }
+                Type::Array(element) => {
+                    flush(&mut lines, &mut format, &mut args);
+                    let print = self.array_helper(&element, "print")?;
+                    lines.push(format!("{print}({value});"));
+                    continue;
+                }
+                Type::Record(fields) => {
+                    flush(&mut lines, &mut format, &mut args);
+                    let print = self.record_helper(&fields, "print")?;
+                    lines.push(format!("{print}({value});"));
+                    continue;
+                }
+                Type::Unknown => return Err("cannot print a value of unknown type".to_string()),
+            }
+            args.push_str(&format!(", {value}"));
+        }
+        flush(&mut lines, &mut format, &mut args);
+        Ok(lines)
+    }
+
+    /// A test that `left` and `right`, both of `value_type`, are equal.
+    fn equals(&mut self, value_type: &Type, left: &str, right: &str) -> Result<String, String> {
+        Ok(match value_type {
+            Type::String => format!("strcmp({left}, {right}) == 0"),
+            Type::Array(element) => {
+                format!("{}({left}, {right})", self.array_helper(element, "equals")?)
+            }
+            Type::Record(fields) => {
+                format!("{}({left}, {right})", self.record_helper(fields, "equals")?)
+            }
+            _ => format!("{left} == {right}"),
+        })
+    }
+
+    /// A test that `left` and `right`, both of `value_type`, differ.
+    fn differs(&mut self, value_type: &Type, left: &str, right: &str) -> Result<String, String> {
+        Ok(match value_type {
+            Type::String => format!("strcmp({left}, {right}) != 0"),
+            Type::Array(_) | Type::Record(_) => {
+                format!("!{}", self.equals(value_type, left, right)?)
+            }
+            _ => format!("{left} != {right}"),
+        })
+    }
+
+    fn operand(&mut self, expression: &Expression, precedence: u8) -> Result<String, String> {
+        let (text, binds) = self.expression(expression)?;
+        Ok(parenthesize(text, binds, precedence))
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Translation {
+        let found = self.ty(expression);
+        if found.is_partial() {
+            return Err(format!("the type of '{expression}' is not known"));
+        }
+        match expression {
+            Expression::Variable(name) => Ok((ident(name), ATOM)),
+            Expression::Literal(text) => Ok(self.literal(text)),
+            Expression::BinaryOp {
+                left,
+                operator,
+                right,
+            } => self.arithmetic(&found, left, operator, right),
+            Expression::FunctionCall { name, args } => self.function_call(name, args),
+            Expression::Not(inner) => Ok((format!("!{}", self.operand(inner, UNARY)?), UNARY)),
+            Expression::Array(_) | Expression::Record(_) => self.value(expression, &found),
+            Expression::Index { target, index } => {
+                let index = self.operand(index, OR)?;
+                match self.ty(target) {
+                    Type::String => {
+                        let target = self.operand(target, OR)?;
+                        let at = self.helper("string_at", STRING_AT);
+                        Ok((format!("{at}({target}, {index})"), ATOM))
+                    }
+                    _ => Ok((
+                        format!("{}.items[{index}]", self.operand(target, ATOM)?),
+                        ATOM,
+                    )),
+                }
+            }
+            Expression::Field { target, name } => Ok((
+                format!("{}.{}", self.operand(target, ATOM)?, ident(name)),
+                ATOM,
+            )),
+        }
+    }
+
+    /// `expression` where a value of `expected` goes, which decides the
+    /// element type of an array literal.
+    fn value(&mut self, expression: &Expression, expected: &Type) -> Translation {
+        match (expression, expected) {
+            (Expression::Array(items), Type::Array(element)) => {
+                let of = self.array_helper(element, "of")?;
+                if items.is_empty() {
+                    return Ok((format!("{of}(0, NULL)"), ATOM));
+                }
+                let item = self.c_type(element)?;
+                let mut values = Vec::new();
+                for item in items {
+                    values.push(self.value(item, element)?.0);
+                }
+                Ok((
+                    format!("{of}({}, ({item}[]){{{}}})", items.len(), values.join(", ")),
+                    ATOM,
+                ))
+            }
+            (Expression::Record(values), Type::Record(fields)) => {
+                let record = self.record_type(fields)?;
+                let mut initializers = Vec::new();
+                for (name, value) in values {
+                    let field_type = fields
+                        .iter()
+                        .find(|(field, _)| field == name)
+                        .map_or(Type::Unknown, |(_, field_type)| field_type.clone());
+                    let value = self.value(value, &field_type)?.0;
+                    initializers.push(format!(".{} = {value}", ident(name)));
+                }
+                Ok((format!("({record}){{{}}}", initializers.join(", ")), ATOM))
+            }
+            _ => {
+                let found = self.ty(expression);
+                if found.is_partial() {
+                    return Err(format!("the type of '{expression}' is not known"));
+                }
+                if found != *expected && !(found.is_numeric() && expected.is_numeric()) {
+                    return Err(format!(
+                        "'{expression}' is {found} where {expected} is expected"
+                    ));
+                }
+                self.expression(expression)
+            }
+        }
+    }
+
+    /// A pseudocode literal in C syntax.
+    fn literal(&mut self, text: &str) -> (String, u8) {
+        if text.starts_with('"') {
+            return (text.replace('\\', "\\\\"), ATOM);
+        }
+        if text == "true" || text == "false" {
+            return (text.to_string(), ATOM);
+        }
+        let binds = if text.starts_with('-') { UNARY } else { ATOM };
+        if let Ok(value) = text.parse::<i64>() {
+            // Without a suffix a literal past the range of `int` is only
+            // guaranteed to be a `long`.
+            if i32::try_from(value).is_ok() {
+                return (value.to_string(), binds);
+            }
+            return (format!("{value}LL"), binds);
+        }
+        match text.parse::<f64>() {
+            Ok(value) if value.is_nan() => {
+                self.includes.insert("math.h");
+                ("NAN".to_string(), ATOM)
+            }
+            Ok(value) if value.is_infinite() => {
+                self.includes.insert("math.h");
+                let sign = if value < 0.0 { "-" } else { "" };
+                (format!("{sign}INFINITY"), binds)
+            }
+            Ok(value) => (format!("{value:?}"), binds),
+            Err(_) => (text.to_string(), ATOM),
+        }
+    }
+
+    fn arithmetic(
+        &mut self,
+        found: &Type,
+        left: &Expression,
+        operator: &ArithmeticOperator,
+        right: &Expression,
+    ) -> Translation {
+        match (operator, found) {
+            (ArithmeticOperator::Addition, Type::String) => {
+                let concat = self.helper("string_concat", STRING_CONCAT);
+                let left = self.operand(left, OR)?;
+                let right = self.operand(right, OR)?;
+                return Ok((format!("{concat}({left}, {right})"), ATOM));
+            }
+            (ArithmeticOperator::Addition, Type::Array(element)) => {
+                let concat = self.array_helper(element, "concat")?;
+                let left = self.value(left, found)?.0;
+                let right = self.value(right, found)?.0;
+                return Ok((format!("{concat}({left}, {right})"), ATOM));
+            }
+            (ArithmeticOperator::Modulo, Type::Real) => {
+                self.includes.insert("math.h");
+                let left = self.operand(left, OR)?;
+                let right = self.operand(right, OR)?;
+                return Ok((format!("fmod({left}, {right})"), ATOM));
+            }
+            (_, Type::Int | Type::Real) => {}
+            _ => return Err(format!("arithmetic on {found} has no C translation")),
+        }
+        // `/` and `%` on two integers drop the fraction toward zero in C,
+        // as in pseudocode.
+        let (symbol, precedence) = match operator {
+            ArithmeticOperator::Addition => ("+", SUM),
+            ArithmeticOperator::Subtraction => ("-", SUM),
+            ArithmeticOperator::Multiplication => ("*", PRODUCT),
+            ArithmeticOperator::Division => ("/", PRODUCT),
+            ArithmeticOperator::Modulo => ("%", PRODUCT),
+        };
+        let left = self.operand(left, precedence)?;
+        let right = self.operand(right, precedence + 1)?;
+        Ok((format!("{left} {symbol} {right}"), precedence))
+    }
+
+    /// A call to one of the program's functions, or the C spelling of a
+    /// builtin.
+    fn function_call(&mut self, name: &str, args: &[Expression]) -> Translation {
+        if self.functions.contains(name) {
+            let Some((params, _)) = self.signatures.get(name).cloned() else {
+                return Err(format!("the types of '{name}' are not known"));
+            };
+            let mut values = Vec::new();
+            for (arg, param) in args.iter().zip(&params) {
+                values.push(self.value(arg, param)?.0);
+            }
+            return Ok((format!("{}({})", ident(name), values.join(", ")), ATOM));
+        }
+        let types: Vec<Type> = args.iter().map(|arg| self.ty(arg)).collect();
+        let call =
+            |function: &str, args: Vec<String>| (format!("{function}({})", args.join(", ")), ATOM);
+        let mut values = Vec::new();
+        for arg in args {
+            values.push(self.operand(arg, OR)?);
+        }
+        Ok(match (name, types.as_slice()) {
+            ("length", [Type::String]) => (format!("(long long)strlen({})", values[0]), UNARY),
+            ("length", [Type::Array(_)]) => {
+                (format!("{}.length", self.operand(&args[0], ATOM)?), ATOM)
+            }
+            ("abs", [Type::Int]) => call("llabs", values),
+            ("min" | "max", [Type::Int, Type::Int]) => {
+                let helper = if name == "min" {
+                    self.helper("int_min", INT_MIN)
+                } else {
+                    self.helper("int_max", INT_MAX)
+                };
+                call(&helper, values)
+            }
+            ("floor" | "ceil" | "round", [Type::Int]) => self.expression(&args[0])?,
+            ("abs" | "min" | "max" | "sqrt" | "pow" | "floor" | "ceil" | "round", _) => {
+                self.includes.insert("math.h");
+                match name {
+                    "abs" => call("fabs", values),
+                    "min" => call("fmin", values),
+                    "max" => call("fmax", values),
+                    "round" => call("llround", values),
+                    "floor" | "ceil" => (format!("(long long){}", call(name, values).0), UNARY),
+                    _ => call(name, values),
+                }
+            }
+            ("random", [_]) => {
+                self.random = true;
+                (
+                    format!("rand() % {}", self.operand(&args[0], PRODUCT + 1)?),
+                    PRODUCT,
+                )
+            }
+            ("range", _) => {
+                self.array_type(&Type::Int)?;
+                let range = self.helper("range", RANGE);
+                call(&range, values)
+            }
+            ("append", [Type::Array(element), _]) => {
+                let append = self.array_helper(element, "append")?;
+                let item = self.value(&args[1], element)?.0;
+                call(&append, vec![values[0].clone(), item])
+            }
+            ("contains", [Type::String, Type::String]) => (
+                format!("strstr({}, {}) != NULL", values[0], values[1]),
+                EQUALITY,
+            ),
+            ("contains", [Type::Array(element), _]) => {
+                let contains = self.array_helper(element, "contains")?;
+                let item = self.value(&args[1], element)?.0;
+                call(&contains, vec![values[0].clone(), item])
+            }
+            ("substring", _) => call(&self.helper("substring", SUBSTRING), values),
+            ("to_upper" | "to_lower", _) => {
+                self.includes.insert("ctype.h");
+                let helper = if name == "to_upper" {
+                    self.helper("string_upper", STRING_UPPER)
+                } else {
+                    self.helper("string_lower", STRING_LOWER)
+                };
+                call(&helper, values)
+            }
+            ("to_string", [Type::String]) => self.expression(&args[0])?,
+            ("to_string", [Type::Int]) => {
+                call(&self.helper("int_to_string", INT_TO_STRING), values)
+            }
+            ("to_string", [Type::Real]) => {
+                call(&self.real_helper("real_to_string", REAL_TO_STRING), values)
+            }
+            ("to_string", [Type::Bool]) => (
+                format!("{} ? \"true\" : \"false\"", self.operand(&args[0], OR)?),
+                CONDITIONAL,
+            ),
+            ("to_int", [Type::Int]) | ("to_real", [Type::Real]) => self.expression(&args[0])?,
+            ("to_int", [Type::Real]) => (
+                format!("(long long){}", self.operand(&args[0], UNARY)?),
+                UNARY,
+            ),
+            ("to_real", [Type::Int]) => {
+                (format!("(double){}", self.operand(&args[0], UNARY)?), UNARY)
+            }
+            ("to_int", [Type::String]) => call("atoll", values),
+            ("to_real", [Type::String]) => call("atof", values),
+            ("print", _) => return Err("'print' gives no value".to_string()),
+            _ => {
+                let types: Vec<String> = types.iter().map(Type::to_string).collect();
+                return Err(format!(
+                    "'{name}' of {} has no C translation",
+                    types.join(" and ")
+                ));
+            }
+        })
+    }
+
+    fn condition(&mut self, condition: &Condition) -> Translation {
+        match condition {
+            Condition::ComparisonOperator {
+                left,
+                operator,
+                right,
+            } => {
+                let left_type = self.ty(left);
+                let right_type = self.ty(right);
+                let ordered =
+                    !matches!(**operator, ComparisonOperator::Eq | ComparisonOperator::Neq);
+                let symbol = match **operator {
+                    ComparisonOperator::Eq => "==",
+                    ComparisonOperator::Neq => "!=",
+                    ComparisonOperator::Gt => ">",
+                    ComparisonOperator::Lt => "<",
+                    ComparisonOperator::Gte => ">=",
+                    ComparisonOperator::Lte => "<=",
+                };
+                let precedence = if ordered { RELATIONAL } else { EQUALITY };
+                match (&left_type, &right_type) {
+                    (Type::String, Type::String) => {
+                        let left = self.operand(left, OR)?;
+                        let right = self.operand(right, OR)?;
+                        Ok((format!("strcmp({left}, {right}) {symbol} 0"), precedence))
+                    }
+                    (Type::Array(_) | Type::Record(_), _) if !ordered => {
+                        let left = self.value(left, &left_type)?.0;
+                        let right = self.value(right, &left_type)?.0;
+                        if matches!(**operator, ComparisonOperator::Eq) {
+                            Ok((self.equals(&left_type, &left, &right)?, ATOM))
+                        } else {
+                            Ok((self.differs(&left_type, &left, &right)?, UNARY))
+                        }
+                    }
+                    (Type::Array(_) | Type::Record(_), _)
+                    | (_, Type::Array(_) | Type::Record(_)) => Err(format!(
+                        "cannot compare {left_type} and {right_type} with '{symbol}'"
+                    )),
+                    _ => {
+                        let left = self.operand(left, precedence)?;
+                        let right = self.operand(right, precedence + 1)?;
+                        Ok((format!("{left} {symbol} {right}"), precedence))
+                    }
+                }
+            }
+            Condition::LogicalOperator {
+                left,
+                operator,
+                right,
+            } => {
+                let (symbol, precedence) = match **operator {
+                    LogicalOperator::And => ("&&", AND),
+                    LogicalOperator::Or => ("||", OR),
+                };
+                // Compilers warn about `&&` inside `||` without parentheses.
+                let nested = |binds: u8| {
+                    if precedence == OR && binds == AND {
+                        CONDITIONAL
+                    } else {
+                        binds
+                    }
+                };
+                let (left, binds) = self.condition(left)?;
+                let left = parenthesize(left, nested(binds), precedence);
+                let (right, binds) = self.condition(right)?;
+                let right = parenthesize(right, nested(binds), precedence + 1);
+                Ok((format!("{left} {symbol} {right}"), precedence))
+            }
+            Condition::Value(value) => self.expression(value),
+        }
+    }
+
+    /// An element or field that can be assigned to.
+    fn place(&mut self, target: &Expression) -> Result<String, String> {
+        match target {
+            Expression::Variable(name) => Ok(ident(name)),
+            Expression::Index { target, index } => match self.ty(target) {
+                Type::Array(_) => Ok(format!(
+                    "{}.items[{}]",
+                    self.place(target)?,
+                    self.operand(index, OR)?
+                )),
+                found => Err(format!("cannot assign to an element of {found}")),
+            },
+            Expression::Field { target, name } => {
+                Ok(format!("{}.{}", self.place(target)?, ident(name)))
+            }
+            _ => Err(format!("cannot assign to '{target}'")),
+        }
+    }
+
+    fn assign(&mut self, assignment: &Assignment) -> Result<String, String> {
+        let expected = match assignment.var() {
+            Some(var) => self.env.get(var).cloned().unwrap_or(Type::Unknown),
+            None => self.ty(&assignment.target),
+        };
+        if expected.is_partial() {
+            return Err(format!("the type of '{}' is not known", assignment.target));
+        }
+        let target = self.place(&assignment.target)?;
+        let mut value = self.value(&assignment.value, &expected)?.0;
+        // Assigning an array to a variable shares its elements in C, which
+        // shows once an element is changed through either name.
+        let shared = match (&assignment.target, &assignment.value) {
+            (Expression::Variable(target), Expression::Variable(value)) => {
+                self.modified.contains(target) || self.modified.contains(value)
+            }
+            (Expression::Variable(target), Expression::Index { .. } | Expression::Field { .. }) => {
+                self.modified.contains(target)
+            }
+            _ => false,
+        };
+        if shared && let Type::Array(element) = &expected {
+            value = format!("{}({value})", self.array_helper(element, "copy")?);
+        }
+        match assignment.var() {
+            Some(var) if self.declared.insert(var.to_string()) => {
+                let c_type = self.c_type(&expected)?;
+                Ok(format!("{} = {value};", declaration(&c_type, &target)))
+            }
+            _ => Ok(format!("{target} = {value};")),
+        }
+    }
+
+    fn print(&mut self, args: &[Expression]) -> Result<Vec<String>, String> {
+        let mut parts = Vec::new();
+        for (index, arg) in args.iter().enumerate() {
+            if index > 0 {
+                parts.push(Part::Text(" ".to_string()));
+            }
+            match arg {
+                Expression::Literal(text) if text.starts_with('"') => {
+                    parts.push(Part::Text(text.trim_matches('"').to_string()))
+                }
+                _ => {
+                    let value_type = self.ty(arg);
+                    if value_type.is_partial() {
+                        return Err(format!("the type of '{arg}' is not known"));
+                    }
+                    // Integer constants are only `int`s, which `%lld` can't
+                    // print.
+                    let value = if value_type == Type::Int && is_int(arg) {
+                        format!("(long long){}", self.operand(arg, UNARY)?)
+                    } else {
+                        self.operand(arg, OR)?
+                    };
+                    parts.push(Part::Value(value, value_type));
+                }
+            }
+        }
+        parts.push(Part::Text("\n".to_string()));
+        self.printf(parts, false)
+    }
+
+    /// The header of a `for` loop and the statement declaring its item,
+    /// after any statement the loop needs before it.
+    fn for_loop(
+        &mut self,
+        item: &str,
+        list: &Expression,
+        statement: &Loop,
+    ) -> Result<(Vec<String>, String, Option<String>), String> {
+        let name = ident(item);
+        let reassigned = self.reassigned.contains(item);
+        if let Expression::FunctionCall { name: range, args } = list
+            && range == "range"
+            && !self.functions.contains(range.as_str())
+            && let [from, to] = args.as_slice()
+        {
+            let from = self.operand(from, OR)?;
+            let to = self.operand(to, OR)?;
+            // A counter of its own keeps the count when the body assigns
+            // the item.
+            let counter = if reassigned {
+                format!("{name}_index")
+            } else {
+                name.clone()
+            };
+            let header = format!(
+                "for (long long {counter} = {from}, {name}_end = {to}; {counter} < {name}_end; {counter}++) {{"
+            );
+            let item = reassigned.then(|| format!("long long {name} = {counter};"));
+            return Ok((Vec::new(), header, item));
+        }
+
+        let list_type = self.ty(list);
+        let mut before = Vec::new();
+        // The loop goes over the value the list had when it started, so a
+        // list the body changes is copied first.
+        let assigned = match list {
+            Expression::Variable(var) => {
+                let mut assigned = false;
+                walk(&statement.body, Scope::default(), &mut |node, _| {
+                    if let Node::Assignment(val) = node
+                        && root(&val.target) == Some(var.as_str())
+                    {
+                        assigned = true;
+                    }
+                });
+                assigned
+            }
+            _ => true,
+        };
+        let source = if assigned {
+            let source = format!("items_{}", statement.line + 1);
+            let c_type = self.c_type(&list_type)?;
+            let mut value = self.value(list, &list_type)?.0;
+            if let (Expression::Variable(_), Type::Array(element)) = (list, &list_type) {
+                value = format!("{}({value})", self.array_helper(element, "copy")?);
+            }
+            before.push(format!("{} = {value};", declaration(&c_type, &source)));
+            source
+        } else {
+            self.operand(list, ATOM)?
+        };
+        let index = format!("{name}_index");
+        match &list_type {
+            Type::Array(element) => {
+                let c_type = self.c_type(element)?;
+                Ok((
+                    before,
+                    format!("for (long long {index} = 0; {index} < {source}.length; {index}++) {{"),
+                    Some(format!(
+                        "{} = {source}.items[{index}];",
+                        declaration(&c_type, &name)
+                    )),
+                ))
+            }
+            Type::String => {
+                let at = self.helper("string_at", STRING_AT);
+                Ok((
+                    before,
+                    format!(
+                        "for (long long {index} = 0; {source}[{index}] != '\\0'; {index}++) {{"
+                    ),
+                    Some(format!("char *{name} = {at}({source}, {index});")),
+                ))
+            }
+            found => Err(format!("cannot loop over {found}")),
+        }
+    }
+
+    fn leave(&mut self, keyword: &str, line: usize) {
+        if self.loops > 0 {
+            self.writer.line(format!("{keyword};"));
+        } else if self.in_main {
+            self.writer.line("return 0;");
+        } else if self.returns.is_none() {
+            self.writer.line("return;");
+        } else {
+            self.fail(
+                line,
+                &format!("'{keyword}' outside a loop ends the function without a value"),
+            );
+        }
+    }
+}
+
+impl Emitter for C<'_> {
+    fn writer(&mut self) -> &mut Writer {
+        &mut self.writer
+    }
+
+    fn assignment(&mut self, assignment: &Assignment) {
+        match self.assign(assignment) {
+            Ok(line) => self.writer.line(line),
+            Err(reason) => self.fail(assignment.line, &reason),
+        }
+    }
+
+    fn call(&mut self, call: &FunctionCall) {
+        let lines = if call.name == "print" && !self.functions.contains("print") {
+            self.print(&call.args)
+        } else {
+            self.function_call(&call.name, &call.args)
+                .map(|(text, _)| vec![format!("{text};")])
+        };
+        match lines {
+            Ok(lines) => {
+                for line in lines {
+                    self.writer.line(line);
+                }
+            }
+            Err(reason) => self.fail(call.line, &reason),
+        }
+    }
+
+    fn return_value(&mut self, value: &Return) {
+        if self.in_main {
+            self.writer.line("return 0;");
+            return;
+        }
+        let Some(returns) = self.returns.clone() else {
+            self.writer.line("return;");
+            return;
+        };
+        match self.value(&value.value, &returns) {
+            Ok((text, _)) => self.writer.line(format!("return {text};")),
+            Err(reason) => self.fail(value.line, &reason),
+        }
+    }
+
+    fn if_statement(&mut self, statement: &If) {
+        let conditions = std::iter::once(&statement.condition)
+            .chain(statement.else_ifs.iter().map(|branch| &branch.condition))
+            .map(|condition| Ok(self.condition(condition)?.0))
+            .collect::<Result<Vec<_>, String>>();
+        let conditions = match conditions {
+            Ok(conditions) => conditions,
+            Err(reason) => return self.fail(statement.line, &reason),
+        };
+        self.writer.line(format!("if ({}) {{", conditions[0]));
+        self.block(&statement.body);
+        for (branch, condition) in statement.else_ifs.iter().zip(&conditions[1..]) {
+            self.writer.line(format!("}} else if ({condition}) {{"));
+            self.block(&branch.body);
+        }
+        if let Some(branch) = &statement.else_branch {
+            self.writer.line("} else {");
+            self.block(&branch.body);
+        }
+        self.writer.line("}");
+    }
+
+    fn loop_statement(&mut self, statement: &Loop) {
+        let (before, header, item) = match &statement.loop_type {
+            LoopType::While(condition) => match self.condition(condition) {
+                Ok((condition, _)) => (Vec::new(), format!("while ({condition}) {{"), None),
+                Err(reason) => return self.fail(statement.line, &reason),
+            },
+            LoopType::For { item, list } => match self.for_loop(item, list, statement) {
+                Ok(parts) => parts,
+                Err(reason) => return self.fail(statement.line, &reason),
+            },
+        };
+        for line in before {
+            self.writer.line(line);
+        }
+        self.writer.line(header);
+        // The loop declares its item, for the body only.
+        let declared = match &statement.loop_type {
+            LoopType::For { item, .. } => {
+                Some(item).filter(|item| self.declared.insert(item.to_string()))
+            }
+            LoopType::While(_) => None,
+        };
+        self.writer.indent();
+        if let Some(item) = item {
+            self.writer.line(item);
+        }
+        self.loops += 1;
+        self.statements(&statement.body);
+        self.loops -= 1;
+        self.writer.dedent();
+        if let Some(item) = declared {
+            self.declared.remove(item);
+        }
+        self.writer.line("}");
+    }
+
+    fn break_statement(&mut self, line: usize) {
+        self.leave("break", line);
+    }
+
+    fn continue_statement(&mut self, line: usize) {
+        self.leave("continue", line);
+    }
+
+    fn comment(&mut self, comment: &Comment) {
+        self.writer.line(format!("// {}", comment.value).trim_end());
+    }
+}
+
+/// Whether `expression` has the C type `int` rather than `long long`, since
+/// it is made of integer literals and `random` alone.
+fn is_int(expression: &Expression) -> bool {
+    match expression {
+        Expression::Literal(text) => text.parse::<i32>().is_ok(),
+        Expression::BinaryOp { left, right, .. } => is_int(left) && is_int(right),
+        Expression::FunctionCall { name, args } if name == "random" => args.iter().all(is_int),
+        _ => false,
+    }
+}
+
+/// `name` declared as `c_type`, with the `*` of a pointer against the name.
+fn declaration(c_type: &str, name: &str) -> String {
+    if c_type.ends_with('*') {
+        format!("{c_type}{name}")
+    } else {
+        format!("{c_type} {name}")
+    }
+}
+
+fn pointer(c_type: &str) -> String {
+    if c_type.ends_with('*') {
+        format!("{c_type}*")
+    } else {
+        format!("{c_type} *")
+    }
+}
+
+/// The part of a struct name that stands for `value_type`.
+fn type_name(value_type: &Type, records: &[Vec<(String, Type)>]) -> String {
+    match value_type {
+        Type::Int => "Int".to_string(),
+        Type::Real => "Real".to_string(),
+        Type::String => "String".to_string(),
+        Type::Bool => "Bool".to_string(),
+        Type::Array(element) => format!("{}Array", type_name(element, records)),
+        Type::Record(fields) => {
+            let index = records.iter().position(|known| known == fields);
+            format!("Record{}", index.map_or(0, |index| index + 1))
+        }
+        Type::Unknown => "Unknown".to_string(),
+    }
+}
+
+/// `text` inside a C string literal.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn parenthesize(text: String, binds: u8, precedence: u8) -> String {
+    if binds < precedence {
+        format!("({text})")
+    } else {
+        text
+    }
+}
+
+fn ident(name: &str) -> String {
+    if RESERVED.contains(&name) {
+        format!("{name}_")
+    } else {
+        name.to_string()
+    }
+}
@@ -0,0 +1,79 @@
+use crate::models::{
+    analysis::cfg::{Cfg, ENTRY, EXIT, EdgeKind, Step, cfgs},
+    flowchart::{condition, expression, statement},
+    nodes::program::Program,
+};
+
+/// The control-flow graphs of the program as a Graphviz digraph, with one
+/// cluster per function and one for `start`.
+pub fn transpile(program: &Program) -> String {
+    let mut text = String::from("digraph cfg {\n");
+    text.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+    for (index, cfg) in cfgs(program).iter().enumerate() {
+        cluster(&mut text, cfg, &format!("f{index}"));
+    }
+    text.push_str("}\n");
+    text
+}
+
+fn cluster(text: &mut String, cfg: &Cfg, prefix: &str) {
+    text.push_str(&format!("    subgraph cluster_{prefix} {{\n"));
+    text.push_str(&format!("        label=\"{}\";\n", escape(&cfg.name)));
+    let reachable = cfg.reachable();
+    for (index, block) in cfg.blocks.iter().enumerate() {
+        let attributes = match index {
+            ENTRY => "label=\"entry\", shape=oval".to_string(),
+            EXIT => "label=\"exit\", shape=oval".to_string(),
+            _ => {
+                // `\l` ends a left-aligned line.
+                let lines: String = block
+                    .steps
+                    .iter()
+                    .map(|step| format!("{}\\l", escape(&step_text(step))))
+                    .collect();
+                let label = format!("label=\"B{index}\\n{lines}\"");
+                if reachable[index] {
+                    label
+                } else {
+                    format!("{label}, style=dashed, fontcolor=gray50, color=gray50")
+                }
+            }
+        };
+        text.push_str(&format!("        {prefix}_{index} [{attributes}];\n"));
+    }
+    let back_edges = cfg.back_edges();
+    for edge in &cfg.edges {
+        let mut attributes = Vec::new();
+        match edge.kind {
+            EdgeKind::True => attributes.push("label=\"true\""),
+            EdgeKind::False => attributes.push("label=\"false\""),
+            EdgeKind::Jump => {}
+        }
+        if back_edges.iter().any(|back| std::ptr::eq(*back, edge)) {
+            attributes.push("style=bold");
+        }
+        let attributes = if attributes.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", attributes.join(", "))
+        };
+        text.push_str(&format!(
+            "        {prefix}_{} -> {prefix}_{}{attributes};\n",
+            edge.from, edge.to
+        ));
+    }
+    text.push_str("    }\n");
+}
+
+fn step_text(step: &Step) -> String {
+    match step {
+        Step::Statement(node) => statement(node),
+        Step::Condition(test) => format!("{}?", condition(test)),
+        Step::Next { item, list } => format!("next {item} in {}?", expression(list)),
+    }
+}
+
+/// `text` inside a quoted DOT string.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,702 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{
+    analysis::types::{Type, Typing},
+    nodes::{
+        assignment::Assignment,
+        comment::Comment,
+        condition::{Condition, If},
+        expression::Expression,
+        function::FunctionCall,
+        loops::{Loop, LoopType},
+        node::Node,
+        operator::{ArithmeticOperator, ComparisonOperator, LogicalOperator},
+        program::Program,
+        return_node::Return,
+        test_case::TestCase,
+        walk::{Scope, walk},
+    },
+    transpile::{Emitter, Scan, Writer, modified, root},
+};
+
+const INDENT: &str = "  ";
+
+/// JavaScript and TypeScript keywords, and the names the generated code uses
+/// itself, which a pseudocode name gets a trailing `_` to stay clear of.
+const RESERVED: &[&str] = &[
+    "arguments",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "eval",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "undefined",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+    "Array",
+    "JSON",
+    "Math",
+    "Number",
+    "String",
+    "console",
+    "main",
+    "structuredClone",
+];
+
+/// Rounding that sends halves away from zero like pseudocode does, where
+/// `Math.round` sends them up.
+const ROUND: &str = "\
+function _round(x) {
+  return Math.sign(x) * Math.round(Math.abs(x));
+}
+";
+const ROUND_TYPED: &str = "\
+function _round(x: number): number {
+  return Math.sign(x) * Math.round(Math.abs(x));
+}
+";
+
+/// How tightly a JavaScript expression binds, loosest first.
+const OR: u8 = 1;
+const AND: u8 = 2;
+const EQUALITY: u8 = 3;
+const RELATIONAL: u8 = 4;
+const SUM: u8 = 5;
+const PRODUCT: u8 = 6;
+const UNARY: u8 = 7;
+const ATOM: u8 = 8;
+
+/// The program as a script: a function per pseudocode function, `start` as
+/// `main()` followed by a call to it, and a `console.assert` per `test`
+/// line. With `typed` it is TypeScript, with every type that inference
+/// found written out.
+pub fn transpile(program: &Program, typed: bool) -> String {
+    let mut script = Script {
+        typing: Typing::new(program),
+        typed,
+        writer: Writer::new(INDENT),
+        functions: program.functions.iter().map(|f| f.name.as_str()).collect(),
+        round: false,
+        env: HashMap::new(),
+        loops: 0,
+        modified: HashSet::new(),
+        reassigned: HashSet::new(),
+        declared: HashSet::new(),
+    };
+
+    let mut definitions = Vec::new();
+    for function in &program.functions {
+        definitions.push(script.routine(
+            &function.name,
+            &ident(&function.name),
+            &function.params,
+            &function.body,
+        ));
+    }
+    definitions.push(script.routine("start", "main", &[], &program.start.body));
+
+    let mut sections = Vec::new();
+    if script.round {
+        sections.push(if typed { ROUND_TYPED } else { ROUND }.to_string());
+    }
+    sections.extend(definitions);
+    sections.push("main();\n".to_string());
+    if !program.tests.is_empty() {
+        script.env = HashMap::new();
+        let tests: Vec<String> = program.tests.iter().map(|test| script.test(test)).collect();
+        sections.push(tests.concat());
+    }
+    sections.join("\n")
+}
+
+struct Script<'a> {
+    typing: Typing,
+    /// Whether to write TypeScript.
+    typed: bool,
+    writer: Writer,
+    /// Names of the program's own functions, which win over builtins.
+    functions: HashSet<&'a str>,
+    /// Whether the `_round` helper is needed.
+    round: bool,
+    /// Types of the variables of the current routine.
+    env: HashMap<String, Type>,
+    /// How many loops the current statement is nested in.
+    loops: usize,
+    /// Variables of the current routine with an element or field assigned.
+    modified: HashSet<String>,
+    /// Variables of the current routine that need `let` over `const`.
+    reassigned: HashSet<String>,
+    /// Variables declared so far in the current routine.
+    declared: HashSet<String>,
+}
+
+impl Script<'_> {
+    fn routine(&mut self, routine: &str, name: &str, params: &[String], body: &[Node]) -> String {
+        self.env = self
+            .typing
+            .variables
+            .get(routine)
+            .cloned()
+            .unwrap_or_default();
+        self.loops = 0;
+        self.modified = modified(body);
+        let scan = Scan::new(body);
+        self.reassigned = scan.reassigned(params);
+        self.declared = params.iter().cloned().collect();
+
+        let names: Vec<String> = params
+            .iter()
+            .map(|param| {
+                let annotation = self.annotation(self.env.get(param).cloned());
+                format!("{}{annotation}", ident(param))
+            })
+            .collect();
+        let returns = self
+            .typing
+            .returns
+            .get(routine)
+            .cloned()
+            .filter(|_| has_return(body));
+        let returns = self.annotation(returns);
+        self.writer
+            .line(format!("function {name}({}){returns} {{", names.join(", ")));
+        self.writer.indent();
+        // Arrays and records are values in pseudocode, so a function that
+        // changes an element must not change the caller's copy.
+        for param in params {
+            if self.modified.contains(param) {
+                let name = ident(param);
+                self.writer
+                    .line(format!("{name} = structuredClone({name});"));
+            }
+        }
+        // A variable used outside the block it is first assigned in is
+        // declared up front, since a `let` only reaches the end of its block.
+        let mut hoisted: Vec<String> = scan.hoisted(params).into_iter().collect();
+        hoisted.sort();
+        for var in hoisted {
+            // TypeScript can't tell that every read comes after an
+            // assignment, so `!` promises it.
+            let annotation = self.annotation(self.env.get(&var).cloned());
+            let definite = if annotation.is_empty() { "" } else { "!" };
+            self.writer
+                .line(format!("let {}{definite}{annotation};", ident(&var)));
+            self.reassigned.insert(var.clone());
+            self.declared.insert(var);
+        }
+        self.statements(body);
+        self.writer.dedent();
+        self.writer.line("}");
+        self.writer.take()
+    }
+
+    fn test(&mut self, test: &TestCase) -> String {
+        let equal = self.equality(&test.call, &test.expected, "===");
+        format!(
+            "console.assert({equal}, \"test on line {}\");\n",
+            test.line + 1
+        )
+    }
+
+    /// `: type` for TypeScript when the type is known, otherwise nothing.
+    fn annotation(&self, value_type: Option<Type>) -> String {
+        match value_type {
+            Some(value_type) if self.typed && !value_type.is_partial() => {
+                format!(": {}", ts_type(&value_type))
+            }
+            _ => String::new(),
+        }
+    }
+
+    fn ty(&mut self, expression: &Expression) -> Type {
+        self.typing.expression(expression, &self.env)
+    }
+
+    /// `expression` wrapped in parentheses when it binds looser than
+    /// `precedence`.
+    fn operand(&mut self, expression: &Expression, precedence: u8) -> String {
+        let (text, binds) = self.expression(expression);
+        parenthesize(text, binds, precedence)
+    }
+
+    fn expression(&mut self, expression: &Expression) -> (String, u8) {
+        match expression {
+            Expression::Variable(name) => (ident(name), ATOM),
+            Expression::Literal(text) => literal(text),
+            Expression::BinaryOp {
+                left,
+                operator,
+                right,
+            } => self.arithmetic(expression, left, operator, right),
+            Expression::FunctionCall { name, args } => self.function_call(name, args),
+            Expression::Not(inner) => (format!("!{}", self.operand(inner, UNARY)), UNARY),
+            Expression::Array(items) => (format!("[{}]", self.list(items)), ATOM),
+            Expression::Record(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, value)| format!("{name}: {}", self.operand(value, OR)))
+                    .collect();
+                (format!("{{ {} }}", fields.join(", ")), ATOM)
+            }
+            Expression::Index { target, index } => {
+                let target = self.operand(target, ATOM);
+                (format!("{target}[{}]", self.operand(index, OR)), ATOM)
+            }
+            Expression::Field { target, name } => {
+                (format!("{}.{name}", self.operand(target, ATOM)), ATOM)
+            }
+        }
+    }
+
+    fn arithmetic(
+        &mut self,
+        expression: &Expression,
+        left: &Expression,
+        operator: &ArithmeticOperator,
+        right: &Expression,
+    ) -> (String, u8) {
+        match operator {
+            ArithmeticOperator::Addition if matches!(self.ty(expression), Type::Array(_)) => {
+                let items = [self.spread(left), self.spread(right)];
+                return (format!("[{}]", items.join(", ")), ATOM);
+            }
+            // Numbers are all floating point in JavaScript, so division of
+            // two ints drops the fraction itself.
+            ArithmeticOperator::Division if self.ty(expression) == Type::Int => {
+                let left = self.operand(left, PRODUCT);
+                let right = self.operand(right, PRODUCT + 1);
+                return (format!("Math.trunc({left} / {right})"), ATOM);
+            }
+            _ => {}
+        }
+        let symbol = match operator {
+            ArithmeticOperator::Addition => "+",
+            ArithmeticOperator::Subtraction => "-",
+            ArithmeticOperator::Multiplication => "*",
+            ArithmeticOperator::Division => "/",
+            // `%` keeps the sign of the dividend, as pseudocode does.
+            ArithmeticOperator::Modulo => "%",
+        };
+        let precedence = match operator {
+            ArithmeticOperator::Addition | ArithmeticOperator::Subtraction => SUM,
+            _ => PRODUCT,
+        };
+        let left = self.operand(left, precedence);
+        let right = self.operand(right, precedence + 1);
+        (format!("{left} {symbol} {right}"), precedence)
+    }
+
+    /// A call to one of the program's functions, or the JavaScript spelling
+    /// of a builtin.
+    fn function_call(&mut self, name: &str, args: &[Expression]) -> (String, u8) {
+        if self.functions.contains(name) {
+            return (format!("{}({})", ident(name), self.list(args)), ATOM);
+        }
+        let call = |function: &str, args: String| (format!("{function}({args})"), ATOM);
+        match (name, args) {
+            ("print", _) => call("console.log", self.list(args)),
+            ("length", [value]) => (format!("{}.length", self.operand(value, ATOM)), ATOM),
+            ("abs" | "min" | "max" | "sqrt" | "pow" | "floor" | "ceil", _) => {
+                call(&format!("Math.{name}"), self.list(args))
+            }
+            ("round", [value]) if self.ty(value) == Type::Int => self.expression(value),
+            ("round", _) => {
+                self.round = true;
+                call("_round", self.list(args))
+            }
+            ("random", [bound]) => {
+                let bound = self.operand(bound, PRODUCT + 1);
+                (format!("Math.floor(Math.random() * {bound})"), ATOM)
+            }
+            ("range", [from, to]) => {
+                let (length, item) = match from {
+                    Expression::Literal(text) if text == "0" => {
+                        (self.operand(to, OR), "i".to_string())
+                    }
+                    _ => {
+                        let to = self.operand(to, SUM);
+                        let length = format!("{to} - {}", self.operand(from, SUM + 1));
+                        (length, format!("{} + i", self.operand(from, SUM)))
+                    }
+                };
+                (
+                    format!("Array.from({{ length: {length} }}, (_, i) => {item})"),
+                    ATOM,
+                )
+            }
+            ("append", [items, item]) => {
+                let items = self.spread(items);
+                (format!("[{items}, {}]", self.operand(item, OR)), ATOM)
+            }
+            ("contains", [haystack, needle]) => {
+                let haystack = self.operand(haystack, ATOM);
+                (
+                    format!("{haystack}.includes({})", self.operand(needle, OR)),
+                    ATOM,
+                )
+            }
+            ("substring", [text, from, to]) => {
+                let text = self.operand(text, ATOM);
+                let from = self.operand(from, OR);
+                (
+                    format!("{text}.substring({from}, {})", self.operand(to, OR)),
+                    ATOM,
+                )
+            }
+            ("to_upper" | "to_lower", [text]) => {
+                let method = if name == "to_upper" {
+                    "toUpperCase"
+                } else {
+                    "toLowerCase"
+                };
+                (format!("{}.{method}()", self.operand(text, ATOM)), ATOM)
+            }
+            ("to_string", [value]) => match self.ty(value) {
+                Type::Array(_) | Type::Record(_) => call("JSON.stringify", self.list(args)),
+                _ => call("String", self.list(args)),
+            },
+            ("to_int", [value]) => match self.ty(value) {
+                Type::Int => self.expression(value),
+                Type::String => call("Number.parseInt", format!("{}, 10", self.list(args))),
+                _ => call("Math.trunc", self.list(args)),
+            },
+            ("to_real", [value]) => match self.ty(value) {
+                Type::Int | Type::Real => self.expression(value),
+                Type::String => call("Number.parseFloat", self.list(args)),
+                _ => call("Number", self.list(args)),
+            },
+            _ => call(name, self.list(args)),
+        }
+    }
+
+    /// The elements of an array inside another array literal.
+    fn spread(&mut self, items: &Expression) -> String {
+        match items {
+            Expression::Array(items) => self.list(items),
+            _ => format!("...{}", self.operand(items, UNARY)),
+        }
+    }
+
+    fn list(&mut self, items: &[Expression]) -> String {
+        let items: Vec<String> = items.iter().map(|item| self.operand(item, OR)).collect();
+        items.join(", ")
+    }
+
+    /// `left === right`, or with `!==` as `operator`. Arrays and records
+    /// are compared by their contents, which `===` doesn't do.
+    fn equality(&mut self, left: &Expression, right: &Expression, operator: &str) -> String {
+        let by_value = [left, right]
+            .into_iter()
+            .any(|side| matches!(self.ty(side), Type::Array(_) | Type::Record(_)));
+        if by_value {
+            let left = self.operand(left, OR);
+            let right = self.operand(right, OR);
+            return format!("JSON.stringify({left}) {operator} JSON.stringify({right})");
+        }
+        let left = self.operand(left, EQUALITY);
+        let right = self.operand(right, EQUALITY + 1);
+        format!("{left} {operator} {right}")
+    }
+
+    fn condition(&mut self, condition: &Condition) -> (String, u8) {
+        match condition {
+            Condition::ComparisonOperator {
+                left,
+                operator,
+                right,
+            } => {
+                let symbol = match **operator {
+                    ComparisonOperator::Eq => return (self.equality(left, right, "==="), EQUALITY),
+                    ComparisonOperator::Neq => {
+                        return (self.equality(left, right, "!=="), EQUALITY);
+                    }
+                    ComparisonOperator::Gt => ">",
+                    ComparisonOperator::Lt => "<",
+                    ComparisonOperator::Gte => ">=",
+                    ComparisonOperator::Lte => "<=",
+                };
+                let left = self.operand(left, RELATIONAL);
+                let right = self.operand(right, RELATIONAL + 1);
+                (format!("{left} {symbol} {right}"), RELATIONAL)
+            }
+            Condition::LogicalOperator {
+                left,
+                operator,
+                right,
+            } => {
+                let (symbol, precedence) = match **operator {
+                    LogicalOperator::And => ("&&", AND),
+                    LogicalOperator::Or => ("||", OR),
+                };
+                let (left, binds) = self.condition(left);
+                let left = parenthesize(left, binds, precedence);
+                let (right, binds) = self.condition(right);
+                let right = parenthesize(right, binds, precedence + 1);
+                (format!("{left} {symbol} {right}"), precedence)
+            }
+            Condition::Value(value) => self.expression(value),
+        }
+    }
+
+    fn condition_text(&mut self, condition: &Condition) -> String {
+        self.condition(condition).0
+    }
+}
+
+impl Emitter for Script<'_> {
+    fn writer(&mut self) -> &mut Writer {
+        &mut self.writer
+    }
+
+    fn assignment(&mut self, assignment: &Assignment) {
+        let target = self.operand(&assignment.target, OR);
+        let mut value = self.operand(&assignment.value, OR);
+        // Assigning an array or record to a variable shares it in
+        // JavaScript, which shows once an element is changed through either
+        // name.
+        let shared = match (&assignment.target, &assignment.value) {
+            (Expression::Variable(target), Expression::Variable(value)) => {
+                self.modified.contains(target) || self.modified.contains(value)
+            }
+            (Expression::Variable(target), Expression::Index { .. } | Expression::Field { .. }) => {
+                self.modified.contains(target)
+            }
+            _ => false,
+        };
+        if shared {
+            value = format!("structuredClone({value})");
+        }
+        let declaration = match assignment.var() {
+            Some(var) if self.declared.insert(var.to_string()) => {
+                let keyword = if self.reassigned.contains(var) {
+                    "let"
+                } else {
+                    "const"
+                };
+                // An empty array literal tells TypeScript nothing about the
+                // elements.
+                let annotation = match &assignment.value {
+                    Expression::Array(items) if items.is_empty() => {
+                        self.annotation(self.env.get(var).cloned())
+                    }
+                    _ => String::new(),
+                };
+                format!("{keyword} {target}{annotation}")
+            }
+            _ => target,
+        };
+        self.writer.line(format!("{declaration} = {value};"));
+    }
+
+    fn call(&mut self, call: &FunctionCall) {
+        let (text, _) = self.function_call(&call.name, &call.args);
+        self.writer.line(format!("{text};"));
+    }
+
+    fn return_value(&mut self, value: &Return) {
+        let value = self.operand(&value.value, OR);
+        self.writer.line(format!("return {value};"));
+    }
+
+    fn if_statement(&mut self, statement: &If) {
+        let condition = self.condition_text(&statement.condition);
+        self.writer.line(format!("if ({condition}) {{"));
+        self.block(&statement.body);
+        for branch in &statement.else_ifs {
+            let condition = self.condition_text(&branch.condition);
+            self.writer.line(format!("}} else if ({condition}) {{"));
+            self.block(&branch.body);
+        }
+        if let Some(branch) = &statement.else_branch {
+            self.writer.line("} else {");
+            self.block(&branch.body);
+        }
+        self.writer.line("}");
+    }
+
+    fn loop_statement(&mut self, statement: &Loop) {
+        let header = match &statement.loop_type {
+            LoopType::While(condition) => format!("while ({}) {{", self.condition_text(condition)),
+            LoopType::For { item, list } => {
+                let name = ident(item);
+                let reassigned = self.reassigned.contains(item);
+                let keyword = if reassigned { "let" } else { "const" };
+                match list {
+                    // A counting loop over a range, when nothing in the
+                    // body can change where it stops.
+                    Expression::FunctionCall { name: range, args }
+                        if range == "range"
+                            && !self.functions.contains(range.as_str())
+                            && !reassigned
+                            && args.len() == 2
+                            && fixed(&args[1], &statement.body) =>
+                    {
+                        let from = self.operand(&args[0], OR);
+                        let to = self.operand(&args[1], RELATIONAL + 1);
+                        format!("for (let {name} = {from}; {name} < {to}; {name}++) {{")
+                    }
+                    _ => format!("for ({keyword} {name} of {}) {{", self.operand(list, OR)),
+                }
+            }
+        };
+        self.writer.line(header);
+        // The loop declares its item, for the body only.
+        let item = match &statement.loop_type {
+            LoopType::For { item, .. } => {
+                Some(item).filter(|item| self.declared.insert(item.to_string()))
+            }
+            LoopType::While(_) => None,
+        };
+        self.loops += 1;
+        self.block(&statement.body);
+        self.loops -= 1;
+        if let Some(item) = item {
+            self.declared.remove(item);
+        }
+        self.writer.line("}");
+    }
+
+    // Outside a loop `break` and `continue` leave the routine.
+    fn break_statement(&mut self, _line: usize) {
+        let keyword = if self.loops > 0 { "break;" } else { "return;" };
+        self.writer.line(keyword);
+    }
+
+    fn continue_statement(&mut self, _line: usize) {
+        let keyword = if self.loops > 0 {
+            "continue;"
+        } else {
+            "return;"
+        };
+        self.writer.line(keyword);
+    }
+
+    fn comment(&mut self, comment: &Comment) {
+        self.writer.line(format!("// {}", comment.value).trim_end());
+    }
+}
+
+/// Whether `bound` has the same value all through `body`: it only reads
+/// variables the body never assigns, and calls nothing but `length`.
+fn fixed(bound: &Expression, body: &[Node]) -> bool {
+    let mut names = HashSet::new();
+    let mut calls = false;
+    bound.visit(&mut |expression| match expression {
+        Expression::Variable(name) => {
+            names.insert(name.as_str());
+        }
+        Expression::FunctionCall { name, .. } if name != "length" => calls = true,
+        _ => {}
+    });
+    let mut assigned = false;
+    walk(body, Scope::default(), &mut |node, _| {
+        if let Node::Assignment(val) = node
+            && root(&val.target).is_some_and(|root| names.contains(root))
+        {
+            assigned = true;
+        }
+    });
+    !calls && !assigned
+}
+
+fn has_return(body: &[Node]) -> bool {
+    let mut found = false;
+    walk(body, Scope::default(), &mut |node, _| {
+        found |= matches!(node, Node::Return(_));
+    });
+    found
+}
+
+fn ts_type(value_type: &Type) -> String {
+    match value_type {
+        Type::Int | Type::Real => "number".to_string(),
+        Type::String => "string".to_string(),
+        Type::Bool => "boolean".to_string(),
+        Type::Array(element) => format!("{}[]", ts_type(element)),
+        Type::Record(fields) => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(name, field)| format!("{name}: {}", ts_type(field)))
+                .collect();
+            format!("{{ {} }}", fields.join("; "))
+        }
+        Type::Unknown => "unknown".to_string(),
+    }
+}
+
+fn parenthesize(text: String, binds: u8, precedence: u8) -> String {
+    if binds < precedence {
+        format!("({text})")
+    } else {
+        text
+    }
+}
+
+fn ident(name: &str) -> String {
+    if RESERVED.contains(&name) {
+        format!("{name}_")
+    } else {
+        name.to_string()
+    }
+}
+
+/// A pseudocode literal in JavaScript syntax, and how tightly it binds.
+fn literal(text: &str) -> (String, u8) {
+    if let Some(inner) = text.strip_prefix('"') {
+        return (format!("\"{}", inner.replace('\\', "\\\\")), ATOM);
+    }
+    if text == "true" || text == "false" {
+        return (text.to_string(), ATOM);
+    }
+    let binds = if text.starts_with('-') { UNARY } else { ATOM };
+    if let Ok(value) = text.parse::<i64>() {
+        return (value.to_string(), binds);
+    }
+    match text.parse::<f64>() {
+        Ok(value) if value.is_nan() => ("NaN".to_string(), ATOM),
+        Ok(value) if value.is_infinite() && value > 0.0 => ("Infinity".to_string(), ATOM),
+        Ok(value) if value.is_infinite() => ("-Infinity".to_string(), UNARY),
+        Ok(value) => (format!("{value:?}"), binds),
+        Err(_) => (text.to_string(), ATOM),
+    }
+}
@@ -0,0 +1,441 @@
+use std::collections::HashSet;
+
+use crate::models::{
+    nodes::{
+        assignment::Assignment,
+        comment::Comment,
+        condition::{Condition, If},
+        expression::Expression,
+        function::FunctionCall,
+        loops::{Loop, LoopType},
+        node::Node,
+        operator::{ArithmeticOperator, ComparisonOperator, LogicalOperator},
+        program::Program,
+        return_node::Return,
+    },
+    transpile::{Emitter, Writer},
+};
+
+const INDENT: &str = "  ";
+
+/// How tightly an expression binds, loosest first.
+const OR: u8 = 1;
+const AND: u8 = 2;
+const NOT: u8 = 3;
+const COMPARE: u8 = 4;
+const SUM: u8 = 5;
+const PRODUCT: u8 = 6;
+const ATOM: u8 = 7;
+
+/// The program as an algorithm for LaTeX: each function and `start` as a
+/// function of an `algorithmic` environment from `algpseudocode`, or with
+/// `algorithm2e` as one `algorithm` environment of that package. Tests are
+/// left out, since they aren't part of the algorithm.
+pub fn transpile(program: &Program, algorithm2e: bool) -> String {
+    let mut latex = Latex {
+        algorithm2e,
+        writer: Writer::new(INDENT),
+        functions: program.functions.iter().map(|f| f.name.as_str()).collect(),
+    };
+    if algorithm2e {
+        latex
+            .writer
+            .line("% \\usepackage[ruled,linesnumbered]{algorithm2e}");
+        latex.writer.line("\\begin{algorithm}");
+        latex.writer.line("\\SetKwProg{Fn}{function}{}{end}");
+        latex.writer.line("\\SetKwProg{Proc}{procedure}{}{end}");
+    } else {
+        latex.writer.line("% \\usepackage{algpseudocode}");
+        latex.writer.line("\\begin{algorithmic}[1]");
+    }
+    for function in &program.functions {
+        let params: Vec<String> = function.params.iter().map(|param| name(param)).collect();
+        latex.routine(&function.name, &params.join(", "), &function.body, false);
+    }
+    latex.routine("start", "", &program.start.body, true);
+    if algorithm2e {
+        latex.writer.line("\\end{algorithm}");
+    } else {
+        latex.writer.line("\\end{algorithmic}");
+    }
+    latex.writer.take()
+}
+
+struct Latex<'a> {
+    /// Whether to write `algorithm2e` rather than `algpseudocode`.
+    algorithm2e: bool,
+    writer: Writer,
+    /// Names of the program's own functions, which win over builtins.
+    functions: HashSet<&'a str>,
+}
+
+impl Latex<'_> {
+    /// A function, or with `procedure` a routine that returns nothing.
+    fn routine(&mut self, routine: &str, params: &str, body: &[Node], procedure: bool) {
+        let title = escape(routine);
+        if self.algorithm2e {
+            let params = if params.is_empty() {
+                String::new()
+            } else {
+                format!("(${params}$)")
+            };
+            let keyword = if procedure { "Proc" } else { "Fn" };
+            self.writer
+                .line(format!("\\{keyword}{{\\textsc{{{title}}}{params}}}{{"));
+            self.block(body);
+            self.writer.line("}");
+        } else {
+            let params = if params.is_empty() {
+                String::new()
+            } else {
+                format!("${params}$")
+            };
+            let keyword = if procedure { "Procedure" } else { "Function" };
+            self.writer
+                .line(format!("\\{keyword}{{{title}}}{{{params}}}"));
+            self.block(body);
+            self.writer.line(format!("\\End{keyword}"));
+        }
+    }
+
+    /// A line holding `text`, ended the way the style ends a statement.
+    fn state(&mut self, text: &str) {
+        if self.algorithm2e {
+            self.writer.line(format!("{text}\\;"));
+        } else {
+            self.writer.line(format!("\\State {text}"));
+        }
+    }
+
+    /// `expression` in math mode.
+    fn math(&mut self, expression: &Expression) -> String {
+        format!("${}$", self.operand(expression, OR))
+    }
+
+    fn condition_math(&mut self, condition: &Condition) -> String {
+        format!("${}$", self.condition(condition).0)
+    }
+
+    fn operand(&mut self, expression: &Expression, precedence: u8) -> String {
+        let (text, binds) = self.expression(expression);
+        parenthesize(text, binds, precedence)
+    }
+
+    fn expression(&mut self, expression: &Expression) -> (String, u8) {
+        match expression {
+            Expression::Variable(var) => (name(var), ATOM),
+            Expression::Literal(text) => (literal(text), ATOM),
+            Expression::BinaryOp {
+                left,
+                operator,
+                right,
+            } => {
+                let (symbol, precedence) = match **operator {
+                    ArithmeticOperator::Addition => ("+", SUM),
+                    ArithmeticOperator::Subtraction => ("-", SUM),
+                    ArithmeticOperator::Multiplication => ("\\cdot", PRODUCT),
+                    ArithmeticOperator::Division => ("/", PRODUCT),
+                    ArithmeticOperator::Modulo => ("\\bmod", PRODUCT),
+                };
+                let left = self.operand(left, precedence);
+                let right = self.operand(right, precedence + 1);
+                (format!("{left} {symbol} {right}"), precedence)
+            }
+            Expression::FunctionCall { name, args } => self.function_call(name, args),
+            Expression::Not(inner) => (format!("\\lnot {}", self.operand(inner, NOT)), NOT),
+            Expression::Array(items) => (format!("[{}]", self.list(items)), ATOM),
+            Expression::Record(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(field, value)| format!("{}: {}", name(field), self.operand(value, OR)))
+                    .collect();
+                (format!("\\{{{}\\}}", fields.join(", ")), ATOM)
+            }
+            Expression::Index { target, index } => {
+                let target = self.operand(target, ATOM);
+                (format!("{target}[{}]", self.operand(index, OR)), ATOM)
+            }
+            Expression::Field {
+                target,
+                name: field,
+            } => (
+                format!("{}.{}", self.operand(target, ATOM), name(field)),
+                ATOM,
+            ),
+        }
+    }
+
+    /// A call, with the builtins that have a usual notation written in it.
+    fn function_call(&mut self, function: &str, args: &[Expression]) -> (String, u8) {
+        if !self.functions.contains(function) {
+            match (function, args) {
+                ("length" | "abs", [value]) => {
+                    return (format!("|{}|", self.operand(value, OR)), ATOM);
+                }
+                ("floor", [value]) => {
+                    return (
+                        format!("\\lfloor {} \\rfloor", self.operand(value, OR)),
+                        ATOM,
+                    );
+                }
+                ("ceil", [value]) => {
+                    return (format!("\\lceil {} \\rceil", self.operand(value, OR)), ATOM);
+                }
+                ("sqrt", [value]) => {
+                    return (format!("\\sqrt{{{}}}", self.operand(value, OR)), ATOM);
+                }
+                ("pow", [base, exponent]) => {
+                    let base = self.operand(base, ATOM);
+                    return (
+                        format!("{{{base}}}^{{{}}}", self.operand(exponent, OR)),
+                        ATOM,
+                    );
+                }
+                _ => {}
+            }
+        }
+        let args = self.list(args);
+        let title = escape(function);
+        if self.algorithm2e {
+            (format!("\\textsc{{{title}}}({args})"), ATOM)
+        } else {
+            (format!("\\Call{{{title}}}{{{args}}}"), ATOM)
+        }
+    }
+
+    fn list(&mut self, items: &[Expression]) -> String {
+        let items: Vec<String> = items.iter().map(|item| self.operand(item, OR)).collect();
+        items.join(", ")
+    }
+
+    fn condition(&mut self, condition: &Condition) -> (String, u8) {
+        match condition {
+            Condition::ComparisonOperator {
+                left,
+                operator,
+                right,
+            } => {
+                let symbol = match **operator {
+                    ComparisonOperator::Eq => "=",
+                    ComparisonOperator::Neq => "\\neq",
+                    ComparisonOperator::Gt => ">",
+                    ComparisonOperator::Lt => "<",
+                    ComparisonOperator::Gte => "\\geq",
+                    ComparisonOperator::Lte => "\\leq",
+                };
+                let left = self.operand(left, SUM);
+                let right = self.operand(right, SUM);
+                (format!("{left} {symbol} {right}"), COMPARE)
+            }
+            Condition::LogicalOperator {
+                left,
+                operator,
+                right,
+            } => {
+                let (symbol, precedence) = match **operator {
+                    LogicalOperator::And => ("\\land", AND),
+                    LogicalOperator::Or => ("\\lor", OR),
+                };
+                let (left, binds) = self.condition(left);
+                let left = parenthesize(left, binds, precedence);
+                let (right, binds) = self.condition(right);
+                let right = parenthesize(right, binds, precedence + 1);
+                (format!("{left} {symbol} {right}"), precedence)
+            }
+            Condition::Value(value) => self.expression(value),
+        }
+    }
+
+    /// The head of a `for` loop: a count from one bound to the other over a
+    /// range, or each item of a list. Tells which of the two it is.
+    fn for_head(&mut self, item: &str, list: &Expression) -> (String, bool) {
+        if let Expression::FunctionCall { name: range, args } = list
+            && range == "range"
+            && !self.functions.contains(range.as_str())
+            && let [from, to] = args.as_slice()
+        {
+            let from = self.operand(from, OR);
+            // The last value is one below the bound, folded into a constant
+            // the bound ends with.
+            let to = match to {
+                Expression::Literal(text) if let Ok(to) = text.parse::<i64>() => {
+                    (to - 1).to_string()
+                }
+                Expression::BinaryOp {
+                    left,
+                    operator,
+                    right,
+                } if matches!(**operator, ArithmeticOperator::Subtraction)
+                    && let Expression::Literal(text) = &**right
+                    && let Ok(offset) = text.parse::<i64>() =>
+                {
+                    format!("{} - {}", self.operand(left, SUM), offset + 1)
+                }
+                _ => format!("{} - 1", self.operand(to, SUM)),
+            };
+            let keyword = if self.algorithm2e {
+                "\\KwTo"
+            } else {
+                "\\textbf{to}"
+            };
+            let head = format!("${} \\gets {from}$ {keyword} ${to}$", name(item));
+            return (head, true);
+        }
+        let head = format!("${} \\in {}$", name(item), self.operand(list, OR));
+        (head, false)
+    }
+}
+
+impl Emitter for Latex<'_> {
+    fn writer(&mut self) -> &mut Writer {
+        &mut self.writer
+    }
+
+    fn assignment(&mut self, assignment: &Assignment) {
+        let target = self.operand(&assignment.target, OR);
+        let value = self.operand(&assignment.value, OR);
+        self.state(&format!("${target} \\gets {value}$"));
+    }
+
+    fn call(&mut self, call: &FunctionCall) {
+        let (text, _) = self.function_call(&call.name, &call.args);
+        self.state(&format!("${text}$"));
+    }
+
+    fn return_value(&mut self, value: &Return) {
+        let value = self.math(&value.value);
+        if self.algorithm2e {
+            self.state(&format!("\\Return{{{value}}}"));
+        } else {
+            self.state(&format!("\\Return {value}"));
+        }
+    }
+
+    fn if_statement(&mut self, statement: &If) {
+        let condition = self.condition_math(&statement.condition);
+        if !self.algorithm2e {
+            self.writer.line(format!("\\If{{{condition}}}"));
+            self.block(&statement.body);
+            for branch in &statement.else_ifs {
+                let condition = self.condition_math(&branch.condition);
+                self.writer.line(format!("\\ElsIf{{{condition}}}"));
+                self.block(&branch.body);
+            }
+            if let Some(branch) = &statement.else_branch {
+                self.writer.line("\\Else");
+                self.block(&branch.body);
+            }
+            self.writer.line("\\EndIf");
+            return;
+        }
+        // algorithm2e ends a chain after its last branch, so the branches
+        // before it use the `u` forms that don't.
+        let chained = !statement.else_ifs.is_empty() || statement.else_branch.is_some();
+        let keyword = if chained { "\\uIf" } else { "\\If" };
+        self.writer.line(format!("{keyword}{{{condition}}}{{"));
+        self.block(&statement.body);
+        self.writer.line("}");
+        for (index, branch) in statement.else_ifs.iter().enumerate() {
+            let last = index + 1 == statement.else_ifs.len() && statement.else_branch.is_none();
+            let keyword = if last { "\\ElseIf" } else { "\\uElseIf" };
+            let condition = self.condition_math(&branch.condition);
+            self.writer.line(format!("{keyword}{{{condition}}}{{"));
+            self.block(&branch.body);
+            self.writer.line("}");
+        }
+        if let Some(branch) = &statement.else_branch {
+            self.writer.line("\\Else{");
+            self.block(&branch.body);
+            self.writer.line("}");
+        }
+    }
+
+    fn loop_statement(&mut self, statement: &Loop) {
+        let (keyword, head, end) = match &statement.loop_type {
+            LoopType::While(condition) => ("While", self.condition_math(condition), "EndWhile"),
+            LoopType::For { item, list } => {
+                let (head, counted) = self.for_head(item, list);
+                let keyword = if self.algorithm2e && !counted {
+                    "ForEach"
+                } else {
+                    "For"
+                };
+                (keyword, head, "EndFor")
+            }
+        };
+        if self.algorithm2e {
+            self.writer.line(format!("\\{keyword}{{{head}}}{{"));
+            self.block(&statement.body);
+            self.writer.line("}");
+        } else {
+            self.writer.line(format!("\\{keyword}{{{head}}}"));
+            self.block(&statement.body);
+            self.writer.line(format!("\\{end}"));
+        }
+    }
+
+    fn break_statement(&mut self, _line: usize) {
+        self.state("\\textbf{break}");
+    }
+
+    fn continue_statement(&mut self, _line: usize) {
+        self.state("\\textbf{continue}");
+    }
+
+    fn comment(&mut self, comment: &Comment) {
+        let text = escape(comment.value.trim());
+        if self.algorithm2e {
+            self.writer.line(format!("\\tcp{{{text}}}"));
+        } else {
+            self.writer.line(format!("\\State \\Comment{{{text}}}"));
+        }
+    }
+}
+
+fn parenthesize(text: String, binds: u8, precedence: u8) -> String {
+    if binds < precedence {
+        format!("({text})")
+    } else {
+        text
+    }
+}
+
+/// An identifier in math mode, set as one italic word when it is longer than
+/// a letter, since math mode would space it like a product.
+fn name(identifier: &str) -> String {
+    if identifier.chars().count() == 1 {
+        identifier.to_string()
+    } else {
+        format!("\\mathit{{{}}}", escape(identifier))
+    }
+}
+
+/// A pseudocode literal in math mode.
+fn literal(text: &str) -> String {
+    if let Some(inner) = text.strip_prefix('"') {
+        let inner = inner.strip_suffix('"').unwrap_or(inner);
+        return format!("\\texttt{{\"{}\"}}", escape(inner));
+    }
+    match text {
+        "true" | "false" => format!("\\textbf{{{text}}}"),
+        _ => text.to_string(),
+    }
+}
+
+/// `text` with the characters LaTeX treats specially escaped.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for character in text.chars() {
+        match character {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '#' | '$' | '%' | '&' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(character);
+            }
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
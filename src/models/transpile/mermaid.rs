@@ -0,0 +1,57 @@
+use crate::models::{
+    flowchart::{Flowchart, Shape, flowcharts},
+    nodes::program::Program,
+};
+
+/// The flowcharts of the program as a Mermaid `flowchart TD`, with one
+/// subgraph per function and one for `start`.
+pub fn transpile(program: &Program) -> String {
+    let mut text = String::from("flowchart TD\n");
+    for (index, chart) in flowcharts(program).iter().enumerate() {
+        chart_text(&mut text, chart, &format!("f{index}"));
+    }
+    text
+}
+
+fn chart_text(text: &mut String, chart: &Flowchart, prefix: &str) {
+    text.push_str(&format!(
+        "    subgraph {prefix} [\"{}\"]\n",
+        escape(&chart.name)
+    ));
+    for (index, block) in chart.blocks.iter().enumerate() {
+        let label = escape(&block.text);
+        let shape = match block.shape {
+            Shape::Terminal => format!("([\"{label}\"])"),
+            Shape::Process => format!("[\"{label}\"]"),
+            Shape::Decision => format!("{{\"{label}\"}}"),
+        };
+        text.push_str(&format!("        {prefix}_{index}{shape}\n"));
+    }
+    for edge in &chart.edges {
+        let label = edge
+            .label
+            .map(|label| format!("|{label}|"))
+            .unwrap_or_default();
+        text.push_str(&format!(
+            "        {prefix}_{} -->{label} {prefix}_{}\n",
+            edge.from, edge.to
+        ));
+    }
+    text.push_str("    end\n");
+}
+
+/// `text` with the characters that would end a quoted label or be read as
+/// HTML written as Mermaid entity codes.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for character in text.chars() {
+        match character {
+            '#' => escaped.push_str("#35;"),
+            '"' => escaped.push_str("#quot;"),
+            '<' => escaped.push_str("#lt;"),
+            '>' => escaped.push_str("#gt;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,438 @@
+pub mod c;
+pub mod dot;
+pub mod javascript;
+pub mod latex;
+pub mod mermaid;
+pub mod python;
+pub mod rust;
+pub mod structogram;
+pub mod typst;
+
+use std::collections::{HashMap, HashSet};
+
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{
+    analysis::types::{Type, Typing},
+    nodes::{
+        assignment::Assignment,
+        comment::Comment,
+        condition::{Condition, If},
+        expression::Expression,
+        function::FunctionCall,
+        loops::{Loop, LoopType},
+        node::Node,
+        program::Program,
+        return_node::Return,
+        walk::{Scope, walk},
+    },
+};
+
+/// A language the pseudocode can be translated to.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Language {
+    #[default]
+    Python,
+    Rust,
+    JavaScript,
+    TypeScript,
+    C,
+    Latex,
+    Algorithm2e,
+    Typst,
+    Mermaid,
+    Dot,
+    Structogram,
+}
+
+impl Language {
+    pub const ALL: &[Language] = &[
+        Language::Python,
+        Language::Rust,
+        Language::JavaScript,
+        Language::TypeScript,
+        Language::C,
+        Language::Latex,
+        Language::Algorithm2e,
+        Language::Typst,
+        Language::Mermaid,
+        Language::Dot,
+        Language::Structogram,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::Python => "python",
+            Language::Rust => "rust",
+            Language::JavaScript => "javascript",
+            Language::TypeScript => "typescript",
+            Language::C => "c",
+            Language::Latex => "latex",
+            Language::Algorithm2e => "algorithm2e",
+            Language::Typst => "typst",
+            Language::Mermaid => "mermaid",
+            Language::Dot => "dot",
+            Language::Structogram => "structogram",
+        }
+    }
+
+    /// File extension of the generated code, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Language::Python => "py",
+            Language::Rust => "rs",
+            Language::JavaScript => "js",
+            Language::TypeScript => "ts",
+            Language::C => "c",
+            Language::Latex | Language::Algorithm2e => "tex",
+            Language::Typst => "typ",
+            Language::Mermaid => "mmd",
+            Language::Dot => "dot",
+            Language::Structogram => "svg",
+        }
+    }
+
+    /// The language called `name` or using it as file extension.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|language| language.name() == name || language.extension() == name)
+    }
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&language| language == self);
+        Self::ALL[index.map_or(0, |index| (index + 1) % Self::ALL.len())]
+    }
+}
+
+/// The parameter types of a function, and its return type unless it returns
+/// nothing.
+pub type Signature = (Vec<Type>, Option<Type>);
+
+/// Signatures of the functions whose types are all known, for languages
+/// that spell every type out.
+pub fn signatures<'a>(program: &'a Program, typing: &Typing) -> HashMap<&'a str, Signature> {
+    let mut signatures = HashMap::new();
+    for function in &program.functions {
+        let params = typing
+            .params
+            .get(&function.name)
+            .cloned()
+            .unwrap_or_else(|| vec![Type::Unknown; function.params.len()]);
+        let returns = typing
+            .returns
+            .get(&function.name)
+            .cloned()
+            .unwrap_or(Type::Unknown);
+        let mut has_return = false;
+        walk(&function.body, Scope::default(), &mut |node, _| {
+            has_return |= matches!(node, Node::Return(_));
+        });
+        if params.iter().any(Type::is_partial) || (has_return && returns.is_partial()) {
+            continue;
+        }
+        signatures.insert(
+            function.name.as_str(),
+            (params, has_return.then_some(returns)),
+        );
+    }
+    signatures
+}
+
+/// Translates the whole program into `language`, tests included wherever
+/// the language can run them.
+pub fn transpile(program: &Program, language: Language) -> Result<String> {
+    Ok(match language {
+        Language::Python => python::transpile(program),
+        Language::Rust => rust::transpile(program),
+        Language::JavaScript => javascript::transpile(program, false),
+        Language::TypeScript => javascript::transpile(program, true),
+        Language::C => c::transpile(program)?,
+        Language::Latex => latex::transpile(program, false),
+        Language::Algorithm2e => latex::transpile(program, true),
+        Language::Typst => typst::transpile(program),
+        Language::Mermaid => mermaid::transpile(program),
+        Language::Dot => dot::transpile(program),
+        Language::Structogram => structogram::transpile(program),
+    })
+}
+
+/// Generated source text, one line at a time at the current indentation.
+pub struct Writer {
+    unit: &'static str,
+    depth: usize,
+    text: String,
+}
+
+impl Writer {
+    /// A writer indenting each level with `unit`.
+    pub fn new(unit: &'static str) -> Self {
+        Self {
+            unit,
+            depth: 0,
+            text: String::new(),
+        }
+    }
+
+    pub fn line(&mut self, line: impl AsRef<str>) {
+        let line = line.as_ref();
+        if !line.is_empty() {
+            self.text.push_str(&self.unit.repeat(self.depth));
+            self.text.push_str(line);
+        }
+        self.text.push('\n');
+    }
+
+    pub fn indent(&mut self) {
+        self.depth += 1;
+    }
+
+    pub fn dedent(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+
+    /// Everything written so far, leaving the writer empty.
+    pub fn take(&mut self) -> String {
+        std::mem::take(&mut self.text)
+    }
+}
+
+/// The statements of a target language. The provided methods walk a body in
+/// order and hand each statement to the method for its kind, so a backend
+/// only decides how each one is spelled.
+pub trait Emitter {
+    fn writer(&mut self) -> &mut Writer;
+    fn assignment(&mut self, assignment: &Assignment);
+    fn call(&mut self, call: &FunctionCall);
+    fn return_value(&mut self, value: &Return);
+    fn if_statement(&mut self, statement: &If);
+    fn loop_statement(&mut self, statement: &Loop);
+    fn break_statement(&mut self, line: usize);
+    fn continue_statement(&mut self, line: usize);
+    fn comment(&mut self, comment: &Comment);
+
+    fn statement(&mut self, node: &Node) {
+        match node {
+            Node::Assignment(val) => self.assignment(val),
+            Node::FunctionCall(val) => self.call(val),
+            Node::Return(val) => self.return_value(val),
+            Node::If(val) => self.if_statement(val),
+            Node::Loop(val) => self.loop_statement(val),
+            Node::Break(val) => self.break_statement(val.line),
+            Node::Continue(val) => self.continue_statement(val.line),
+            Node::Comment(val) => self.comment(val),
+        }
+    }
+
+    fn statements(&mut self, body: &[Node]) {
+        for node in body {
+            self.statement(node);
+        }
+    }
+
+    /// The statements of a nested body, one level deeper.
+    fn block(&mut self, body: &[Node]) {
+        self.writer().indent();
+        self.statements(body);
+        self.writer().dedent();
+    }
+}
+
+/// The variable an assignment target such as `xs[i].x` belongs to.
+pub fn root(target: &Expression) -> Option<&str> {
+    match target {
+        Expression::Variable(name) => Some(name),
+        Expression::Index { target, .. } | Expression::Field { target, .. } => root(target),
+        _ => None,
+    }
+}
+
+/// Whether every way through `body` ends in a `return`.
+pub fn always_returns(body: &[Node]) -> bool {
+    match body
+        .iter()
+        .rev()
+        .find(|node| !matches!(node, Node::Comment(_)))
+    {
+        Some(Node::Return(_)) => true,
+        Some(Node::If(val)) => {
+            val.else_branch
+                .as_ref()
+                .is_some_and(|branch| always_returns(&branch.body))
+                && always_returns(&val.body)
+                && val
+                    .else_ifs
+                    .iter()
+                    .all(|branch| always_returns(&branch.body))
+        }
+        _ => false,
+    }
+}
+
+/// Variables of a routine that have an element or field assigned, which
+/// share their value in languages where arrays and records are references.
+pub fn modified(body: &[Node]) -> HashSet<String> {
+    let mut modified = HashSet::new();
+    walk(body, Scope::default(), &mut |node, _| {
+        if let Node::Assignment(val) = node
+            && val.var().is_none()
+            && let Some(root) = root(&val.target)
+        {
+            modified.insert(root.to_string());
+        }
+    });
+    modified
+}
+
+/// Where the variables of a routine are assigned and read, for languages
+/// that declare variables: where each declaration goes and which variables
+/// change after it.
+#[derive(Default)]
+pub struct Scan {
+    uses: HashMap<String, Vec<Use>>,
+    /// How often each variable is assigned as a whole.
+    assignments: HashMap<String, usize>,
+    /// Variables with an element or field assigned.
+    modified: HashSet<String>,
+    items: HashSet<String>,
+    /// The blocks enclosing the current statement, outermost first.
+    path: Vec<usize>,
+    blocks: usize,
+    order: usize,
+}
+
+struct Use {
+    order: usize,
+    path: Vec<usize>,
+    assigns: bool,
+}
+
+impl Scan {
+    pub fn new(body: &[Node]) -> Self {
+        let mut scan = Self::default();
+        scan.block(body);
+        scan
+    }
+
+    fn block(&mut self, body: &[Node]) {
+        self.blocks += 1;
+        self.path.push(self.blocks);
+        for node in body {
+            self.node(node);
+        }
+        self.path.pop();
+    }
+
+    fn node(&mut self, node: &Node) {
+        match node {
+            Node::Assignment(val) => {
+                self.reads(&val.value);
+                match val.var() {
+                    Some(var) => {
+                        self.record(var, true);
+                        *self.assignments.entry(var.to_string()).or_default() += 1;
+                    }
+                    None => {
+                        self.reads(&val.target);
+                        if let Some(root) = root(&val.target) {
+                            self.modified.insert(root.to_string());
+                        }
+                    }
+                }
+            }
+            Node::FunctionCall(val) => {
+                for arg in &val.args {
+                    self.reads(arg);
+                }
+            }
+            Node::Return(val) => self.reads(&val.value),
+            Node::If(val) => {
+                self.condition(&val.condition);
+                self.block(&val.body);
+                for branch in &val.else_ifs {
+                    self.condition(&branch.condition);
+                    self.block(&branch.body);
+                }
+                if let Some(branch) = &val.else_branch {
+                    self.block(&branch.body);
+                }
+            }
+            Node::Loop(val) => {
+                match &val.loop_type {
+                    LoopType::While(condition) => self.condition(condition),
+                    LoopType::For { item, list } => {
+                        self.reads(list);
+                        self.items.insert(item.clone());
+                    }
+                }
+                self.block(&val.body);
+            }
+            Node::Break(_) | Node::Continue(_) | Node::Comment(_) => {}
+        }
+    }
+
+    fn condition(&mut self, condition: &Condition) {
+        for name in condition.variables() {
+            self.record(name, false);
+        }
+    }
+
+    fn reads(&mut self, expression: &Expression) {
+        let mut names = Vec::new();
+        expression.visit(&mut |expression| {
+            if let Expression::Variable(name) = expression {
+                names.push(name.clone());
+            }
+        });
+        for name in names {
+            self.record(&name, false);
+        }
+    }
+
+    fn record(&mut self, name: &str, assigns: bool) {
+        self.order += 1;
+        self.uses.entry(name.to_string()).or_default().push(Use {
+            order: self.order,
+            path: self.path.clone(),
+            assigns,
+        });
+    }
+
+    /// Variables used before their first assignment or outside the block it
+    /// is in.
+    pub fn hoisted(&self, params: &[String]) -> HashSet<String> {
+        self.uses
+            .iter()
+            .filter(|(name, _)| !params.contains(name) && !self.items.contains(*name))
+            .filter_map(|(name, uses)| {
+                let first = uses.iter().find(|use_| use_.assigns)?;
+                uses.iter()
+                    .any(|use_| use_.order < first.order || !use_.path.starts_with(&first.path))
+                    .then(|| name.clone())
+            })
+            .collect()
+    }
+
+    /// Variables assigned again after their declaration.
+    pub fn reassigned(&self, params: &[String]) -> HashSet<String> {
+        self.assignments
+            .iter()
+            .filter(|&(name, &count)| {
+                let declared = params.contains(name) || self.items.contains(name);
+                count > 1 || (declared && count > 0)
+            })
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Variables assigned again after their declaration, or with an element
+    /// or field assigned.
+    pub fn mutable(&self, params: &[String]) -> HashSet<String> {
+        let mut mutable = self.reassigned(params);
+        mutable.extend(self.modified.iter().cloned());
+        mutable
+    }
+}
@@ -0,0 +1,896 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::models::{
+    analysis::types::{Type, Typing},
+    nodes::{
+        assignment::Assignment,
+        comment::Comment,
+        condition::{Condition, If},
+        expression::Expression,
+        function::FunctionCall,
+        loops::{Loop, LoopType},
+        node::Node,
+        operator::{ArithmeticOperator, ComparisonOperator, LogicalOperator},
+        program::Program,
+        return_node::Return,
+        test_case::TestCase,
+    },
+    transpile::{Emitter, Writer, modified},
+};
+
+const INDENT: &str = "    ";
+
+/// Python keywords, and the names the generated code uses itself, which a
+/// pseudocode name gets a trailing `_` to stay clear of.
+const RESERVED: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "class", "def", "del",
+    "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is",
+    "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+    "copy", "decimal", "math", "random", "main",
+];
+
+/// Integer division and remainder that round toward zero like pseudocode
+/// does, where Python's `//` and `%` round toward negative infinity.
+const DIV: &str = "\
+def _div(a, b):
+    if isinstance(a, int) and isinstance(b, int):
+        quotient = abs(a) // abs(b)
+        return quotient if (a < 0) == (b < 0) else -quotient
+    return a / b
+";
+const MOD: &str = "\
+def _mod(a, b):
+    if isinstance(a, int) and isinstance(b, int):
+        return a - b * _div(a, b)
+    return math.fmod(a, b)
+";
+
+/// Rounds halves away from zero like pseudocode does, where Python's `round`
+/// rounds them to even.
+const ROUND: &str = "\
+def _round(x):
+    if x < 0:
+        return -math.floor(-x + 0.5)
+    return math.floor(x + 0.5)
+";
+
+/// A value as the interpreter prints it: `true` and `false` in lower case,
+/// reals without an exponent and with their `.0`, and strings in double
+/// quotes inside arrays and records.
+const SHOW: &str = "\
+def _show(value, nested=False):
+    if isinstance(value, bool):
+        return \"true\" if value else \"false\"
+    if isinstance(value, float):
+        text = format(decimal.Decimal(repr(value)), \"f\")
+        return text if \".\" in text else text + \".0\"
+    if isinstance(value, str):
+        return f'\"{value}\"' if nested else value
+    if isinstance(value, list):
+        return \"[\" + \", \".join(_show(item, True) for item in value) + \"]\"
+    if isinstance(value, dict):
+        fields = (f\"{name}: {_show(item, True)}\" for name, item in value.items())
+        return \"{\" + \", \".join(fields) + \"}\"
+    return str(value)
+";
+
+/// The longest line black leaves alone.
+const LINE_LENGTH: usize = 88;
+
+/// How loosely the delimiters black splits a line at bind.
+const COMMA_PRIORITY: u8 = 18;
+const LOGIC_PRIORITY: u8 = 14;
+const COMPARATOR_PRIORITY: u8 = 10;
+const ARITH_PRIORITY: u8 = 5;
+const TERM_PRIORITY: u8 = 4;
+
+/// Binary operators as the generated code spaces them, with the priority
+/// black gives each. Longer spellings come first so `not in` isn't taken
+/// for `in`.
+const OPERATORS: &[(&str, u8)] = &[
+    (" not in ", COMPARATOR_PRIORITY),
+    (" and ", LOGIC_PRIORITY),
+    (" or ", LOGIC_PRIORITY),
+    (" in ", COMPARATOR_PRIORITY),
+    (" == ", COMPARATOR_PRIORITY),
+    (" != ", COMPARATOR_PRIORITY),
+    (" <= ", COMPARATOR_PRIORITY),
+    (" >= ", COMPARATOR_PRIORITY),
+    (" < ", COMPARATOR_PRIORITY),
+    (" > ", COMPARATOR_PRIORITY),
+    (" + ", ARITH_PRIORITY),
+    (" - ", ARITH_PRIORITY),
+    (" * ", TERM_PRIORITY),
+    (" / ", TERM_PRIORITY),
+    (" % ", TERM_PRIORITY),
+];
+
+/// How tightly a Python expression binds, loosest first.
+const OR: u8 = 1;
+const AND: u8 = 2;
+const NOT: u8 = 3;
+const COMPARE: u8 = 4;
+const SUM: u8 = 5;
+const PRODUCT: u8 = 6;
+const ATOM: u8 = 7;
+
+/// The program as a Python module: a function per pseudocode function,
+/// `start` as `main()` and a pytest function per `test` line.
+pub fn transpile(program: &Program) -> String {
+    let mut python = Python {
+        typing: Typing::new(program),
+        env: HashMap::new(),
+        functions: program.functions.iter().map(|f| f.name.as_str()).collect(),
+        writer: Writer::new(INDENT),
+        imports: BTreeSet::new(),
+        div: false,
+        modulo: false,
+        round: false,
+        show: false,
+        loops: 0,
+        modified: HashSet::new(),
+    };
+
+    let mut definitions = Vec::new();
+    for function in &program.functions {
+        definitions.push(python.routine(
+            &function.name,
+            &ident(&function.name),
+            &function.params,
+            &function.body,
+        ));
+    }
+    definitions.push(python.routine("start", "main", &[], &program.start.body));
+    python.env = HashMap::new();
+    for test in &program.tests {
+        definitions.push(python.test(test));
+    }
+
+    if python.show {
+        python.imports.insert("decimal");
+    }
+    let mut sections = Vec::new();
+    if !python.imports.is_empty() {
+        let imports: Vec<String> = python
+            .imports
+            .iter()
+            .map(|module| format!("import {module}\n"))
+            .collect();
+        sections.push(imports.concat());
+    }
+    if python.div || python.modulo {
+        sections.push(DIV.to_string());
+    }
+    if python.modulo {
+        sections.push(MOD.to_string());
+    }
+    if python.round {
+        sections.push(ROUND.to_string());
+    }
+    if python.show {
+        sections.push(SHOW.to_string());
+    }
+    sections.extend(definitions);
+    sections.push("if __name__ == \"__main__\":\n    main()\n".to_string());
+    // black keeps two blank lines between top-level definitions.
+    wrap_lines(&sections.join("\n\n"))
+}
+
+struct Python<'a> {
+    typing: Typing,
+    /// Types of the variables of the current routine.
+    env: HashMap<String, Type>,
+    /// Names of the program's own functions, which win over builtins.
+    functions: HashSet<&'a str>,
+    writer: Writer,
+    imports: BTreeSet<&'static str>,
+    /// Whether the `_div`, `_mod`, `_round` and `_show` helpers are needed.
+    div: bool,
+    modulo: bool,
+    round: bool,
+    show: bool,
+    /// How many loops the current statement is nested in.
+    loops: usize,
+    /// Variables of the current routine with an element or field assigned.
+    modified: HashSet<String>,
+}
+
+impl Python<'_> {
+    fn routine(&mut self, routine: &str, name: &str, params: &[String], body: &[Node]) -> String {
+        self.env = self
+            .typing
+            .variables
+            .get(routine)
+            .cloned()
+            .unwrap_or_default();
+        self.modified = modified(body);
+        self.loops = 0;
+        let names: Vec<String> = params.iter().map(|param| ident(param)).collect();
+        self.writer
+            .line(format!("def {name}({}):", names.join(", ")));
+        self.writer.indent();
+        // Arrays and records are values in pseudocode, so a function that
+        // changes an element must not change the caller's copy.
+        for (param, name) in params.iter().zip(&names) {
+            if self.modified.contains(param) {
+                self.imports.insert("copy");
+                self.writer.line(format!("{name} = copy.deepcopy({name})"));
+            }
+        }
+        self.statements(body);
+        if !params.iter().any(|param| self.modified.contains(param)) && is_empty(body) {
+            self.writer.line("pass");
+        }
+        self.writer.dedent();
+        self.writer.take()
+    }
+
+    fn test(&mut self, test: &TestCase) -> String {
+        let call = self.operand(&test.call, SUM);
+        let expected = self.operand(&test.expected, SUM);
+        format!(
+            "def test_line_{}():\n{INDENT}assert {call} == {expected}\n",
+            test.line + 1
+        )
+    }
+
+    fn ty(&mut self, expression: &Expression) -> Type {
+        self.typing.expression(expression, &self.env)
+    }
+
+    /// `expression` wrapped in parentheses when it binds looser than
+    /// `precedence`.
+    fn operand(&mut self, expression: &Expression, precedence: u8) -> String {
+        let (text, binds) = self.expression(expression);
+        parenthesize(text, binds, precedence)
+    }
+
+    fn expression(&mut self, expression: &Expression) -> (String, u8) {
+        match expression {
+            Expression::Variable(name) => (ident(name), ATOM),
+            Expression::Literal(text) => (literal(text), ATOM),
+            Expression::BinaryOp {
+                left,
+                operator,
+                right,
+            } => self.arithmetic(left, operator, right),
+            Expression::FunctionCall { name, args } => self.function_call(name, args),
+            Expression::Not(inner) => match &**inner {
+                Expression::FunctionCall { name, args }
+                    if name == "contains"
+                        && args.len() == 2
+                        && !self.functions.contains(name.as_str()) =>
+                {
+                    self.membership(&args[0], &args[1], "not in")
+                }
+                _ => (format!("not {}", self.operand(inner, NOT)), NOT),
+            },
+            Expression::Array(items) => (format!("[{}]", self.list(items)), ATOM),
+            Expression::Record(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, value)| format!("\"{name}\": {}", self.operand(value, OR)))
+                    .collect();
+                (format!("{{{}}}", fields.join(", ")), ATOM)
+            }
+            Expression::Index { target, index } => {
+                let target = self.operand(target, ATOM);
+                (format!("{target}[{}]", self.operand(index, OR)), ATOM)
+            }
+            Expression::Field { target, name } => {
+                (format!("{}[\"{name}\"]", self.operand(target, ATOM)), ATOM)
+            }
+        }
+    }
+
+    fn arithmetic(
+        &mut self,
+        left: &Expression,
+        operator: &ArithmeticOperator,
+        right: &Expression,
+    ) -> (String, u8) {
+        let real = is_real(left) || is_real(right);
+        let symbol = match operator {
+            ArithmeticOperator::Addition => "+",
+            ArithmeticOperator::Subtraction => "-",
+            ArithmeticOperator::Multiplication => "*",
+            ArithmeticOperator::Division if real => "/",
+            ArithmeticOperator::Division | ArithmeticOperator::Modulo => {
+                let helper = if matches!(operator, ArithmeticOperator::Division) {
+                    self.div = true;
+                    "_div"
+                } else if real {
+                    self.imports.insert("math");
+                    "math.fmod"
+                } else {
+                    self.imports.insert("math");
+                    self.modulo = true;
+                    "_mod"
+                };
+                let args = format!("{}, {}", self.operand(left, OR), self.operand(right, OR));
+                return (format!("{helper}({args})"), ATOM);
+            }
+        };
+        let precedence = match operator {
+            ArithmeticOperator::Addition | ArithmeticOperator::Subtraction => SUM,
+            _ => PRODUCT,
+        };
+        let left = self.operand(left, precedence);
+        let right = self.operand(right, precedence + 1);
+        (format!("{left} {symbol} {right}"), precedence)
+    }
+
+    /// A call to one of the program's functions, or the Python spelling of
+    /// a builtin.
+    fn function_call(&mut self, name: &str, args: &[Expression]) -> (String, u8) {
+        if self.functions.contains(name) {
+            return (format!("{}({})", ident(name), self.list(args)), ATOM);
+        }
+        let call = |function: &str, args: String| (format!("{function}({args})"), ATOM);
+        match (name, args) {
+            ("length", _) => call("len", self.list(args)),
+            ("sqrt" | "floor" | "ceil" | "pow", _) => {
+                self.imports.insert("math");
+                call(&format!("math.{name}"), self.list(args))
+            }
+            ("random", _) => {
+                self.imports.insert("random");
+                call("random.randrange", self.list(args))
+            }
+            ("round", [value]) if self.ty(value) == Type::Int => self.expression(value),
+            ("round", _) => {
+                self.imports.insert("math");
+                self.round = true;
+                call("_round", self.list(args))
+            }
+            // Python keeps whichever argument wins, where pseudocode turns
+            // an int into a real when the other one is.
+            ("min" | "max", [left, right])
+                if matches!(
+                    (self.ty(left), self.ty(right)),
+                    (Type::Int, Type::Real) | (Type::Real, Type::Int)
+                ) =>
+            {
+                call("float", format!("{name}({})", self.list(args)))
+            }
+            ("range", _) => call("list", format!("range({})", self.list(args))),
+            ("append", [items, item]) => {
+                let items = self.operand(items, SUM);
+                (format!("{items} + [{}]", self.operand(item, OR)), SUM)
+            }
+            ("contains", [haystack, needle]) => self.membership(haystack, needle, "in"),
+            ("substring", [text, from, to]) => {
+                let text = self.operand(text, ATOM);
+                // black spaces the colon of a slice unless both ends are
+                // plain names or numbers.
+                let colon = if is_simple(from) && is_simple(to) {
+                    ":"
+                } else {
+                    " : "
+                };
+                let from = self.operand(from, OR);
+                (
+                    format!("{text}[{from}{colon}{}]", self.operand(to, OR)),
+                    ATOM,
+                )
+            }
+            ("to_upper" | "to_lower", [text]) => {
+                let method = if name == "to_upper" { "upper" } else { "lower" };
+                (format!("{}.{method}()", self.operand(text, ATOM)), ATOM)
+            }
+            ("to_string", [value]) => {
+                self.show = true;
+                call("_show", self.operand(value, OR))
+            }
+            ("print", _) => {
+                let args: Vec<String> = args.iter().map(|arg| self.shown(arg)).collect();
+                call("print", args.join(", "))
+            }
+            ("to_int", _) => call("int", self.list(args)),
+            ("to_real", _) => call("float", self.list(args)),
+            _ => call(name, self.list(args)),
+        }
+    }
+
+    /// An argument of `print`, passed through `_show` unless Python already
+    /// prints it the way the interpreter does.
+    fn shown(&mut self, value: &Expression) -> String {
+        match value {
+            Expression::Literal(text)
+                if matches!(Type::of_literal(text), Some(Type::Int | Type::String)) =>
+            {
+                literal(text)
+            }
+            // `to_string` already goes through `_show`.
+            Expression::FunctionCall { name, .. }
+                if name == "to_string" && !self.functions.contains(name.as_str()) =>
+            {
+                self.operand(value, OR)
+            }
+            _ => {
+                self.show = true;
+                format!("_show({})", self.operand(value, OR))
+            }
+        }
+    }
+
+    /// `needle in haystack`, or with `not in` as `operator`.
+    fn membership(
+        &mut self,
+        haystack: &Expression,
+        needle: &Expression,
+        operator: &str,
+    ) -> (String, u8) {
+        let needle = self.operand(needle, SUM);
+        let haystack = self.operand(haystack, SUM);
+        (format!("{needle} {operator} {haystack}"), COMPARE)
+    }
+
+    fn list(&mut self, items: &[Expression]) -> String {
+        let items: Vec<String> = items.iter().map(|item| self.operand(item, OR)).collect();
+        items.join(", ")
+    }
+
+    fn condition(&mut self, condition: &Condition) -> (String, u8) {
+        match condition {
+            Condition::ComparisonOperator {
+                left,
+                operator,
+                right,
+            } => {
+                let symbol = match **operator {
+                    ComparisonOperator::Eq => "==",
+                    ComparisonOperator::Neq => "!=",
+                    ComparisonOperator::Gt => ">",
+                    ComparisonOperator::Lt => "<",
+                    ComparisonOperator::Gte => ">=",
+                    ComparisonOperator::Lte => "<=",
+                };
+                let left = self.operand(left, SUM);
+                let right = self.operand(right, SUM);
+                (format!("{left} {symbol} {right}"), COMPARE)
+            }
+            Condition::LogicalOperator {
+                left,
+                operator,
+                right,
+            } => {
+                let (symbol, precedence) = match **operator {
+                    LogicalOperator::And => ("and", AND),
+                    LogicalOperator::Or => ("or", OR),
+                };
+                let (left, binds) = self.condition(left);
+                let left = parenthesize(left, binds, precedence);
+                let (right, binds) = self.condition(right);
+                let right = parenthesize(right, binds, precedence + 1);
+                (format!("{left} {symbol} {right}"), precedence)
+            }
+            Condition::Value(value) => self.expression(value),
+        }
+    }
+
+    fn condition_text(&mut self, condition: &Condition) -> String {
+        self.condition(condition).0
+    }
+}
+
+impl Emitter for Python<'_> {
+    fn writer(&mut self) -> &mut Writer {
+        &mut self.writer
+    }
+
+    fn assignment(&mut self, assignment: &Assignment) {
+        let target = self.operand(&assignment.target, OR);
+        let mut value = self.operand(&assignment.value, OR);
+        // Assigning an array or record to a variable shares it in Python,
+        // which shows once an element is changed through either name.
+        let shared = match (&assignment.target, &assignment.value) {
+            (Expression::Variable(target), Expression::Variable(value)) => {
+                self.modified.contains(target) || self.modified.contains(value)
+            }
+            (Expression::Variable(target), Expression::Index { .. } | Expression::Field { .. }) => {
+                self.modified.contains(target)
+            }
+            _ => false,
+        };
+        if shared {
+            self.imports.insert("copy");
+            value = format!("copy.deepcopy({value})");
+        }
+        self.writer.line(format!("{target} = {value}"));
+    }
+
+    fn call(&mut self, call: &FunctionCall) {
+        let (text, _) = self.function_call(&call.name, &call.args);
+        self.writer.line(text);
+    }
+
+    fn return_value(&mut self, value: &Return) {
+        let value = self.operand(&value.value, OR);
+        self.writer.line(format!("return {value}"));
+    }
+
+    fn if_statement(&mut self, statement: &If) {
+        let condition = self.condition_text(&statement.condition);
+        self.writer.line(format!("if {condition}:"));
+        self.block(&statement.body);
+        for branch in &statement.else_ifs {
+            let condition = self.condition_text(&branch.condition);
+            self.writer.line(format!("elif {condition}:"));
+            self.block(&branch.body);
+        }
+        if let Some(branch) = &statement.else_branch {
+            self.writer.line("else:");
+            self.block(&branch.body);
+        }
+    }
+
+    fn loop_statement(&mut self, statement: &Loop) {
+        let header = match &statement.loop_type {
+            LoopType::While(condition) => format!("while {}:", self.condition_text(condition)),
+            LoopType::For { item, list } => {
+                let list = match list {
+                    // Python loops over a range without building the list.
+                    Expression::FunctionCall { name, args }
+                        if name == "range" && !self.functions.contains(name.as_str()) =>
+                    {
+                        format!("range({})", self.list(args))
+                    }
+                    _ => self.operand(list, OR),
+                };
+                format!("for {} in {list}:", ident(item))
+            }
+        };
+        self.writer.line(header);
+        self.loops += 1;
+        self.block(&statement.body);
+        self.loops -= 1;
+    }
+
+    // Outside a loop `break` and `continue` leave the routine.
+    fn break_statement(&mut self, _line: usize) {
+        let keyword = if self.loops > 0 { "break" } else { "return" };
+        self.writer.line(keyword);
+    }
+
+    fn continue_statement(&mut self, _line: usize) {
+        let keyword = if self.loops > 0 { "continue" } else { "return" };
+        self.writer.line(keyword);
+    }
+
+    fn comment(&mut self, comment: &Comment) {
+        self.writer.line(format!("# {}", comment.value).trim_end());
+    }
+
+    /// Python needs a statement in every block, and comments do not count.
+    fn block(&mut self, body: &[Node]) {
+        self.writer.indent();
+        self.statements(body);
+        if is_empty(body) {
+            self.writer.line("pass");
+        }
+        self.writer.dedent();
+    }
+}
+
+fn is_empty(body: &[Node]) -> bool {
+    body.iter().all(|node| matches!(node, Node::Comment(_)))
+}
+
+fn parenthesize(text: String, binds: u8, precedence: u8) -> String {
+    if binds < precedence {
+        format!("({text})")
+    } else {
+        text
+    }
+}
+
+fn ident(name: &str) -> String {
+    if RESERVED.contains(&name) {
+        format!("{name}_")
+    } else {
+        name.to_string()
+    }
+}
+
+/// A pseudocode literal in Python syntax.
+fn literal(text: &str) -> String {
+    if let Some(inner) = text.strip_prefix('"') {
+        return format!("\"{}", inner.replace('\\', "\\\\"));
+    }
+    match text {
+        "true" => return "True".to_string(),
+        "false" => return "False".to_string(),
+        _ => {}
+    }
+    if let Ok(value) = text.parse::<i64>() {
+        return value.to_string();
+    }
+    match text.parse::<f64>() {
+        Ok(value) if value.is_finite() => format!("{value:?}"),
+        Ok(value) => format!("float(\"{value}\")"),
+        Err(_) => text.to_string(),
+    }
+}
+
+fn is_real(expression: &Expression) -> bool {
+    matches!(expression, Expression::Literal(text) if Type::of_literal(text) == Some(Type::Real))
+}
+
+fn is_simple(expression: &Expression) -> bool {
+    matches!(expression, Expression::Variable(_) | Expression::Literal(_))
+}
+
+/// `code` with every line longer than black allows split the way black
+/// splits it.
+fn wrap_lines(code: &str) -> String {
+    let mut wrapped = String::new();
+    for line in code.lines() {
+        let content = line.trim_start();
+        for line in wrap(line.len() - content.len(), content) {
+            wrapped.push_str(&line);
+            wrapped.push('\n');
+        }
+    }
+    wrapped
+}
+
+/// A statement at `indent`, split when it is too long. The value of a
+/// `return`, `assert`, assignment or the head of a compound statement goes
+/// in parentheses of its own unless a bracket in it reads better as the
+/// place to split, as black decides with its invisible parentheses.
+fn wrap(indent: usize, line: &str) -> Vec<String> {
+    if fits(indent, line) || line.starts_with('#') {
+        return vec![padded(indent, line)];
+    }
+    if let Some((prefix, body, suffix)) = statement_parts(line)
+        && !omits_parentheses(indent, body)
+    {
+        let mut lines = vec![padded(indent, &format!("{prefix}("))];
+        lines.extend(split_body(indent + INDENT.len(), body));
+        lines.push(padded(indent, &format!("){suffix}")));
+        return lines;
+    }
+    right_hand_split(indent, line)
+}
+
+/// Splits at the last bracket pair whose opening line fits, putting what
+/// the brackets hold on lines of its own, one level deeper.
+fn right_hand_split(indent: usize, line: &str) -> Vec<String> {
+    let pairs: Vec<(usize, usize)> = bracket_pairs(line)
+        .into_iter()
+        .filter(|(open, close)| close > &(open + 1))
+        .collect();
+    let Some(&(open, close)) = pairs
+        .iter()
+        .rev()
+        .find(|(open, _)| fits(indent, &line[..=*open]))
+        .or(pairs.last())
+    else {
+        return vec![padded(indent, line)];
+    };
+    let mut body = line[open + 1..close].to_string();
+    // A lone parameter is exploded with a trailing comma.
+    if line.starts_with("def ") && delimiters(&body).0 != COMMA_PRIORITY {
+        body.push(',');
+    }
+    let mut lines = vec![padded(indent, &line[..=open])];
+    lines.extend(split_body(indent + INDENT.len(), &body));
+    lines.push(padded(indent, &line[close..]));
+    lines
+}
+
+/// The contents of brackets: on one line when they fit, otherwise one item
+/// per line with a trailing comma, or one operand per line led by its
+/// operator.
+fn split_body(indent: usize, body: &str) -> Vec<String> {
+    if fits(indent, body) {
+        return vec![padded(indent, body)];
+    }
+    let (priority, ranges) = delimiters(body);
+    if ranges.is_empty() {
+        return right_hand_split(indent, body);
+    }
+    let mut lines = Vec::new();
+    if priority == COMMA_PRIORITY {
+        let mut from = 0;
+        for (start, end) in ranges.into_iter().chain([(body.len(), body.len())]) {
+            let item = body[from..start].trim();
+            lines.extend(split_body(indent, &format!("{item},")));
+            from = end;
+        }
+    } else {
+        let mut from = 0;
+        for (start, _) in ranges.into_iter().chain([(body.len(), body.len())]) {
+            lines.extend(split_body(indent, body[from..start].trim()));
+            from = start;
+        }
+    }
+    lines
+}
+
+/// The keyword or target before the value of a statement, the value and
+/// what follows it.
+fn statement_parts(line: &str) -> Option<(&str, &str, &str)> {
+    for keyword in ["if ", "elif ", "while "] {
+        if let Some(rest) = line.strip_prefix(keyword) {
+            return Some((keyword, rest.strip_suffix(':')?, ":"));
+        }
+    }
+    if line.starts_with("for ") {
+        let split = line.find(" in ")? + " in ".len();
+        return Some((&line[..split], line[split..].strip_suffix(':')?, ":"));
+    }
+    for keyword in ["return ", "assert "] {
+        if let Some(rest) = line.strip_prefix(keyword) {
+            return Some((keyword, rest, ""));
+        }
+    }
+    let split = scan(line)
+        .into_iter()
+        .find(|&(index, depth)| depth == 0 && line[index..].starts_with(" = "))?
+        .0
+        + " = ".len();
+    Some((&line[..split], &line[split..], ""))
+}
+
+/// Whether black leaves out the parentheses around a statement's value:
+/// when it has no delimiter to split at, or a single one and ends in a
+/// call or literal that opens early enough on the line.
+fn omits_parentheses(indent: usize, body: &str) -> bool {
+    let (_, ranges) = delimiters(body);
+    match ranges.len() {
+        0 => true,
+        1 => {
+            let Some(&(open, close)) = bracket_pairs(body).last() else {
+                return false;
+            };
+            let subscript = body.as_bytes()[open] == b'['
+                && body[..open]
+                    .chars()
+                    .next_back()
+                    .is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | ')' | ']'));
+            close == body.len() - 1
+                && close > open + 1
+                && !subscript
+                && (body[..open].contains(['(', '[', '{']) || fits(indent, &body[..=open]))
+        }
+        _ => false,
+    }
+}
+
+/// The byte ranges of the delimiters outside brackets and strings that
+/// bind loosest, with their priority. A comma ending the text doesn't
+/// count.
+fn delimiters(text: &str) -> (u8, Vec<(usize, usize)>) {
+    let mut found: Vec<(u8, usize, usize)> = Vec::new();
+    let mut skip_to = 0;
+    for (index, depth) in scan(text) {
+        if depth > 0 || index < skip_to {
+            continue;
+        }
+        let rest = &text[index..];
+        if rest.starts_with(',') && index + 1 < text.len() {
+            found.push((COMMA_PRIORITY, index, index + 1));
+        } else if let Some((operator, priority)) = OPERATORS
+            .iter()
+            .find(|(operator, _)| rest.starts_with(operator))
+        {
+            found.push((*priority, index + 1, index + operator.len() - 1));
+            skip_to = index + operator.len() - 1;
+        }
+    }
+    let priority = found
+        .iter()
+        .map(|(priority, ..)| *priority)
+        .max()
+        .unwrap_or(0);
+    let ranges = found
+        .into_iter()
+        .filter(|(found, ..)| *found == priority)
+        .map(|(_, start, end)| (start, end))
+        .collect();
+    (priority, ranges)
+}
+
+/// The byte offsets of the brackets outside strings that aren't inside
+/// other brackets, each opening one with its closing one.
+fn bracket_pairs(text: &str) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    let mut open = 0;
+    for (index, depth) in scan(text) {
+        match text.as_bytes()[index] {
+            b'(' | b'[' | b'{' if depth == 1 => open = index,
+            b')' | b']' | b'}' if depth == 0 => pairs.push((open, index)),
+            _ => {}
+        }
+    }
+    pairs
+}
+
+/// The offset of every character outside string literals, with how many
+/// brackets are open at it. An opening bracket counts itself, a closing
+/// one doesn't.
+fn scan(text: &str) -> Vec<(usize, usize)> {
+    let mut characters = Vec::new();
+    let mut depth: usize = 0;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for (index, character) in text.char_indices() {
+        if let Some(open) = quote {
+            if escaped {
+                escaped = false;
+            } else if character == '\\' {
+                escaped = true;
+            } else if character == open {
+                quote = None;
+            }
+            continue;
+        }
+        match character {
+            '"' | '\'' => {
+                quote = Some(character);
+                continue;
+            }
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        characters.push((index, depth));
+    }
+    characters
+}
+
+fn fits(indent: usize, text: &str) -> bool {
+    indent + text.chars().count() <= LINE_LENGTH
+}
+
+fn padded(indent: usize, text: &str) -> String {
+    format!("{}{text}", " ".repeat(indent))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+    use crate::models::interpreter::Interpreter;
+
+    /// The lines the interpreter prints for `source`, and the ones its Python
+    /// translation prints, or `None` without a `python3` to run it.
+    fn outputs(source: &str) -> Option<(Vec<String>, Vec<String>)> {
+        let program = Program::new(source).expect("test programs parse");
+        let mut interpreter = Interpreter::with_observer(&program, ());
+        interpreter.run().expect("test programs run");
+        let python = Command::new("python3")
+            .arg("-c")
+            .arg(transpile(&program))
+            .output()
+            .ok()?;
+        assert!(
+            python.status.success(),
+            "{}",
+            String::from_utf8_lossy(&python.stderr)
+        );
+        let printed = String::from_utf8(python.stdout).expect("python prints utf-8");
+        Some((
+            interpreter.output,
+            printed.lines().map(str::to_string).collect(),
+        ))
+    }
+
+    #[test]
+    fn builtins_print_like_the_interpreter() {
+        let source = "
+start
+    let half = 2.5
+    print(round(2.5), round(3.5), round(-2.5), round(half), round(7))
+    print(min(2, 3.5), max(2, 3.5), min(4.5, 1), max(2, 3), min(1.5, 2.5))
+    print(to_string(true), [\"a\", \"b\"], {x: 1.0, y: false}, 1.0 / 4.0)
+";
+        let Some((interpreted, translated)) = outputs(source) else {
+            return;
+        };
+        assert_eq!(interpreted, translated);
+    }
+}
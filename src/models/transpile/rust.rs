@@ -0,0 +1,944 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{
+    analysis::types::{Type, Typing},
+    nodes::{
+        assignment::Assignment,
+        comment::Comment,
+        condition::{Condition, If},
+        expression::Expression,
+        function::{Function, FunctionCall},
+        loops::{Loop, LoopType},
+        node::Node,
+        operator::{ArithmeticOperator, ComparisonOperator, LogicalOperator},
+        program::Program,
+        return_node::Return,
+        test_case::TestCase,
+    },
+    transpile::{Emitter, Scan, Signature, Writer, always_returns, signatures},
+};
+
+const INDENT: &str = "    ";
+
+/// Rust keywords, and `main`, which a pseudocode name gets a trailing `_` to
+/// stay clear of.
+const RESERVED: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield", "main",
+];
+
+/// How tightly a Rust expression binds, loosest first.
+const OR: u8 = 1;
+const AND: u8 = 2;
+const COMPARE: u8 = 3;
+const SUM: u8 = 4;
+const PRODUCT: u8 = 5;
+const CAST: u8 = 6;
+const UNARY: u8 = 7;
+const ATOM: u8 = 8;
+
+/// Rust text and how tightly it binds, or why the construct has no
+/// translation.
+type Translation = Result<(String, u8), String>;
+
+/// The program as a Rust crate root: a struct per record type, a function
+/// per pseudocode function, `start` as `main` and a `#[test]` per `test`
+/// line. Types come from inference, so a function is only spelled out when
+/// something calls it with arguments of known types; anything else becomes
+/// `todo!()` behind a comment with the source line.
+pub fn transpile(program: &Program) -> String {
+    let mut rust = Rust::new(program);
+    let mut items = Vec::new();
+    for function in &program.functions {
+        items.push(rust.function(function));
+    }
+    items.push(rust.main(&program.start.body));
+    if !program.tests.is_empty() {
+        items.push(rust.tests(&program.tests));
+    }
+    let mut structs = rust.structs();
+    structs.extend(items);
+    structs.join("\n")
+}
+
+struct Rust<'a> {
+    typing: Typing,
+    writer: Writer,
+    /// Names of the program's own functions, which win over builtins.
+    functions: HashSet<&'a str>,
+    /// Signatures of the functions whose types are all known.
+    signatures: HashMap<&'a str, Signature>,
+    /// Fields of the record type behind each generated struct, in order of
+    /// first use; the struct is named after its position.
+    records: Vec<Vec<(String, Type)>>,
+    /// Types of the variables of the current routine.
+    env: HashMap<String, Type>,
+    /// Return type of the current routine.
+    returns: Option<Type>,
+    /// Variables that need `let mut`.
+    mutable: HashSet<String>,
+    /// Variables of the current routine declared before its first statement.
+    hoisted: Vec<String>,
+    /// Variables declared so far in the current routine.
+    declared: HashSet<String>,
+    /// How many loops the current statement is nested in.
+    loops: usize,
+}
+
+impl<'a> Rust<'a> {
+    fn new(program: &'a Program) -> Self {
+        let typing = Typing::new(program);
+        let signatures = signatures(program, &typing);
+        Self {
+            typing,
+            writer: Writer::new(INDENT),
+            functions: program.functions.iter().map(|f| f.name.as_str()).collect(),
+            signatures,
+            records: Vec::new(),
+            env: HashMap::new(),
+            returns: None,
+            mutable: HashSet::new(),
+            hoisted: Vec::new(),
+            declared: HashSet::new(),
+            loops: 0,
+        }
+    }
+
+    fn function(&mut self, function: &Function) -> String {
+        let name = ident(&function.name);
+        let Some((params, returns)) = self.signatures.get(function.name.as_str()).cloned() else {
+            return format!(
+                "// line {}: the types of '{}' are unknown, since nothing calls it with \
+                 arguments of known types\nfn {name}() {{\n{INDENT}todo!()\n}}\n",
+                function.line + 1,
+                function.name
+            );
+        };
+        self.begin(&function.name, &function.params, &function.body, returns);
+        let params: Vec<String> = function
+            .params
+            .iter()
+            .zip(&params)
+            .map(|(param, param_type)| {
+                let mutable = if self.mutable.contains(param) {
+                    "mut "
+                } else {
+                    ""
+                };
+                format!("{mutable}{}: {}", ident(param), self.rust_type(param_type))
+            })
+            .collect();
+        let returns = match self.returns.clone() {
+            Some(returns) => format!(" -> {}", self.rust_type(&returns)),
+            None => String::new(),
+        };
+        self.writer
+            .line(format!("fn {name}({}){returns} {{", params.join(", ")));
+        self.body(&function.body);
+        self.writer.line("}");
+        self.writer.take()
+    }
+
+    fn main(&mut self, body: &[Node]) -> String {
+        self.begin("start", &[], body, None);
+        self.writer.line("fn main() {");
+        self.body(body);
+        self.writer.line("}");
+        self.writer.take()
+    }
+
+    fn tests(&mut self, tests: &[TestCase]) -> String {
+        self.env = HashMap::new();
+        self.writer.line("#[cfg(test)]");
+        self.writer.line("mod tests {");
+        self.writer.indent();
+        self.writer.line("use super::*;");
+        for test in tests {
+            self.writer.line("");
+            self.writer.line("#[test]");
+            self.writer.line(format!("fn line_{}() {{", test.line + 1));
+            self.writer.indent();
+            let expected = self.ty(&test.call);
+            match self
+                .operand(&test.call, OR)
+                .and_then(|call| Ok((call, self.value(&test.expected, &expected)?.0)))
+            {
+                Ok((call, expected)) => {
+                    self.writer.line(format!("assert_eq!({call}, {expected});"))
+                }
+                Err(reason) => self.todo(test.line, &reason),
+            }
+            self.writer.dedent();
+            self.writer.line("}");
+        }
+        self.writer.dedent();
+        self.writer.line("}");
+        self.writer.take()
+    }
+
+    /// The struct of every record type used, including the ones nested in
+    /// other structs.
+    fn structs(&mut self) -> Vec<String> {
+        let mut structs = Vec::new();
+        let mut index = 0;
+        while let Some(fields) = self.records.get(index).cloned() {
+            let mut text = format!(
+                "#[derive(Clone, Debug, Default, PartialEq)]\nstruct Record{} {{\n",
+                index + 1
+            );
+            for (name, field) in &fields {
+                text.push_str(&format!(
+                    "{INDENT}{}: {},\n",
+                    ident(name),
+                    self.rust_type(field)
+                ));
+            }
+            text.push_str("}\n");
+            structs.push(text);
+            index += 1;
+        }
+        structs
+    }
+
+    fn begin(&mut self, routine: &str, params: &[String], body: &[Node], returns: Option<Type>) {
+        self.env = self
+            .typing
+            .variables
+            .get(routine)
+            .cloned()
+            .unwrap_or_default();
+        self.returns = returns;
+        self.loops = 0;
+        self.declared = params.iter().cloned().collect();
+
+        let scan = Scan::new(body);
+        self.mutable = scan.mutable(params);
+        self.hoisted = scan.hoisted(params).into_iter().collect();
+        self.hoisted.sort();
+        for name in &self.hoisted {
+            self.mutable.insert(name.clone());
+        }
+    }
+
+    /// The statements of a routine, with a final `return` written as the
+    /// value of the body.
+    fn body(&mut self, body: &[Node]) {
+        self.writer.indent();
+        // A variable used outside the block it is first assigned in is
+        // declared up front, since a `let` would only reach the end of the
+        // block.
+        for name in std::mem::take(&mut self.hoisted) {
+            let Some(var_type) = self.env.get(&name).cloned().filter(|t| !t.is_partial()) else {
+                continue;
+            };
+            let rust_type = self.rust_type(&var_type);
+            self.writer.line(format!(
+                "let mut {}: {rust_type} = Default::default();",
+                ident(&name)
+            ));
+            self.declared.insert(name);
+        }
+        let (last, rest) = match (body.split_last(), &self.returns) {
+            (Some((Node::Return(val), rest)), Some(_)) => (Some(val), rest),
+            _ => (None, body),
+        };
+        self.statements(rest);
+        match (last, self.returns.clone()) {
+            (Some(val), Some(returns)) => match self.returned(&val.value, &returns) {
+                Ok((value, _)) => self.writer.line(value),
+                Err(reason) => self.todo(val.line, &reason),
+            },
+            // rustc cannot tell that e.g. a `while` always returns.
+            (None, Some(_)) if !always_returns(body) => self.writer.line("unreachable!()"),
+            _ => {}
+        }
+        self.writer.dedent();
+    }
+
+    /// A statement that has no translation: a comment with the reason and
+    /// the source line, then `todo!()`.
+    fn todo(&mut self, line: usize, reason: &str) {
+        self.writer.line(format!("// line {}: {reason}", line + 1));
+        self.writer.line("todo!();");
+    }
+
+    fn ty(&mut self, expression: &Expression) -> Type {
+        self.typing.expression(expression, &self.env)
+    }
+
+    fn rust_type(&mut self, value_type: &Type) -> String {
+        match value_type {
+            Type::Int => "i64".to_string(),
+            Type::Real => "f64".to_string(),
+            Type::String => "String".to_string(),
+            Type::Bool => "bool".to_string(),
+            Type::Array(element) => format!("Vec<{}>", self.rust_type(element)),
+            Type::Record(fields) => {
+                let index = match self.records.iter().position(|known| known == fields) {
+                    Some(index) => index,
+                    None => {
+                        self.records.push(fields.clone());
+                        self.records.len() - 1
+                    }
+                };
+                format!("Record{}", index + 1)
+            }
+            Type::Unknown => "_".to_string(),
+        }
+    }
+
+    fn operand(&mut self, expression: &Expression, precedence: u8) -> Result<String, String> {
+        let (text, binds) = self.expression(expression)?;
+        Ok(parenthesize(text, binds, precedence))
+    }
+
+    /// `expression` where its value is read without being taken, such as
+    /// an operand of a comparison or a method call.
+    fn expression(&mut self, expression: &Expression) -> Translation {
+        let found = self.ty(expression);
+        if found.is_partial() {
+            return Err(format!("the type of '{expression}' is unknown"));
+        }
+        match expression {
+            Expression::Variable(name) => Ok((ident(name), ATOM)),
+            Expression::Literal(text) => Ok(literal(text)),
+            Expression::BinaryOp {
+                left,
+                operator,
+                right,
+            } => self.arithmetic(left, operator, right, &found),
+            Expression::FunctionCall { name, args } => self.function_call(name, args),
+            Expression::Not(inner) => Ok((format!("!{}", self.operand(inner, UNARY)?), UNARY)),
+            Expression::Array(_) | Expression::Record(_) => self.value(expression, &found),
+            Expression::Index { target, index } => {
+                let index = self.index(index)?;
+                let target_type = self.ty(target);
+                let target = self.operand(target, ATOM)?;
+                if target_type == Type::String {
+                    return Ok((
+                        format!("{target}.chars().nth({index}).unwrap().to_string()"),
+                        ATOM,
+                    ));
+                }
+                Ok((format!("{target}[{index}]"), ATOM))
+            }
+            Expression::Field { target, name } => Ok((
+                format!("{}.{}", self.operand(target, ATOM)?, ident(name)),
+                ATOM,
+            )),
+        }
+    }
+
+    /// `expression` as an owned value of type `expected`: cloned when it
+    /// names an existing array, string or record, and widened when an int
+    /// goes where a real is expected.
+    fn value(&mut self, expression: &Expression, expected: &Type) -> Translation {
+        let found = self.ty(expression);
+        match (expression, expected) {
+            (Expression::Literal(text), Type::Real) if found == Type::Int => {
+                let (text, binds) = literal(text);
+                return Ok((format!("{text}.0"), binds));
+            }
+            (Expression::Literal(text), Type::String) if found == Type::String => {
+                return Ok((format!("String::from({})", literal(text).0), ATOM));
+            }
+            (Expression::Array(items), Type::Array(element)) => {
+                if items.is_empty() {
+                    return Ok(("Vec::new()".to_string(), ATOM));
+                }
+                let items = items
+                    .iter()
+                    .map(|item| Ok(self.value(item, element)?.0))
+                    .collect::<Result<Vec<_>, String>>()?;
+                return Ok((format!("vec![{}]", items.join(", ")), ATOM));
+            }
+            (Expression::Record(fields), Type::Record(types))
+                if fields.len() == types.len()
+                    && fields.iter().zip(types).all(|((a, _), (b, _))| a == b) =>
+            {
+                let name = self.rust_type(expected);
+                let fields = fields
+                    .iter()
+                    .zip(types)
+                    .map(|((field, value), (_, field_type))| {
+                        Ok(format!(
+                            "{}: {}",
+                            ident(field),
+                            self.value(value, field_type)?.0
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+                return Ok((format!("{name} {{ {} }}", fields.join(", ")), ATOM));
+            }
+            _ => {}
+        }
+
+        let (text, binds) = self.expression(expression)?;
+        if found == *expected {
+            let place = match expression {
+                Expression::Variable(_) | Expression::Field { .. } => true,
+                Expression::Index { target, .. } => self.ty(target) != Type::String,
+                _ => false,
+            };
+            if place && !matches!(found, Type::Int | Type::Real | Type::Bool) {
+                return Ok((format!("{}.clone()", parenthesize(text, binds, ATOM)), ATOM));
+            }
+            return Ok((text, binds));
+        }
+        if *expected == Type::Real && found == Type::Int {
+            return Ok((format!("{} as f64", parenthesize(text, binds, UNARY)), CAST));
+        }
+        Err(format!(
+            "'{expression}' is {found} where {expected} is expected"
+        ))
+    }
+
+    /// A returned value. A variable can be moved out since the routine ends.
+    fn returned(&mut self, value: &Expression, returns: &Type) -> Translation {
+        match value {
+            Expression::Variable(_) if self.ty(value) == *returns => self.expression(value),
+            _ => self.value(value, returns),
+        }
+    }
+
+    /// An array index as `usize`.
+    fn index(&mut self, index: &Expression) -> Result<String, String> {
+        if let Expression::Literal(text) = index
+            && text.parse::<usize>().is_ok()
+        {
+            return Ok(text.clone());
+        }
+        let (text, binds) = self.value(index, &Type::Int)?;
+        Ok(format!("{} as usize", parenthesize(text, binds, UNARY)))
+    }
+
+    fn arithmetic(
+        &mut self,
+        left: &Expression,
+        operator: &ArithmeticOperator,
+        right: &Expression,
+        found: &Type,
+    ) -> Translation {
+        match (operator, found) {
+            (ArithmeticOperator::Addition, Type::String) => {
+                let mut parts = Vec::new();
+                self.concatenated(left, &mut parts);
+                self.concatenated(right, &mut parts);
+                let mut format = String::new();
+                let mut values = String::new();
+                for part in parts {
+                    match part {
+                        Expression::Literal(text) if text.starts_with('"') => {
+                            format.push_str(&format_text(text))
+                        }
+                        _ => {
+                            format.push_str("{}");
+                            values.push_str(&format!(", {}", self.operand(part, OR)?));
+                        }
+                    }
+                }
+                Ok((format!("format!(\"{format}\"{values})"), ATOM))
+            }
+            (ArithmeticOperator::Addition, Type::Array(_)) => {
+                let left = self.value(left, found)?.0;
+                let right = self.value(right, found)?.0;
+                Ok((format!("[{left}, {right}].concat()"), ATOM))
+            }
+            (_, Type::Int | Type::Real) => {
+                let (symbol, precedence) = match operator {
+                    ArithmeticOperator::Addition => ("+", SUM),
+                    ArithmeticOperator::Subtraction => ("-", SUM),
+                    ArithmeticOperator::Multiplication => ("*", PRODUCT),
+                    ArithmeticOperator::Division => ("/", PRODUCT),
+                    ArithmeticOperator::Modulo => ("%", PRODUCT),
+                };
+                let (text, binds) = self.value(left, found)?;
+                let left = parenthesize(text, binds, precedence);
+                let (text, binds) = self.value(right, found)?;
+                let right = parenthesize(text, binds, precedence + 1);
+                Ok((format!("{left} {symbol} {right}"), precedence))
+            }
+            _ => Err(format!("cannot apply '{operator}' to {found}")),
+        }
+    }
+
+    /// The strings joined by a chain of `+`, in order.
+    fn concatenated<'e>(&mut self, expression: &'e Expression, parts: &mut Vec<&'e Expression>) {
+        match expression {
+            Expression::BinaryOp {
+                left,
+                operator,
+                right,
+            } if matches!(**operator, ArithmeticOperator::Addition)
+                && self.ty(expression) == Type::String =>
+            {
+                self.concatenated(left, parts);
+                self.concatenated(right, parts);
+            }
+            _ => parts.push(expression),
+        }
+    }
+
+    /// A call to one of the program's functions, or the Rust spelling of a
+    /// builtin for the types of its arguments.
+    fn function_call(&mut self, name: &str, args: &[Expression]) -> Translation {
+        if self.functions.contains(name) {
+            let Some((params, _)) = self.signatures.get(name).cloned() else {
+                return Err(format!("the types of '{name}' are unknown"));
+            };
+            if params.len() != args.len() {
+                return Err(format!("'{name}' expects {} argument(s)", params.len()));
+            }
+            let args = args
+                .iter()
+                .zip(&params)
+                .map(|(arg, param)| Ok(self.value(arg, param)?.0))
+                .collect::<Result<Vec<_>, String>>()?;
+            return Ok((format!("{}({})", ident(name), args.join(", ")), ATOM));
+        }
+
+        let types: Vec<Type> = args.iter().map(|arg| self.ty(arg)).collect();
+        if let Some(arg) = args.iter().zip(&types).find(|(_, t)| t.is_partial()) {
+            return Err(format!("the type of '{}' is unknown", arg.0));
+        }
+        match (name, args, types.as_slice()) {
+            ("print", _, _) => Ok((self.print(args)?, ATOM)),
+            ("length", [sequence], [Type::String]) => Ok((
+                format!("{}.chars().count() as i64", self.operand(sequence, ATOM)?),
+                CAST,
+            )),
+            ("length", [sequence], [Type::Array(_)]) => Ok((
+                format!("{}.len() as i64", self.operand(sequence, ATOM)?),
+                CAST,
+            )),
+            ("abs" | "min" | "max", _, _) => {
+                let found = types
+                    .iter()
+                    .try_fold(Type::Unknown, |found, arg| found.unify(arg))
+                    .filter(Type::is_numeric)
+                    .ok_or_else(|| format!("'{name}' needs numbers"))?;
+                let args = args
+                    .iter()
+                    .map(|arg| Ok(self.value(arg, &found)?.0))
+                    .collect::<Result<Vec<_>, String>>()?;
+                let rust_type = self.rust_type(&found);
+                Ok((format!("{rust_type}::{name}({})", args.join(", ")), ATOM))
+            }
+            ("sqrt" | "pow", _, _) => {
+                let function = if name == "pow" { "powf" } else { name };
+                let args = args
+                    .iter()
+                    .map(|arg| Ok(self.value(arg, &Type::Real)?.0))
+                    .collect::<Result<Vec<_>, String>>()?;
+                Ok((format!("f64::{function}({})", args.join(", ")), ATOM))
+            }
+            ("floor" | "ceil" | "round", [value], [Type::Int]) => self.expression(value),
+            ("floor" | "ceil" | "round", [value], [Type::Real]) => Ok((
+                format!("f64::{name}({}) as i64", self.expression(value)?.0),
+                CAST,
+            )),
+            ("random", _, _) => Err("random numbers need a crate such as `rand`".to_string()),
+            ("range", [from, to], _) => {
+                let from = self.value(from, &Type::Int)?.0;
+                let to = self.value(to, &Type::Int)?.0;
+                Ok((format!("({from}..{to}).collect::<Vec<i64>>()"), ATOM))
+            }
+            ("append", [items, item], [Type::Array(element), _]) => {
+                let item = self.value(item, element)?.0;
+                let items = self.value(items, &types[0])?.0;
+                Ok((format!("[{items}, vec![{item}]].concat()"), ATOM))
+            }
+            ("contains", [haystack, needle], [Type::Array(element), _]) => {
+                let (text, binds) = self.value(needle, element)?;
+                let needle = parenthesize(text, binds, UNARY);
+                let haystack = self.operand(haystack, ATOM)?;
+                Ok((format!("{haystack}.contains(&{needle})"), ATOM))
+            }
+            ("contains", [haystack, needle], [Type::String, Type::String]) => {
+                let needle = match needle {
+                    Expression::Literal(text) => text.clone(),
+                    _ => format!("{}.as_str()", self.operand(needle, ATOM)?),
+                };
+                let haystack = self.operand(haystack, ATOM)?;
+                Ok((format!("{haystack}.contains({needle})"), ATOM))
+            }
+            ("substring", [text, from, to], [Type::String, _, _]) => {
+                let text = self.operand(text, ATOM)?;
+                let start = self.index(from)?;
+                let (from, binds) = self.value(from, &Type::Int)?;
+                let from = parenthesize(from, binds, PRODUCT);
+                let (to, binds) = self.value(to, &Type::Int)?;
+                let to = parenthesize(to, binds, SUM);
+                Ok((
+                    format!(
+                        "{text}.chars().skip({start}).take(({to} - {from}) as usize).collect::<String>()"
+                    ),
+                    ATOM,
+                ))
+            }
+            ("to_upper" | "to_lower", [text], [Type::String]) => {
+                let method = if name == "to_upper" {
+                    "to_uppercase"
+                } else {
+                    "to_lowercase"
+                };
+                Ok((format!("{}.{method}()", self.operand(text, ATOM)?), ATOM))
+            }
+            ("to_string", [value], [Type::Int | Type::Bool | Type::String]) => {
+                Ok((format!("{}.to_string()", self.operand(value, ATOM)?), ATOM))
+            }
+            ("to_string", [value], _) => Ok((
+                format!("format!(\"{{:?}}\", {})", self.operand(value, OR)?),
+                ATOM,
+            )),
+            ("to_int", [value], [Type::Int]) | ("to_real", [value], [Type::Real]) => {
+                self.expression(value)
+            }
+            ("to_int" | "to_real", [value], [Type::Int | Type::Real]) => {
+                let target = if name == "to_int" { "i64" } else { "f64" };
+                let value = self.operand(value, UNARY)?;
+                Ok((format!("{value} as {target}"), CAST))
+            }
+            ("to_int" | "to_real", [value], [Type::String]) => {
+                let target = if name == "to_int" { "i64" } else { "f64" };
+                let value = self.operand(value, ATOM)?;
+                Ok((format!("{value}.trim().parse::<{target}>().unwrap()"), ATOM))
+            }
+            _ => Err(format!("cannot translate the call to '{name}'")),
+        }
+    }
+
+    /// `println!` with string literals written into the format string.
+    fn print(&mut self, args: &[Expression]) -> Result<String, String> {
+        if args.is_empty() {
+            return Ok("println!()".to_string());
+        }
+        let mut format = Vec::new();
+        let mut values = String::new();
+        for arg in args {
+            if let Expression::Literal(text) = arg
+                && text.starts_with('"')
+            {
+                format.push(format_text(text));
+                continue;
+            }
+            let placeholder = match self.ty(arg) {
+                Type::Int | Type::Bool | Type::String => "{}",
+                _ => "{:?}",
+            };
+            format.push(placeholder.to_string());
+            values.push_str(&format!(", {}", self.operand(arg, OR)?));
+        }
+        Ok(format!("println!(\"{}\"{values})", format.join(" ")))
+    }
+
+    fn condition(&mut self, condition: &Condition) -> Translation {
+        match condition {
+            Condition::ComparisonOperator {
+                left,
+                operator,
+                right,
+            } => {
+                let ordered =
+                    !matches!(**operator, ComparisonOperator::Eq | ComparisonOperator::Neq);
+                let left_type = self.ty(left);
+                let right_type = self.ty(right);
+                let common = left_type
+                    .unify(&right_type)
+                    .filter(|common| !common.is_partial())
+                    .ok_or_else(|| format!("cannot compare {left_type} with {right_type}"))?;
+                let mut left = self.comparand(left, &common, ordered)?;
+                let right = self.comparand(right, &common, ordered)?;
+                let symbol = match **operator {
+                    ComparisonOperator::Eq => "==",
+                    ComparisonOperator::Neq => "!=",
+                    ComparisonOperator::Gt => ">",
+                    ComparisonOperator::Lt => "<",
+                    ComparisonOperator::Gte => ">=",
+                    ComparisonOperator::Lte => "<=",
+                };
+                // `x as i64 < y` would read as the start of generic arguments.
+                if symbol.starts_with('<')
+                    && [" as i64", " as f64"].iter().any(|c| left.ends_with(c))
+                {
+                    left = format!("({left})");
+                }
+                Ok((format!("{left} {symbol} {right}"), COMPARE))
+            }
+            Condition::LogicalOperator {
+                left,
+                operator,
+                right,
+            } => {
+                let (symbol, precedence) = match **operator {
+                    LogicalOperator::And => ("&&", AND),
+                    LogicalOperator::Or => ("||", OR),
+                };
+                let (text, binds) = self.condition(left)?;
+                let left = parenthesize(text, binds, precedence);
+                let (text, binds) = self.condition(right)?;
+                let right = parenthesize(text, binds, precedence + 1);
+                Ok((format!("{left} {symbol} {right}"), precedence))
+            }
+            Condition::Value(value) => match self.ty(value) {
+                Type::Bool => self.expression(value),
+                found => Err(format!("condition '{value}' is {found}, expected bool")),
+            },
+        }
+    }
+
+    /// One side of a comparison of values of type `common`. Ints compared
+    /// with reals are widened, and string literals compared by order become
+    /// `String`s, since `&str` only compares by equality with them.
+    fn comparand(
+        &mut self,
+        side: &Expression,
+        common: &Type,
+        ordered: bool,
+    ) -> Result<String, String> {
+        let found = self.ty(side);
+        let literal = matches!(side, Expression::Literal(_));
+        let (text, binds) = if found != *common || (ordered && literal && found == Type::String) {
+            self.value(side, common)?
+        } else {
+            self.expression(side)?
+        };
+        Ok(parenthesize(text, binds, SUM))
+    }
+
+    /// What a `for` loop walks: a range, the characters of a string or the
+    /// elements of a copy of an array.
+    fn iterable(&mut self, list: &Expression) -> Result<String, String> {
+        if let Expression::FunctionCall { name, args } = list
+            && name == "range"
+            && !self.functions.contains(name.as_str())
+            && let [from, to] = args.as_slice()
+        {
+            let from = self.value(from, &Type::Int)?.0;
+            let to = self.value(to, &Type::Int)?.0;
+            return Ok(format!("{from}..{to}"));
+        }
+        match self.ty(list) {
+            Type::String => Ok(format!(
+                "{}.chars().map(String::from)",
+                self.operand(list, ATOM)?
+            )),
+            found @ Type::Array(_) => Ok(self.value(list, &found)?.0),
+            found => Err(format!("cannot loop over {found}")),
+        }
+    }
+}
+
+impl Emitter for Rust<'_> {
+    fn writer(&mut self) -> &mut Writer {
+        &mut self.writer
+    }
+
+    fn assignment(&mut self, assignment: &Assignment) {
+        let Some(var) = assignment.var() else {
+            let target_type = self.ty(&assignment.target);
+            let result = self
+                .place(&assignment.target)
+                .and_then(|place| Ok((place, self.value(&assignment.value, &target_type)?.0)));
+            match result {
+                Ok((place, value)) => self.writer.line(format!("{place} = {value};")),
+                Err(reason) => self.todo(assignment.line, &reason),
+            }
+            return;
+        };
+
+        let name = ident(var);
+        let var_type = self.env.get(var).cloned().unwrap_or(Type::Unknown);
+        let value = if var_type.is_partial() {
+            Err(format!("the type of '{var}' is unknown"))
+        } else {
+            self.value(&assignment.value, &var_type)
+                .map(|(value, _)| value)
+        };
+        if !self.declared.insert(var.to_string()) {
+            match value {
+                Ok(value) => self.writer.line(format!("{name} = {value};")),
+                Err(reason) => self.todo(assignment.line, &reason),
+            }
+            return;
+        }
+        if var_type.is_partial() {
+            self.todo(assignment.line, &format!("the type of '{var}' is unknown"));
+            return;
+        }
+        let mutable = if self.mutable.contains(var) {
+            "mut "
+        } else {
+            ""
+        };
+        let rust_type = self.rust_type(&var_type);
+        let value = value.unwrap_or_else(|reason| {
+            self.writer
+                .line(format!("// line {}: {reason}", assignment.line + 1));
+            "todo!()".to_string()
+        });
+        self.writer
+            .line(format!("let {mutable}{name}: {rust_type} = {value};"));
+    }
+
+    fn call(&mut self, call: &FunctionCall) {
+        match self.function_call(&call.name, &call.args) {
+            Ok((text, _)) => self.writer.line(format!("{text};")),
+            Err(reason) => self.todo(call.line, &reason),
+        }
+    }
+
+    fn return_value(&mut self, value: &Return) {
+        let Some(returns) = self.returns.clone() else {
+            self.writer.line("return;");
+            return;
+        };
+        match self.returned(&value.value, &returns) {
+            Ok((text, _)) => self.writer.line(format!("return {text};")),
+            Err(reason) => self.todo(value.line, &reason),
+        }
+    }
+
+    fn if_statement(&mut self, statement: &If) {
+        let conditions = std::iter::once(&statement.condition)
+            .chain(statement.else_ifs.iter().map(|branch| &branch.condition))
+            .map(|condition| Ok(self.condition(condition)?.0))
+            .collect::<Result<Vec<_>, String>>();
+        let conditions = match conditions {
+            Ok(conditions) => conditions,
+            Err(reason) => return self.todo(statement.line, &reason),
+        };
+        self.writer.line(format!("if {} {{", conditions[0]));
+        self.block(&statement.body);
+        for (branch, condition) in statement.else_ifs.iter().zip(&conditions[1..]) {
+            self.writer.line(format!("}} else if {condition} {{"));
+            self.block(&branch.body);
+        }
+        if let Some(branch) = &statement.else_branch {
+            self.writer.line("} else {");
+            self.block(&branch.body);
+        }
+        self.writer.line("}");
+    }
+
+    fn loop_statement(&mut self, statement: &Loop) {
+        let header = match &statement.loop_type {
+            LoopType::While(Condition::Value(Expression::Literal(text))) if text == "true" => {
+                Ok("loop {".to_string())
+            }
+            LoopType::While(condition) => self
+                .condition(condition)
+                .map(|(condition, _)| format!("while {condition} {{")),
+            LoopType::For { item, list } => self.iterable(list).map(|list| {
+                self.declared.insert(item.clone());
+                let mutable = if self.mutable.contains(item) {
+                    "mut "
+                } else {
+                    ""
+                };
+                format!("for {mutable}{} in {list} {{", ident(item))
+            }),
+        };
+        match header {
+            Ok(header) => self.writer.line(header),
+            Err(reason) => return self.todo(statement.line, &reason),
+        }
+        self.loops += 1;
+        self.block(&statement.body);
+        self.loops -= 1;
+        self.writer.line("}");
+    }
+
+    // Outside a loop `break` and `continue` leave the routine.
+    fn break_statement(&mut self, line: usize) {
+        self.leave("break", line);
+    }
+
+    fn continue_statement(&mut self, line: usize) {
+        self.leave("continue", line);
+    }
+
+    fn comment(&mut self, comment: &Comment) {
+        self.writer.line(format!("// {}", comment.value).trim_end());
+    }
+}
+
+impl Rust<'_> {
+    fn leave(&mut self, keyword: &str, line: usize) {
+        if self.loops > 0 {
+            self.writer.line(format!("{keyword};"));
+        } else if self.returns.is_none() {
+            self.writer.line("return;");
+        } else {
+            self.todo(
+                line,
+                &format!("'{keyword}' outside a loop ends the function without a value"),
+            );
+        }
+    }
+
+    /// An element or field that can be assigned to.
+    fn place(&mut self, target: &Expression) -> Result<String, String> {
+        match target {
+            Expression::Variable(name) => Ok(ident(name)),
+            Expression::Index { target, index } => match self.ty(target) {
+                Type::Array(_) => Ok(format!("{}[{}]", self.place(target)?, self.index(index)?)),
+                found => Err(format!("cannot assign to an element of {found}")),
+            },
+            Expression::Field { target, name } => {
+                Ok(format!("{}.{}", self.place(target)?, ident(name)))
+            }
+            _ => Err(format!("cannot assign to '{target}'")),
+        }
+    }
+}
+
+fn parenthesize(text: String, binds: u8, precedence: u8) -> String {
+    if binds < precedence {
+        format!("({text})")
+    } else {
+        text
+    }
+}
+
+fn ident(name: &str) -> String {
+    if RESERVED.contains(&name) {
+        format!("{name}_")
+    } else {
+        name.to_string()
+    }
+}
+
+/// A string literal as part of a format string.
+fn format_text(text: &str) -> String {
+    let inner = text.trim_start_matches('"').trim_end_matches('"');
+    inner
+        .replace('\\', "\\\\")
+        .replace('{', "{{")
+        .replace('}', "}}")
+}
+
+/// A pseudocode literal in Rust syntax. Strings stay `&str` literals, which
+/// [`Rust::value`] turns into a `String` where one is owned.
+fn literal(text: &str) -> (String, u8) {
+    let binds = if text.starts_with('-') { UNARY } else { ATOM };
+    if text.starts_with('"') {
+        return (text.replace('\\', "\\\\"), ATOM);
+    }
+    if text == "true" || text == "false" {
+        return (text.to_string(), ATOM);
+    }
+    if let Ok(value) = text.parse::<i64>() {
+        return (value.to_string(), binds);
+    }
+    match text.parse::<f64>() {
+        Ok(value) if value.is_nan() => ("f64::NAN".to_string(), ATOM),
+        Ok(value) if value.is_infinite() && value > 0.0 => ("f64::INFINITY".to_string(), ATOM),
+        Ok(value) if value.is_infinite() => ("f64::NEG_INFINITY".to_string(), ATOM),
+        Ok(value) => (format!("{value:?}"), binds),
+        Err(_) => (text.to_string(), ATOM),
+    }
+}
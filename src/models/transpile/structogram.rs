@@ -0,0 +1,90 @@
+use crate::models::{
+    nodes::program::Program,
+    structogram::{Mark, structogram},
+};
+
+/// Pixels a character cell of the layout takes in the picture.
+const CELL_WIDTH: usize = 10;
+const CELL_HEIGHT: usize = 20;
+const FONT_SIZE: usize = 14;
+
+/// The structograms of the program as an SVG picture, laid out as in the
+/// preview pane with each character cell scaled up to a fixed size.
+pub fn transpile(program: &Program) -> String {
+    let structogram = structogram(program);
+    let (width, height) = (
+        structogram.width * CELL_WIDTH,
+        structogram.height * CELL_HEIGHT,
+    );
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         viewBox=\"0 0 {width} {height}\">\n"
+    );
+    svg.push_str(&format!(
+        "  <rect width=\"{width}\" height=\"{height}\" fill=\"white\"/>\n"
+    ));
+    svg.push_str(&format!(
+        "  <g fill=\"none\" stroke=\"black\" stroke-width=\"1\" \
+         font-family=\"monospace\" font-size=\"{FONT_SIZE}\">\n"
+    ));
+    for mark in &structogram.marks {
+        let element = match mark {
+            Mark::Box {
+                x,
+                y,
+                width,
+                height,
+            } => format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+                center_x(*x),
+                center_y(*y),
+                (width - 1) * CELL_WIDTH,
+                (height - 1) * CELL_HEIGHT
+            ),
+            Mark::Diagonal { from, to } => format!(
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>",
+                center_x(from.0),
+                center_y(from.1),
+                center_x(to.0),
+                center_y(to.1)
+            ),
+            Mark::Label { x, y, text, bold } => {
+                // Centred on the cells the text covers in the preview, since
+                // the font's characters needn't be as wide as a cell.
+                let middle = x * CELL_WIDTH + text.chars().count() * CELL_WIDTH / 2;
+                let weight = if *bold { " font-weight=\"bold\"" } else { "" };
+                format!(
+                    "<text x=\"{middle}\" y=\"{}\" fill=\"black\" stroke=\"none\" \
+                     text-anchor=\"middle\" dominant-baseline=\"central\"{weight}>{}</text>",
+                    center_y(*y),
+                    escape(text)
+                )
+            }
+        };
+        svg.push_str(&format!("    {element}\n"));
+    }
+    svg.push_str("  </g>\n</svg>\n");
+    svg
+}
+
+fn center_x(x: usize) -> usize {
+    x * CELL_WIDTH + CELL_WIDTH / 2
+}
+
+fn center_y(y: usize) -> usize {
+    y * CELL_HEIGHT + CELL_HEIGHT / 2
+}
+
+/// `text` with the characters XML gives a meaning written as entities.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
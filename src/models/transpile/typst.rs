@@ -0,0 +1,343 @@
+use std::collections::HashSet;
+
+use crate::models::{
+    nodes::{
+        assignment::Assignment,
+        comment::Comment,
+        condition::{Condition, If},
+        expression::Expression,
+        function::FunctionCall,
+        loops::{Loop, LoopType},
+        node::Node,
+        operator::{ArithmeticOperator, ComparisonOperator, LogicalOperator},
+        program::Program,
+        return_node::Return,
+    },
+    transpile::{Emitter, Writer},
+};
+
+const INDENT: &str = "  ";
+
+/// How tightly an expression binds, loosest first.
+const OR: u8 = 1;
+const AND: u8 = 2;
+const NOT: u8 = 3;
+const COMPARE: u8 = 4;
+const SUM: u8 = 5;
+const PRODUCT: u8 = 6;
+const ATOM: u8 = 7;
+
+/// The program for Typst's `algorithmic` package: an algorithm figure per
+/// function, as a `Function` captioned with its name, and one for `start` as
+/// a `Procedure`. Tests are left out, since they aren't part of the
+/// algorithm.
+pub fn transpile(program: &Program) -> String {
+    let mut typst = Typst {
+        writer: Writer::new(INDENT),
+        functions: program.functions.iter().map(|f| f.name.as_str()).collect(),
+    };
+    typst.writer.line("#import \"@preview/algorithmic:1.0.0\"");
+    typst
+        .writer
+        .line("#import algorithmic: algorithm-figure, style-algorithm");
+    typst.writer.line("#show: style-algorithm");
+    for function in &program.functions {
+        typst.figure("Function", &function.name, &function.params, &function.body);
+    }
+    typst.figure("Procedure", "start", &[], &program.start.body);
+    typst.writer.take()
+}
+
+struct Typst<'a> {
+    writer: Writer,
+    /// Names of the program's own functions, which win over builtins.
+    functions: HashSet<&'a str>,
+}
+
+impl Typst<'_> {
+    /// A routine in an algorithm figure of its own, captioned with its name.
+    fn figure(&mut self, keyword: &str, routine: &str, params: &[String], body: &[Node]) {
+        let title = string(routine);
+        let params: Vec<String> = params.iter().map(|param| string(param)).collect();
+        let params = match params.as_slice() {
+            [param] => format!("({param},)"),
+            _ => format!("({})", params.join(", ")),
+        };
+        self.writer.line("");
+        self.writer.line(format!("#algorithm-figure({title}, {{"));
+        self.writer.indent();
+        self.writer.line("import algorithmic: *");
+        self.writer.line(format!("{keyword}({title}, {params}, {{"));
+        self.block(body);
+        self.writer.line("})");
+        self.writer.dedent();
+        self.writer.line("})");
+    }
+
+    /// `expression` in math mode.
+    fn math(&mut self, expression: &Expression) -> String {
+        format!("${}$", self.operand(expression, OR))
+    }
+
+    fn condition_math(&mut self, condition: &Condition) -> String {
+        format!("${}$", self.condition(condition).0)
+    }
+
+    fn operand(&mut self, expression: &Expression, precedence: u8) -> String {
+        let (text, binds) = self.expression(expression);
+        parenthesize(text, binds, precedence)
+    }
+
+    fn expression(&mut self, expression: &Expression) -> (String, u8) {
+        match expression {
+            Expression::Variable(var) => (name(var), ATOM),
+            Expression::Literal(text) => (literal(text), ATOM),
+            Expression::BinaryOp {
+                left,
+                operator,
+                right,
+            } => {
+                // A plain `/` would be set as a fraction.
+                let (symbol, precedence) = match **operator {
+                    ArithmeticOperator::Addition => ("+", SUM),
+                    ArithmeticOperator::Subtraction => ("-", SUM),
+                    ArithmeticOperator::Multiplication => ("dot", PRODUCT),
+                    ArithmeticOperator::Division => ("slash", PRODUCT),
+                    ArithmeticOperator::Modulo => ("mod", PRODUCT),
+                };
+                let left = self.operand(left, precedence);
+                let right = self.operand(right, precedence + 1);
+                (format!("{left} {symbol} {right}"), precedence)
+            }
+            Expression::FunctionCall { name, args } => self.function_call(name, args),
+            Expression::Not(inner) => (format!("not {}", self.operand(inner, NOT)), NOT),
+            Expression::Array(items) => (format!("[{}]", self.list(items)), ATOM),
+            Expression::Record(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(field, value)| format!("{}: {}", name(field), self.operand(value, OR)))
+                    .collect();
+                (format!("{{{}}}", fields.join(", ")), ATOM)
+            }
+            Expression::Index { target, index } => {
+                let target = self.operand(target, ATOM);
+                (format!("{target}[{}]", self.operand(index, OR)), ATOM)
+            }
+            Expression::Field {
+                target,
+                name: field,
+            } => (
+                format!("{}\\.{}", self.operand(target, ATOM), name(field)),
+                ATOM,
+            ),
+        }
+    }
+
+    /// A call, with the builtins that have a usual notation written in it.
+    fn function_call(&mut self, function: &str, args: &[Expression]) -> (String, u8) {
+        if !self.functions.contains(function) {
+            match (function, args) {
+                ("length" | "abs", [value]) => {
+                    return (format!("abs({})", self.operand(value, OR)), ATOM);
+                }
+                ("floor" | "ceil" | "sqrt", [value]) => {
+                    return (format!("{function}({})", self.operand(value, OR)), ATOM);
+                }
+                ("pow", [base, exponent]) => {
+                    let base = self.operand(base, ATOM);
+                    return (format!("{base}^({})", self.operand(exponent, OR)), ATOM);
+                }
+                _ => {}
+            }
+        }
+        // Quoted, the name is set upright and can't be taken for a Typst
+        // function.
+        let args = self.list(args);
+        (format!("{}({args})", string(function)), ATOM)
+    }
+
+    fn list(&mut self, items: &[Expression]) -> String {
+        let items: Vec<String> = items.iter().map(|item| self.operand(item, OR)).collect();
+        items.join(", ")
+    }
+
+    fn condition(&mut self, condition: &Condition) -> (String, u8) {
+        match condition {
+            Condition::ComparisonOperator {
+                left,
+                operator,
+                right,
+            } => {
+                let symbol = match **operator {
+                    ComparisonOperator::Eq => "=",
+                    ComparisonOperator::Neq => "!=",
+                    ComparisonOperator::Gt => ">",
+                    ComparisonOperator::Lt => "<",
+                    ComparisonOperator::Gte => ">=",
+                    ComparisonOperator::Lte => "<=",
+                };
+                let left = self.operand(left, SUM);
+                let right = self.operand(right, SUM);
+                (format!("{left} {symbol} {right}"), COMPARE)
+            }
+            Condition::LogicalOperator {
+                left,
+                operator,
+                right,
+            } => {
+                let (symbol, precedence) = match **operator {
+                    LogicalOperator::And => ("and", AND),
+                    LogicalOperator::Or => ("or", OR),
+                };
+                let (left, binds) = self.condition(left);
+                let left = parenthesize(left, binds, precedence);
+                let (right, binds) = self.condition(right);
+                let right = parenthesize(right, binds, precedence + 1);
+                (format!("{left} {symbol} {right}"), precedence)
+            }
+            Condition::Value(value) => self.expression(value),
+        }
+    }
+
+    /// The head of a `for` loop: a count from one bound to the other over a
+    /// range, or each item of a list.
+    fn for_head(&mut self, item: &str, list: &Expression) -> String {
+        if let Expression::FunctionCall { name: range, args } = list
+            && range == "range"
+            && !self.functions.contains(range.as_str())
+            && let [from, to] = args.as_slice()
+        {
+            let from = self.operand(from, OR);
+            // The last value is one below the bound, folded into a constant
+            // the bound ends with.
+            let to = match to {
+                Expression::Literal(text) if let Ok(to) = text.parse::<i64>() => {
+                    (to - 1).to_string()
+                }
+                Expression::BinaryOp {
+                    left,
+                    operator,
+                    right,
+                } if matches!(**operator, ArithmeticOperator::Subtraction)
+                    && let Expression::Literal(text) = &**right
+                    && let Ok(offset) = text.parse::<i64>() =>
+                {
+                    format!("{} - {}", self.operand(left, SUM), offset + 1)
+                }
+                _ => format!("{} - 1", self.operand(to, SUM)),
+            };
+            return format!("[${} <- {from}$ *to* ${to}$]", name(item));
+        }
+        format!("${} in {}$", name(item), self.operand(list, OR))
+    }
+}
+
+impl Emitter for Typst<'_> {
+    fn writer(&mut self) -> &mut Writer {
+        &mut self.writer
+    }
+
+    fn assignment(&mut self, assignment: &Assignment) {
+        let target = self.math(&assignment.target);
+        let value = self.math(&assignment.value);
+        self.writer.line(format!("Assign[{target}][{value}]"));
+    }
+
+    fn call(&mut self, call: &FunctionCall) {
+        let (text, _) = self.function_call(&call.name, &call.args);
+        self.writer.line(format!("Line(${text}$)"));
+    }
+
+    fn return_value(&mut self, value: &Return) {
+        let value = self.math(&value.value);
+        self.writer.line(format!("Return[{value}]"));
+    }
+
+    fn if_statement(&mut self, statement: &If) {
+        let condition = self.condition_math(&statement.condition);
+        self.writer.line(format!("If({condition}, {{"));
+        self.block(&statement.body);
+        self.writer.line("})");
+        for branch in &statement.else_ifs {
+            let condition = self.condition_math(&branch.condition);
+            self.writer.line(format!("ElseIf({condition}, {{"));
+            self.block(&branch.body);
+            self.writer.line("})");
+        }
+        if let Some(branch) = &statement.else_branch {
+            self.writer.line("Else({");
+            self.block(&branch.body);
+            self.writer.line("})");
+        }
+    }
+
+    fn loop_statement(&mut self, statement: &Loop) {
+        let (keyword, head) = match &statement.loop_type {
+            LoopType::While(condition) => ("While", self.condition_math(condition)),
+            LoopType::For { item, list } => ("For", self.for_head(item, list)),
+        };
+        self.writer.line(format!("{keyword}({head}, {{"));
+        self.block(&statement.body);
+        self.writer.line("})");
+    }
+
+    fn break_statement(&mut self, _line: usize) {
+        self.writer.line("Line(strong(\"break\"))");
+    }
+
+    fn continue_statement(&mut self, _line: usize) {
+        self.writer.line("Line(strong(\"continue\"))");
+    }
+
+    fn comment(&mut self, comment: &Comment) {
+        // A string rather than content, so markup characters stay as typed.
+        let text = string(comment.value.trim());
+        self.writer.line(format!("Comment({text})"));
+    }
+}
+
+fn parenthesize(text: String, binds: u8, precedence: u8) -> String {
+    if binds < precedence {
+        format!("({text})")
+    } else {
+        text
+    }
+}
+
+/// An identifier in math mode. Longer than a letter it has to be quoted, or
+/// Typst would look it up as a symbol or function.
+fn name(identifier: &str) -> String {
+    if identifier.chars().count() == 1 {
+        identifier.to_string()
+    } else {
+        format!("italic({})", string(identifier))
+    }
+}
+
+/// A pseudocode literal in math mode.
+fn literal(text: &str) -> String {
+    if let Some(inner) = text.strip_prefix('"') {
+        let inner = inner.strip_suffix('"').unwrap_or(inner);
+        return format!("mono({})", string(&format!("\"{inner}\"")));
+    }
+    match text {
+        "true" | "false" => format!("bold(\"{text}\")"),
+        _ => text.to_string(),
+    }
+}
+
+/// `text` as a Typst string literal.
+fn string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for character in text.chars() {
+        match character {
+            '\\' | '"' => {
+                quoted.push('\\');
+                quoted.push(character);
+            }
+            _ => quoted.push(character),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
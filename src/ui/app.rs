@@ -5,16 +5,79 @@ use ratatui::{
 };
 
 use crate::{
-    models::state::State,
-    ui::{input, output},
+    models::{
+        analysis::types,
+        diagnostics::Diagnostic,
+        state::{Panel, State},
+    },
+    ui::{code, complexity, debugger, diagnostics, diagram, input, metrics, output, trace},
 };
 
-pub fn draw_main(frame: &mut Frame, rect: Rect, state: &State, editor_state: &mut EditorState) {
+const MAX_DIAGNOSTICS_HEIGHT: u16 = 8;
+
+pub fn draw_main(frame: &mut Frame, rect: Rect, state: &mut State, editor_state: &mut EditorState) {
     let layout = Layout::default()
         .direction(Direction::Horizontal)
         .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
         .split(rect);
 
     input::render(frame, &layout[0], state, editor_state);
-    output::render(frame, &layout[1], state, editor_state);
+    if let Some(session) = &state.debugger {
+        debugger::render(frame, &layout[1], session, state);
+        return;
+    }
+
+    let text: String = editor_state.lines.clone().into();
+    let mut program = State::get_output(text.as_str());
+    let mut diagnostics = State::get_diagnostics(&program, &state.lint_config);
+    diagnostics.extend(
+        state
+            .test_results
+            .iter()
+            .filter(|result| !result.passed())
+            .map(|result| Diagnostic::error(result.line, result.details().join(", "))),
+    );
+    if state.show_types
+        && let Ok(program) = &mut program
+    {
+        types::annotate(program);
+    }
+
+    let entries = diagnostics.len() + state.settings_errors.len();
+    let diagnostics_height = if entries == 0 {
+        0
+    } else {
+        (entries as u16 + 2).min(MAX_DIAGNOSTICS_HEIGHT)
+    };
+    let right = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![
+            Constraint::Min(0),
+            Constraint::Length(diagnostics_height),
+        ])
+        .split(layout[1]);
+
+    let metrics_width = if state.show_metrics {
+        metrics::WIDTH
+    } else {
+        0
+    };
+    let top = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Constraint::Min(0), Constraint::Length(metrics_width)])
+        .split(right[0]);
+
+    match state.panel {
+        Panel::Preview => output::render(frame, &top[0], state, &program),
+        Panel::Trace => trace::render(frame, &top[0], state),
+        Panel::Complexity => complexity::render(frame, &top[0], state),
+        Panel::Code => code::render(frame, &top[0], state, &program),
+        Panel::Flowchart | Panel::Structogram => {
+            diagram::render(frame, &top[0], state, &program, editor_state.cursor.row)
+        }
+    }
+    if state.show_metrics {
+        metrics::render(frame, &top[1], &program);
+    }
+    diagnostics::render(frame, &right[1], &state.settings_errors, &diagnostics);
 }
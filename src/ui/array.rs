@@ -0,0 +1,95 @@
+use ratatui::{
+    Frame,
+    layout::{Direction, Rect},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, Paragraph, Widget},
+};
+
+use crate::models::{
+    debugger::{Access, Playback},
+    interpreter::value::Value,
+};
+
+const HELP: &str = "space: play  +/-: speed  a: next array";
+
+/// Draws the elements of an array variable as bars, marking the ones the
+/// last statement read in yellow and wrote in red.
+pub fn render(
+    frame: &mut Frame,
+    rect: &Rect,
+    name: &str,
+    items: &[Value],
+    accesses: &[Access],
+    playback: &Playback,
+) {
+    let status = if playback.playing {
+        format!("▶ {}/s", playback.steps_per_second)
+    } else {
+        format!("⏸ {}/s", playback.steps_per_second)
+    };
+    let block = Block::new()
+        .style(Style::default().fg(Color::Magenta))
+        .borders(Borders::all())
+        .title_top(format!("array: {name}"))
+        .title_top(Line::from(status).right_aligned())
+        .title_bottom(HELP);
+
+    let numbers: Option<Vec<i64>> = items
+        .iter()
+        .map(|item| match item {
+            Value::Int(value) => Some(*value),
+            Value::Real(value) => Some(value.round() as i64),
+            _ => None,
+        })
+        .collect();
+    let Some(numbers) = numbers else {
+        Paragraph::new("only arrays of numbers can be drawn")
+            .block(block)
+            .render(*rect, frame.buffer_mut());
+        return;
+    };
+
+    let width = block.inner(*rect).width as usize;
+    let gap = usize::from(width >= numbers.len() * 3);
+    let bar_width = match numbers.len() {
+        0 => 1,
+        count => (width.saturating_sub((count - 1) * gap) / count).max(1),
+    };
+    // Bars can't go below zero, so shift everything up when needed.
+    let lowest = numbers.iter().copied().min().unwrap_or_default();
+    let offset = if lowest < 0 { 1 - lowest } else { 0 };
+
+    let bars: Vec<Bar> = numbers
+        .iter()
+        .zip(items)
+        .enumerate()
+        .map(|(i, (number, item))| {
+            let touched = accesses
+                .iter()
+                .filter(|access| access.array == name && access.index == i);
+            let color = match touched.map(|access| access.write).max() {
+                Some(true) => Color::Red,
+                Some(false) => Color::Yellow,
+                None => Color::Blue,
+            };
+            let mut bar = Bar::default()
+                .value((number + offset) as u64)
+                .text_value(item.to_string())
+                .style(Style::default().fg(color))
+                .value_style(Style::default().fg(Color::Black).bg(color));
+            if bar_width > i.to_string().len() {
+                bar = bar.label(Line::from(i.to_string()));
+            }
+            bar
+        })
+        .collect();
+
+    BarChart::default()
+        .block(block)
+        .direction(Direction::Vertical)
+        .bar_width(bar_width as u16)
+        .bar_gap(gap as u16)
+        .data(BarGroup::default().bars(&bars))
+        .render(*rect, frame.buffer_mut());
+}
@@ -0,0 +1,58 @@
+use color_eyre::eyre::eyre;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Text},
+    widgets::{Block, Borders, Paragraph, Widget},
+};
+
+use crate::models::{
+    nodes::program::Program,
+    state::{AppState, State},
+    transpile,
+};
+
+const HELP: &str = "c: next language  e: export  j/k: scroll  p: preview";
+
+/// The program translated into the selected language.
+pub fn render(
+    frame: &mut Frame,
+    rect: &Rect,
+    state: &State,
+    program: &color_eyre::Result<Program>,
+) {
+    let color = match state.app_state {
+        AppState::RightSelected => Color::Yellow,
+        _ => Color::White,
+    };
+    let mut block = Block::new()
+        .style(Style::default().fg(color))
+        .borders(Borders::all())
+        .title_top(format!("code: {}", state.language.name()))
+        .title_bottom(HELP);
+    if let Some(status) = &state.status {
+        block = block.title_top(Line::from(status.as_str()).right_aligned());
+    }
+    let code = match program {
+        Ok(program) => transpile::transpile(program, state.language),
+        Err(e) => Err(eyre!("{e}")),
+    };
+    let text: Text = match code {
+        Ok(code) => code
+            .lines()
+            .skip(state.code_scroll)
+            .map(|line| Line::from(line.to_string()).green())
+            .collect::<Vec<Line>>()
+            .into(),
+        Err(e) => e
+            .to_string()
+            .lines()
+            .map(|line| Line::from(line.to_string()).red())
+            .collect::<Vec<Line>>()
+            .into(),
+    };
+    Paragraph::new(text)
+        .block(block)
+        .render(*rect, frame.buffer_mut());
+}
@@ -0,0 +1,129 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    style::{Color, Style},
+    symbols::Marker,
+    text::Line,
+    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, LegendPosition, Paragraph, Widget},
+};
+
+use crate::models::{
+    complexity::Sample,
+    state::{AppState, State},
+};
+
+const HELP: &str = "o: measure  i: input  e: export  p: preview";
+
+/// Points between two sizes on the fitted curve, so it looks smooth.
+const CURVE_STEPS: usize = 8;
+
+pub fn render(frame: &mut Frame, rect: &Rect, state: &State) {
+    let color = match state.app_state {
+        AppState::RightSelected => Color::Yellow,
+        _ => Color::White,
+    };
+    let mut block = Block::new()
+        .style(Style::default().fg(color))
+        .borders(Borders::all())
+        .title_bottom(HELP);
+    if let Some(status) = &state.status {
+        block = block.title_top(Line::from(status.as_str()).right_aligned());
+    }
+
+    let Some(measurement) = &state.complexity else {
+        Paragraph::new("press o to measure the function under the cursor")
+            .block(block.title_top("complexity"))
+            .render(*rect, frame.buffer_mut());
+        return;
+    };
+
+    let inputs: Vec<String> = measurement.inputs.iter().map(ToString::to_string).collect();
+    let mut title = format!(
+        "complexity: {}({})",
+        measurement.function,
+        inputs.join(", ")
+    );
+    if let Some((growth, _)) = &measurement.fit {
+        title.push_str(&format!(" ~ {growth}"));
+    }
+    block = block.title_top(title);
+    if let Some(error) = &measurement.error {
+        block = block.title_bottom(Line::styled(
+            error.as_str(),
+            Style::default().fg(Color::Red),
+        ));
+    }
+
+    let series = |count: fn(&Sample) -> usize| -> Vec<(f64, f64)> {
+        measurement
+            .samples
+            .iter()
+            .map(|sample| (sample.size as f64, count(sample) as f64))
+            .collect()
+    };
+    let comparisons = series(|sample| sample.comparisons);
+    let assignments = series(|sample| sample.assignments);
+    let accesses = series(|sample| sample.array_accesses);
+    let totals = series(Sample::total);
+
+    let max_size = totals.last().map_or(1.0, |(size, _)| size.max(1.0));
+    let max_count = totals.iter().map(|(_, total)| *total).fold(1.0, f64::max);
+
+    let curve: Vec<(f64, f64)> = match &measurement.fit {
+        Some((growth, factor)) => {
+            let sizes: Vec<f64> = totals.iter().map(|(size, _)| *size).collect();
+            sizes
+                .windows(2)
+                .flat_map(|pair| {
+                    (0..CURVE_STEPS).map(move |step| {
+                        pair[0] + (pair[1] - pair[0]) * step as f64 / CURVE_STEPS as f64
+                    })
+                })
+                .chain(sizes.last().copied())
+                .map(|size| (size, (factor * growth.value(size)).min(max_count)))
+                .collect()
+        }
+        None => Vec::new(),
+    };
+
+    let line = |name: String, data, color| {
+        Dataset::default()
+            .name(name)
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(color))
+            .data(data)
+    };
+    let mut datasets = vec![
+        line("comparisons".to_string(), &comparisons, Color::Yellow),
+        line("assignments".to_string(), &assignments, Color::Cyan),
+        line("array accesses".to_string(), &accesses, Color::Magenta),
+        line("total".to_string(), &totals, Color::White),
+    ];
+    if let Some((growth, factor)) = &measurement.fit {
+        datasets.push(line(
+            format!("{factor:.2} · {growth}"),
+            &curve,
+            Color::Green,
+        ));
+    }
+
+    let labels = |max: f64| vec![Line::from("0"), Line::from(format!("{max:.0}"))];
+    Chart::new(datasets)
+        .block(block)
+        .legend_position(Some(LegendPosition::TopLeft))
+        .hidden_legend_constraints((Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)))
+        .x_axis(
+            Axis::default()
+                .title("n")
+                .bounds([0.0, max_size])
+                .labels(labels(max_size)),
+        )
+        .y_axis(
+            Axis::default()
+                .title("operations")
+                .bounds([0.0, max_count])
+                .labels(labels(max_count)),
+        )
+        .render(*rect, frame.buffer_mut());
+}
@@ -0,0 +1,123 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Borders, Paragraph, Row, Table, Widget},
+};
+
+use crate::{
+    models::{debugger::Session, interpreter::value::Value, state::State},
+    ui::array,
+};
+
+const HELP: &str = "s: into  n: over  c: continue  b: break  r: restart  esc: stop";
+
+pub fn render(frame: &mut Frame, rect: &Rect, session: &Session, state: &State) {
+    let shown = session.current().and_then(|snapshot| {
+        snapshot
+            .variables
+            .iter()
+            .filter(|(name, _)| {
+                state
+                    .array_view
+                    .as_ref()
+                    .is_none_or(|chosen| chosen == name)
+            })
+            .find_map(|(name, value)| match value {
+                Value::Array(items) => Some((name, items)),
+                _ => None,
+            })
+    });
+    let constraints = match shown {
+        Some(_) => vec![
+            Constraint::Percentage(40),
+            Constraint::Percentage(35),
+            Constraint::Percentage(25),
+        ],
+        None => vec![
+            Constraint::Percentage(60),
+            Constraint::Length(0),
+            Constraint::Percentage(40),
+        ],
+    };
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(*rect);
+    if let Some((name, items)) = shown {
+        array::render(
+            frame,
+            &layout[1],
+            name,
+            items,
+            session.last_accesses(),
+            &state.playback,
+        );
+    }
+
+    let (title, rows): (String, Vec<Row>) = match session.current() {
+        Some(snapshot) => (
+            format!(
+                "variables: {} (line {})",
+                snapshot.routine,
+                snapshot.line + 1
+            ),
+            snapshot
+                .variables
+                .iter()
+                .map(|(name, value)| Row::new(vec![name.clone(), value.to_string()]))
+                .collect(),
+        ),
+        None => ("variables: finished".to_string(), Vec::new()),
+    };
+    let block = Block::new()
+        .style(Style::default().fg(Color::Magenta))
+        .borders(Borders::all())
+        .title_top(title)
+        .title_bottom(HELP);
+    Table::new(
+        rows,
+        [Constraint::Percentage(30), Constraint::Percentage(70)],
+    )
+    .header(Row::new(vec!["name", "value"]).bold())
+    .block(block)
+    .render(layout[0], frame.buffer_mut());
+
+    let mut lines: Vec<Line> = session
+        .visible_output()
+        .iter()
+        .map(|line| Line::from(line.as_str()))
+        .collect();
+    if session.current().is_none()
+        && let Some(error) = &session.error
+    {
+        lines.push(Line::styled(
+            error.to_string(),
+            Style::default().fg(Color::Red),
+        ));
+    }
+    if session.current().is_none() && session.truncated {
+        lines.push(Line::styled(
+            format!(
+                "debug history truncated after {} statements",
+                session.snapshots.len()
+            ),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    let mut block = Block::new()
+        .style(Style::default().fg(Color::White))
+        .borders(Borders::all())
+        .title_top("output");
+    if session.truncated {
+        block = block.title_top(Line::from("history truncated").yellow().right_aligned());
+    }
+    let scroll = lines
+        .len()
+        .saturating_sub(layout[2].height.saturating_sub(2) as usize);
+    Paragraph::new(lines)
+        .block(block)
+        .scroll((scroll as u16, 0))
+        .render(layout[2], frame.buffer_mut());
+}
@@ -0,0 +1,51 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Text},
+    widgets::{Block, Borders, Paragraph, Widget},
+};
+
+use crate::models::diagnostics::{Diagnostic, Severity};
+
+/// The diagnostics of the program, after any problems with the settings
+/// files.
+pub fn render(
+    frame: &mut Frame,
+    rect: &Rect,
+    settings_errors: &[String],
+    diagnostics: &[Diagnostic],
+) {
+    if diagnostics.is_empty() && settings_errors.is_empty() {
+        return;
+    }
+
+    let title = if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.fix.is_some())
+    {
+        "diagnostics (f: apply fix on cursor line)"
+    } else {
+        "diagnostics"
+    };
+    let block = Block::new()
+        .style(Style::default().fg(Color::White))
+        .borders(Borders::all())
+        .title_top(title);
+    let styled_text: Text = settings_errors
+        .iter()
+        .map(|error| Line::styled(error.as_str(), Style::default().fg(Color::Yellow)))
+        .chain(diagnostics.iter().map(|diagnostic| {
+            let color = match diagnostic.severity {
+                Severity::Error => Color::Red,
+                Severity::Warning => Color::Yellow,
+                Severity::Info => Color::Cyan,
+            };
+            Line::styled(diagnostic.to_string(), Style::default().fg(color))
+        }))
+        .collect::<Vec<Line>>()
+        .into();
+    Paragraph::new(styled_text)
+        .block(block)
+        .render(*rect, frame.buffer_mut());
+}
@@ -0,0 +1,150 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Paragraph, Widget},
+};
+
+use crate::models::{
+    canvas::{Canvas, Region},
+    flowchart::layout,
+    nodes::program::Program,
+    state::{AppState, Panel, State},
+    structogram::structogram,
+};
+
+/// The program drawn as flowcharts or structograms, with the block for the
+/// editor's `cursor_row` highlighted and scrolled into view when the cursor
+/// moves to another line.
+pub fn render(
+    frame: &mut Frame,
+    rect: &Rect,
+    state: &mut State,
+    program: &color_eyre::Result<Program>,
+    cursor_row: usize,
+) {
+    let (title, help) = match state.panel {
+        Panel::Structogram => ("structogram", "h/j/k/l: pan  e: export svg  p: preview"),
+        _ => ("flowchart", "h/j/k/l: pan  p: preview"),
+    };
+    let color = match state.app_state {
+        AppState::RightSelected => Color::Yellow,
+        _ => Color::White,
+    };
+    let block = Block::new()
+        .style(Style::default().fg(color))
+        .borders(Borders::all())
+        .title_top(title)
+        .title_bottom(help);
+    let program = match program {
+        Ok(program) => program,
+        Err(e) => {
+            let text: Text = e
+                .to_string()
+                .lines()
+                .map(|line| Line::from(line.to_string()).red())
+                .collect::<Vec<Line>>()
+                .into();
+            Paragraph::new(text)
+                .block(block)
+                .render(*rect, frame.buffer_mut());
+            return;
+        }
+    };
+
+    let drawing: Canvas = match state.panel {
+        Panel::Structogram => structogram(program).canvas(),
+        _ => layout::draw(program),
+    };
+    follow(state, &drawing, rect, cursor_row);
+    // The blocks drawn for the cursor's line, found once for the frame.
+    let highlighted: Vec<&Region> = drawing
+        .regions
+        .iter()
+        .filter(|region| region.line == cursor_row)
+        .collect();
+    let (height, width) = viewport(rect);
+    let lines: Vec<Line> = drawing
+        .rows()
+        .iter()
+        .enumerate()
+        .skip(state.diagram_scroll)
+        .take(height)
+        .map(|(y, row)| {
+            // Columns of the row inside a highlighted block.
+            let columns: Vec<_> = highlighted
+                .iter()
+                .filter(|region| (region.y..region.y + region.height).contains(&y))
+                .map(|region| region.x..region.x + region.width)
+                .collect();
+            let lit_at = |x: usize| columns.iter().any(|range| range.contains(&x));
+            let mut spans: Vec<Span> = Vec::new();
+            let mut run = String::new();
+            let mut lit = false;
+            for (x, character) in row.chars().enumerate().skip(state.diagram_pan).take(width) {
+                if lit_at(x) != lit && !run.is_empty() {
+                    spans.push(styled(std::mem::take(&mut run), lit));
+                }
+                lit = lit_at(x);
+                run.push(character);
+            }
+            spans.push(styled(run, lit));
+            Line::from(spans)
+        })
+        .collect();
+    Paragraph::new(Text::from(lines))
+        .block(block)
+        .render(*rect, frame.buffer_mut());
+}
+
+/// Keeps the scroll and pan inside the drawing, after bringing the block for
+/// `cursor_row` into view if the cursor is on a new line.
+fn follow(state: &mut State, drawing: &Canvas, rect: &Rect, cursor_row: usize) {
+    let (height, width) = viewport(rect);
+    if state.diagram_cursor != Some(cursor_row) {
+        state.diagram_cursor = Some(cursor_row);
+        if let Some(region) = drawing
+            .regions
+            .iter()
+            .find(|region| region.line == cursor_row)
+        {
+            state.diagram_scroll = into_view(state.diagram_scroll, height, region.y, region.height);
+            state.diagram_pan = into_view(state.diagram_pan, width, region.x, region.width);
+        }
+    }
+    state.diagram_scroll = state
+        .diagram_scroll
+        .min(drawing.height.saturating_sub(height));
+    state.diagram_pan = state.diagram_pan.min(drawing.width.saturating_sub(width));
+}
+
+/// The rows and columns of the drawing that fit inside the borders.
+fn viewport(rect: &Rect) -> (usize, usize) {
+    (
+        rect.height.saturating_sub(2) as usize,
+        rect.width.saturating_sub(2) as usize,
+    )
+}
+
+/// The offset of a `view` cells long that shows `start..start + length`, or
+/// as much of its beginning as fits, moving from `offset` as little as
+/// possible.
+fn into_view(offset: usize, view: usize, start: usize, length: usize) -> usize {
+    let end = start + length.min(view);
+    if start < offset {
+        start
+    } else if end > offset + view {
+        end - view
+    } else {
+        offset
+    }
+}
+
+fn styled(text: String, highlighted: bool) -> Span<'static> {
+    if highlighted {
+        Span::from(text).yellow().bold()
+    } else {
+        Span::from(text).cyan()
+    }
+}
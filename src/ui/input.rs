@@ -2,26 +2,119 @@ use crate::models::state::{AppState, State};
 use edtui::{EditorState, EditorTheme, EditorView};
 use ratatui::{
     Frame,
-    layout::Rect,
+    layout::{Constraint, Layout, Rect},
     style::{Color, Style},
-    widgets::{Block, Borders, Widget},
+    text::Line,
+    widgets::{Block, Borders, Paragraph, Widget},
 };
 
-pub fn render(frame: &mut Frame, rect: &Rect, state: &State, editor_state: &mut EditorState) {
-    let color = match state.app_state {
-        AppState::LeftSelected => Color::Yellow,
-        _ => Color::White,
+/// A marker column, a coverage column and a space before the text.
+const GUTTER_WIDTH: u16 = 3;
+const BREAKPOINT: &str = "●";
+const CURRENT_LINE: &str = "▶";
+const TEST_PASSED: &str = "✔";
+const TEST_FAILED: &str = "✘";
+const COVERAGE: &str = "┃";
+/// Colors of the coldest and hottest lines that ran; lines that never ran
+/// are red.
+const COLD: (u8, u8, u8) = (40, 90, 160);
+const HOT: (u8, u8, u8) = (255, 200, 0);
+
+pub fn render(frame: &mut Frame, rect: &Rect, state: &mut State, editor_state: &mut EditorState) {
+    let (color, title) = match state.app_state {
+        AppState::LeftSelected => (Color::Yellow, "edit"),
+        AppState::Debugging => (Color::Magenta, "debug"),
+        _ => (Color::White, "edit"),
     };
-    let block = Block::new()
+    let mut block = Block::new()
         .style(Style::default().fg(color))
         .borders(Borders::all())
-        .title_top("edit");
+        .title_top(title);
+    let coverage = state.coverage.as_ref().filter(|_| state.show_coverage);
+    if let Some(coverage) = coverage {
+        block = block.title_top(
+            Line::from(format!(
+                "covered {}/{} (F7: hide, F8: lcov)",
+                coverage.covered(),
+                coverage.hits.len()
+            ))
+            .right_aligned(),
+        );
+    }
+    let inner = block.inner(*rect);
+    block.render(*rect, frame.buffer_mut());
+
+    let [gutter, editor] =
+        Layout::horizontal([Constraint::Length(GUTTER_WIDTH), Constraint::Min(0)]).areas(inner);
+    let [markers_area, coverage_area, _] =
+        Layout::horizontal([Constraint::Length(1); 3]).areas(gutter);
 
-    let theme = EditorTheme::default()
-        .block(block)
-        .base(Style::default().bg(Color::Reset).fg(Color::White));
+    // The editor keeps its scroll offset private, so follow the cursor the
+    // same way it does. The last row is its status line.
+    let height = editor.height.saturating_sub(1) as usize;
+    let row = editor_state.cursor.row;
+    let last_visible = height.saturating_sub(1) + state.editor_scroll;
+    if row < state.editor_scroll {
+        state.editor_scroll = row;
+    }
+    if row >= last_visible {
+        state.editor_scroll += row - last_visible;
+    }
+
+    let theme = EditorTheme::default().base(Style::default().bg(Color::Reset).fg(Color::White));
     EditorView::new(editor_state)
         .theme(theme)
-        .wrap(true)
-        .render(*rect, frame.buffer_mut());
+        .wrap(false)
+        .render(editor, frame.buffer_mut());
+
+    let current = state.debugger.as_ref().and_then(|session| session.line());
+    let markers: Vec<Line> = (state.editor_scroll..state.editor_scroll + height)
+        .map(|line| {
+            if current == Some(line) {
+                Line::styled(CURRENT_LINE, Style::default().fg(Color::Yellow))
+            } else if state.breakpoints.contains(&line) {
+                Line::styled(BREAKPOINT, Style::default().fg(Color::Red))
+            } else if let Some(result) = state.test_results.iter().find(|r| r.line == line) {
+                if result.passed() {
+                    Line::styled(TEST_PASSED, Style::default().fg(Color::Green))
+                } else {
+                    Line::styled(TEST_FAILED, Style::default().fg(Color::Red))
+                }
+            } else {
+                Line::default()
+            }
+        })
+        .collect();
+    Paragraph::new(markers).render(markers_area, frame.buffer_mut());
+
+    if let Some(coverage) = coverage {
+        let heat: Vec<Line> = (state.editor_scroll..state.editor_scroll + height)
+            .map(|line| match coverage.heat(line) {
+                Some(heat) if heat > 0.0 => {
+                    Line::styled(COVERAGE, Style::default().fg(blend(heat)))
+                }
+                Some(_) => Line::styled(COVERAGE, Style::default().fg(Color::Red)),
+                None => Line::default(),
+            })
+            .collect();
+        Paragraph::new(heat).render(coverage_area, frame.buffer_mut());
+    }
+
+    if let Some(line) = current
+        && let Some(offset) = line.checked_sub(state.editor_scroll)
+        && offset < height
+    {
+        let mut highlight = editor;
+        highlight.y += offset as u16;
+        highlight.height = 1;
+        frame
+            .buffer_mut()
+            .set_style(highlight, Style::default().bg(Color::DarkGray));
+    }
+}
+
+/// The color `heat` of the way from cold to hot.
+fn blend(heat: f64) -> Color {
+    let mix = |cold: u8, hot: u8| (cold as f64 + (hot as f64 - cold as f64) * heat).round() as u8;
+    Color::Rgb(mix(COLD.0, HOT.0), mix(COLD.1, HOT.1), mix(COLD.2, HOT.2))
 }
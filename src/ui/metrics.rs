@@ -0,0 +1,46 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    style::{Color, Style, Stylize},
+    widgets::{Block, Borders, Row, Table, Widget},
+};
+
+use crate::models::{analysis::metrics, nodes::program::Program};
+
+pub const WIDTH: u16 = 44;
+
+pub fn render(frame: &mut Frame, rect: &Rect, program: &color_eyre::Result<Program>) {
+    let block = Block::new()
+        .style(Style::default().fg(Color::White))
+        .borders(Borders::all())
+        .title_top("metrics");
+    let rows: Vec<Row> = match program {
+        Ok(program) => metrics::measure(program)
+            .into_iter()
+            .map(|metrics| {
+                Row::new(vec![
+                    metrics.name,
+                    metrics.cyclomatic.to_string(),
+                    metrics.max_nesting.to_string(),
+                    metrics.statements.to_string(),
+                    metrics.variables.to_string(),
+                ])
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    let header = Row::new(vec!["routine", "cc", "nest", "stmts", "vars"]).bold();
+    Table::new(
+        rows,
+        [
+            Constraint::Min(10),
+            Constraint::Length(4),
+            Constraint::Length(4),
+            Constraint::Length(5),
+            Constraint::Length(4),
+        ],
+    )
+    .header(header)
+    .block(block)
+    .render(*rect, frame.buffer_mut());
+}
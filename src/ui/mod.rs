@@ -4,9 +4,17 @@ use ratatui::Frame;
 use crate::models::state::State;
 
 mod app;
+mod array;
+mod code;
+mod complexity;
+mod debugger;
+mod diagnostics;
+mod diagram;
 mod input;
+mod metrics;
 mod output;
+mod trace;
 
-pub fn draw(frame: &mut Frame, state: &State, editor_state: &mut EditorState) {
+pub fn draw(frame: &mut Frame, state: &mut State, editor_state: &mut EditorState) {
     app::draw_main(frame, frame.area(), state, editor_state);
 }
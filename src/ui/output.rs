@@ -1,4 +1,3 @@
-use edtui::EditorState;
 use ratatui::{
     Frame,
     layout::Rect,
@@ -7,20 +6,30 @@ use ratatui::{
     widgets::{Block, Borders, Paragraph, Widget},
 };
 
-use crate::models::state::{AppState, State};
+use crate::models::{
+    nodes::program::Program,
+    state::{AppState, State},
+};
 
-pub fn render(frame: &mut Frame, rect: &Rect, state: &State, editor_state: &EditorState) {
+pub fn render(
+    frame: &mut Frame,
+    rect: &Rect,
+    state: &State,
+    program: &color_eyre::Result<Program>,
+) {
     let color = match state.app_state {
         AppState::RightSelected => Color::Yellow,
         _ => Color::White,
     };
-    let block = Block::new()
+    let mut block = Block::new()
         .style(Style::default().fg(color))
         .borders(Borders::all())
-        .title_top("preview");
-    let text: String = editor_state.lines.clone().into();
-    let text: String = match State::get_output(text.as_str()) {
-        Ok(node) => node.to_string(),
+        .title_top("preview (t: types, m: metrics, r: trace, o: complexity, c: code, f: flowchart, s: structogram)");
+    if let Some(status) = &state.status {
+        block = block.title_top(Line::from(status.as_str()).right_aligned());
+    }
+    let text: String = match program {
+        Ok(program) => program.to_string(),
         Err(e) => e.to_string(),
     };
     let styled_text: Text = text
@@ -0,0 +1,68 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Borders, Paragraph, Row, Table, Widget},
+};
+
+use crate::models::state::{AppState, State};
+
+const HELP: &str = "r: rerun  e: export  j/k: scroll  p: preview";
+
+pub fn render(frame: &mut Frame, rect: &Rect, state: &State) {
+    let color = match state.app_state {
+        AppState::RightSelected => Color::Yellow,
+        _ => Color::White,
+    };
+    let mut block = Block::new()
+        .style(Style::default().fg(color))
+        .borders(Borders::all())
+        .title_top("trace")
+        .title_bottom(HELP);
+    if let Some(status) = &state.status {
+        block = block.title_top(Line::from(status.as_str()).right_aligned());
+    }
+
+    let Some(trace) = &state.trace else {
+        Paragraph::new("press r to run the program")
+            .block(block)
+            .render(*rect, frame.buffer_mut());
+        return;
+    };
+
+    let header = trace.header();
+    let cells = trace.cells();
+    let widths: Vec<Constraint> = header
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let widest = cells
+                .iter()
+                .filter_map(|cells| cells.get(i))
+                .flat_map(|cell| cell.lines())
+                .map(|line| line.chars().count())
+                .chain([name.chars().count()])
+                .max()
+                .unwrap_or_default();
+            Constraint::Length(widest as u16)
+        })
+        .collect();
+
+    let mut rows: Vec<Row> = cells
+        .into_iter()
+        .skip(state.trace_scroll)
+        .map(|cells| Row::new(cells.into_iter().map(|cell| cell.replace('\n', " / "))))
+        .collect();
+    if let Some(truncation) = trace.truncation() {
+        rows.push(Row::new(vec![truncation]).style(Style::default().fg(Color::Yellow)));
+    }
+    if let Some(error) = &trace.error {
+        rows.push(Row::new(vec![error.clone()]).style(Style::default().fg(Color::Red)));
+    }
+
+    Table::new(rows, widths)
+        .header(Row::new(header).bold())
+        .block(block)
+        .render(*rect, frame.buffer_mut());
+}